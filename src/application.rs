@@ -0,0 +1,268 @@
+mod client;
+mod codec;
+mod handlers;
+pub use client::*;
+pub use handlers::*;
+
+use codec::{Reader, Writer};
+use thiserror::Error;
+use wg_2024::network::NodeId;
+
+/// Version written in the first byte of every application message.
+pub const PROTOCOL_VERSION: u8 = 1;
+
+const REQUEST: u8 = 0;
+const RESPONSE: u8 = 1;
+
+#[derive(Debug, Clone, PartialEq, Error)]
+pub enum DecodeError {
+    #[error("Protocol version {0} is not supported.")]
+    UnsupportedVersion(u8),
+    #[error("Unknown message type {0}.")]
+    UnknownTag(u8),
+    #[error("The message is truncated.")]
+    Truncated,
+    #[error("A string of the message is not valid UTF-8.")]
+    InvalidUtf8,
+    #[error("{0} bytes left after the end of the message.")]
+    TrailingBytes(usize),
+    #[error("Expected a {0}, got a message in the other direction.")]
+    WrongDirection(&'static str),
+}
+
+/// Error answered by a server instead of a response.
+#[derive(Debug, Clone, PartialEq, Eq, Error)]
+pub enum ErrorResponse {
+    #[error("Protocol version {0} is not supported.")]
+    UnsupportedVersion(u8),
+    #[error("Unknown message type {0}.")]
+    UnknownMessage(u8),
+    #[error("The request is malformed.")]
+    Malformed,
+    #[error("The server does not support this request.")]
+    UnsupportedRequest,
+    #[error("File {0} not found.")]
+    FileNotFound(String),
+    #[error("The client is not registered to the chat.")]
+    NotRegistered,
+    #[error("Client {0} is not registered to the chat.")]
+    UnknownClient(NodeId),
+}
+
+impl From<DecodeError> for ErrorResponse {
+    fn from(error: DecodeError) -> Self {
+        match error {
+            DecodeError::UnsupportedVersion(version) => ErrorResponse::UnsupportedVersion(version),
+            DecodeError::UnknownTag(tag) => ErrorResponse::UnknownMessage(tag),
+            _ => ErrorResponse::Malformed,
+        }
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ServerKind {
+    Text,
+    Media,
+    Chat,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum Request {
+    ServerType,
+    ListFiles,
+    GetFile(String),
+    Register(String), //Name shown to the other clients
+    ListClients,
+    SendMessage { to: NodeId, message: String },
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum Response {
+    ServerType(ServerKind),
+    FileList(Vec<String>),
+    File { name: String, content: Vec<u8> },
+    Registered,
+    ClientList(Vec<(NodeId, String)>),
+    MessageSent,
+    MessageFrom { from: NodeId, message: String }, //Pushed by a chat server without a request
+    Error(ErrorResponse),
+}
+
+// Reads the version and direction of a message, then its tag
+fn header(reader: &mut Reader, direction: u8) -> Result<u8, DecodeError> {
+    let version = reader.u8()?;
+    if version != PROTOCOL_VERSION {
+        return Err(DecodeError::UnsupportedVersion(version));
+    }
+    if reader.u8()? != direction {
+        return Err(DecodeError::WrongDirection(match direction {
+            REQUEST => "request",
+            _ => "response",
+        }));
+    }
+    reader.u8()
+}
+
+impl Request {
+    pub fn encode(&self) -> Vec<u8> {
+        let mut writer = Writer::new();
+        writer.u8(PROTOCOL_VERSION).u8(REQUEST);
+        match self {
+            Request::ServerType => writer.u8(1),
+            Request::ListFiles => writer.u8(2),
+            Request::GetFile(name) => writer.u8(3).string(name),
+            Request::Register(name) => writer.u8(4).string(name),
+            Request::ListClients => writer.u8(5),
+            Request::SendMessage { to, message } => writer.u8(6).node_id(*to).string(message),
+        };
+        writer.finish()
+    }
+
+    /// Whether `response` can be the answer to this request, so that a late response to an
+    /// earlier request is not taken for it.
+    pub fn accepts(&self, response: &Response) -> bool {
+        match (self, response) {
+            (Request::ServerType, Response::ServerType(_))
+            | (Request::ListFiles, Response::FileList(_))
+            | (Request::Register(_), Response::Registered)
+            | (Request::ListClients, Response::ClientList(_))
+            | (Request::SendMessage { .. }, Response::MessageSent) => true,
+            (Request::GetFile(requested), Response::File { name, .. })
+            | (
+                Request::GetFile(requested),
+                Response::Error(ErrorResponse::FileNotFound(name)),
+            ) => requested == name,
+            (
+                Request::ListClients | Request::SendMessage { .. },
+                Response::Error(ErrorResponse::NotRegistered),
+            ) => true,
+            (
+                Request::SendMessage { to, .. },
+                Response::Error(ErrorResponse::UnknownClient(id)),
+            ) => to == id,
+            //Errors about the request itself can answer any request
+            (
+                _,
+                Response::Error(
+                    ErrorResponse::UnsupportedVersion(_)
+                    | ErrorResponse::UnknownMessage(_)
+                    | ErrorResponse::Malformed
+                    | ErrorResponse::UnsupportedRequest,
+                ),
+            ) => true,
+            _ => false,
+        }
+    }
+
+    pub fn decode(bytes: &[u8]) -> Result<Self, DecodeError> {
+        let mut reader = Reader::new(bytes);
+        let request = match header(&mut reader, REQUEST)? {
+            1 => Request::ServerType,
+            2 => Request::ListFiles,
+            3 => Request::GetFile(reader.string()?),
+            4 => Request::Register(reader.string()?),
+            5 => Request::ListClients,
+            6 => Request::SendMessage {
+                to: reader.node_id()?,
+                message: reader.string()?,
+            },
+            tag => return Err(DecodeError::UnknownTag(tag)),
+        };
+        reader.finish()?;
+        Ok(request)
+    }
+}
+
+impl Response {
+    pub fn encode(&self) -> Vec<u8> {
+        let mut writer = Writer::new();
+        writer.u8(PROTOCOL_VERSION).u8(RESPONSE);
+        match self {
+            Response::ServerType(kind) => writer.u8(1).u8(match kind {
+                ServerKind::Text => 0,
+                ServerKind::Media => 1,
+                ServerKind::Chat => 2,
+            }),
+            Response::FileList(names) => {
+                writer.u8(2).u32(names.len() as u32);
+                for name in names {
+                    writer.string(name);
+                }
+                &mut writer
+            }
+            Response::File { name, content } => writer.u8(3).string(name).bytes(content),
+            Response::Registered => writer.u8(4),
+            Response::ClientList(clients) => {
+                writer.u8(5).u32(clients.len() as u32);
+                for (id, name) in clients {
+                    writer.node_id(*id).string(name);
+                }
+                &mut writer
+            }
+            Response::MessageSent => writer.u8(6),
+            Response::MessageFrom { from, message } => writer.u8(7).node_id(*from).string(message),
+            Response::Error(error) => {
+                writer.u8(8);
+                match error {
+                    ErrorResponse::UnsupportedVersion(version) => writer.u8(1).u8(*version),
+                    ErrorResponse::UnknownMessage(tag) => writer.u8(2).u8(*tag),
+                    ErrorResponse::Malformed => writer.u8(3),
+                    ErrorResponse::UnsupportedRequest => writer.u8(4),
+                    ErrorResponse::FileNotFound(name) => writer.u8(5).string(name),
+                    ErrorResponse::NotRegistered => writer.u8(6),
+                    ErrorResponse::UnknownClient(id) => writer.u8(7).node_id(*id),
+                }
+            }
+        };
+        writer.finish()
+    }
+
+    pub fn decode(bytes: &[u8]) -> Result<Self, DecodeError> {
+        let mut reader = Reader::new(bytes);
+        let response = match header(&mut reader, RESPONSE)? {
+            1 => Response::ServerType(match reader.u8()? {
+                0 => ServerKind::Text,
+                1 => ServerKind::Media,
+                2 => ServerKind::Chat,
+                kind => return Err(DecodeError::UnknownTag(kind)),
+            }),
+            2 => {
+                let count = reader.count(4)?;
+                let names = (0..count)
+                    .map(|_| reader.string())
+                    .collect::<Result<_, _>>()?;
+                Response::FileList(names)
+            }
+            3 => Response::File {
+                name: reader.string()?,
+                content: reader.bytes()?,
+            },
+            4 => Response::Registered,
+            5 => {
+                let count = reader.count(5)?;
+                let clients = (0..count)
+                    .map(|_| Ok((reader.node_id()?, reader.string()?)))
+                    .collect::<Result<_, DecodeError>>()?;
+                Response::ClientList(clients)
+            }
+            6 => Response::MessageSent,
+            7 => Response::MessageFrom {
+                from: reader.node_id()?,
+                message: reader.string()?,
+            },
+            8 => Response::Error(match reader.u8()? {
+                1 => ErrorResponse::UnsupportedVersion(reader.u8()?),
+                2 => ErrorResponse::UnknownMessage(reader.u8()?),
+                3 => ErrorResponse::Malformed,
+                4 => ErrorResponse::UnsupportedRequest,
+                5 => ErrorResponse::FileNotFound(reader.string()?),
+                6 => ErrorResponse::NotRegistered,
+                7 => ErrorResponse::UnknownClient(reader.node_id()?),
+                code => return Err(DecodeError::UnknownTag(code)),
+            }),
+            tag => return Err(DecodeError::UnknownTag(tag)),
+        };
+        reader.finish()?;
+        Ok(response)
+    }
+}
@@ -0,0 +1,169 @@
+use super::{DecodeError, ErrorResponse, Request, Response, ServerKind};
+use crate::{Client, ClientError};
+use std::collections::VecDeque;
+use std::time::{Duration, Instant};
+use thiserror::Error;
+use wg_2024::network::NodeId;
+
+const DEFAULT_RESPONSE_TIMEOUT: Duration = Duration::from_secs(5);
+
+#[derive(Debug, Clone, PartialEq, Error)]
+pub enum ApplicationError {
+    #[error("{0}")]
+    Client(#[from] ClientError),
+    #[error("The response cannot be decoded: {0}")]
+    Decode(#[from] DecodeError),
+    #[error("The server answered with an error: {0}")]
+    Server(ErrorResponse),
+    #[error("Unexpected response {0:?}.")]
+    UnexpectedResponse(Response),
+}
+
+/// Typed requests to text, media and chat servers, on top of a `Client`.
+///
+/// Chat messages pushed by a server while waiting for a response are kept for
+/// `recv_chat_message`.
+pub struct ApplicationClient {
+    client: Client,
+    timeout: Duration,                         //Time to wait for each response
+    inbox: VecDeque<(NodeId, NodeId, String)>, //Server, sender and text of the chat messages
+}
+
+impl ApplicationClient {
+    pub fn new(client: Client) -> Self {
+        Self {
+            client,
+            timeout: DEFAULT_RESPONSE_TIMEOUT,
+            inbox: VecDeque::new(),
+        }
+    }
+
+    #[must_use]
+    pub fn with_timeout(mut self, timeout: Duration) -> Self {
+        self.timeout = timeout;
+        self
+    }
+
+    pub fn client(&self) -> &Client {
+        &self.client
+    }
+
+    pub fn client_mut(&mut self) -> &mut Client {
+        &mut self.client
+    }
+
+    pub fn into_inner(self) -> Client {
+        self.client
+    }
+
+    pub fn server_type(&mut self, server: NodeId) -> Result<ServerKind, ApplicationError> {
+        match self.request(server, &Request::ServerType)? {
+            Response::ServerType(kind) => Ok(kind),
+            response => Err(ApplicationError::UnexpectedResponse(response)),
+        }
+    }
+
+    pub fn list_files(&mut self, server: NodeId) -> Result<Vec<String>, ApplicationError> {
+        match self.request(server, &Request::ListFiles)? {
+            Response::FileList(names) => Ok(names),
+            response => Err(ApplicationError::UnexpectedResponse(response)),
+        }
+    }
+
+    pub fn get_file(&mut self, server: NodeId, name: &str) -> Result<Vec<u8>, ApplicationError> {
+        match self.request(server, &Request::GetFile(name.to_string()))? {
+            Response::File { content, .. } => Ok(content),
+            response => Err(ApplicationError::UnexpectedResponse(response)),
+        }
+    }
+
+    pub fn register(&mut self, server: NodeId, name: &str) -> Result<(), ApplicationError> {
+        match self.request(server, &Request::Register(name.to_string()))? {
+            Response::Registered => Ok(()),
+            response => Err(ApplicationError::UnexpectedResponse(response)),
+        }
+    }
+
+    pub fn list_clients(
+        &mut self,
+        server: NodeId,
+    ) -> Result<Vec<(NodeId, String)>, ApplicationError> {
+        match self.request(server, &Request::ListClients)? {
+            Response::ClientList(clients) => Ok(clients),
+            response => Err(ApplicationError::UnexpectedResponse(response)),
+        }
+    }
+
+    /// Sends `message` to client `to` through chat server `server`.
+    pub fn send_chat_message(
+        &mut self,
+        server: NodeId,
+        to: NodeId,
+        message: &str,
+    ) -> Result<(), ApplicationError> {
+        let request = Request::SendMessage {
+            to,
+            message: message.to_string(),
+        };
+        match self.request(server, &request)? {
+            Response::MessageSent => Ok(()),
+            response => Err(ApplicationError::UnexpectedResponse(response)),
+        }
+    }
+
+    /// Next chat message as (server, sender, text), waiting at most `timeout` for it.
+    pub fn recv_chat_message(&mut self, timeout: Duration) -> Option<(NodeId, NodeId, String)> {
+        if let Some(message) = self.inbox.pop_front() {
+            return Some(message);
+        }
+        let deadline = Instant::now() + timeout;
+        loop {
+            let (server, message) = self
+                .client
+                .recv_message(deadline.saturating_duration_since(Instant::now()))?;
+            match Response::decode(&message) {
+                Ok(Response::MessageFrom { from, message }) => {
+                    return Some((server, from, message))
+                }
+                other => log::warn!(
+                    "Client {} dropped a message of node {}: {:?}",
+                    self.client.id(),
+                    server,
+                    other
+                ),
+            }
+        }
+    }
+
+    /// Sends a request and waits for its response, turning error responses into `Server` errors.
+    ///
+    /// Responses that cannot answer `request`, such as the late response to a request that
+    /// timed out, are dropped.
+    pub fn request(
+        &mut self,
+        server: NodeId,
+        request: &Request,
+    ) -> Result<Response, ApplicationError> {
+        self.client.send_message(server, &request.encode())?;
+        let deadline = Instant::now() + self.timeout;
+        loop {
+            let message = self
+                .client
+                .recv_message_from(server, deadline.saturating_duration_since(Instant::now()))
+                .ok_or(ClientError::NoResponse(server))?;
+            match Response::decode(&message)? {
+                Response::MessageFrom { from, message } => {
+                    self.inbox.push_back((server, from, message))
+                }
+                response if !request.accepts(&response) => log::warn!(
+                    "Client {} dropped a late response of node {}: {:?}",
+                    self.client.id(),
+                    server,
+                    response
+                ),
+                Response::Error(error) => return Err(ApplicationError::Server(error)),
+                response => return Ok(response),
+            }
+        }
+    }
+}
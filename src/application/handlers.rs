@@ -0,0 +1,127 @@
+use super::{ErrorResponse, Request, Response, ServerKind};
+use crate::RequestHandler;
+use std::collections::BTreeMap;
+use wg_2024::network::NodeId;
+
+// Decodes a request, answering the ones that cannot be decoded with an explicit error
+fn decode_request(request: &[u8]) -> Result<Request, Response> {
+    Request::decode(request).map_err(|error| {
+        log::warn!("Received a request that cannot be decoded: {}", error);
+        Response::Error(error.into())
+    })
+}
+
+/// Text or media server: lists its files and sends them on request.
+#[derive(Debug, Clone)]
+pub struct ContentServer {
+    kind: ServerKind,
+    files: BTreeMap<String, Vec<u8>>,
+}
+
+impl ContentServer {
+    pub fn text() -> Self {
+        Self {
+            kind: ServerKind::Text,
+            files: BTreeMap::new(),
+        }
+    }
+
+    pub fn media() -> Self {
+        Self {
+            kind: ServerKind::Media,
+            files: BTreeMap::new(),
+        }
+    }
+
+    #[must_use]
+    pub fn with_file(mut self, name: &str, content: &[u8]) -> Self {
+        self.files.insert(name.to_string(), content.to_vec());
+        self
+    }
+
+    pub fn kind(&self) -> ServerKind {
+        self.kind
+    }
+
+    pub fn respond(&self, request: Request) -> Response {
+        match request {
+            Request::ServerType => Response::ServerType(self.kind),
+            Request::ListFiles => Response::FileList(self.files.keys().cloned().collect()),
+            Request::GetFile(name) => match self.files.get(&name) {
+                Some(content) => Response::File {
+                    content: content.clone(),
+                    name,
+                },
+                None => Response::Error(ErrorResponse::FileNotFound(name)),
+            },
+            _ => Response::Error(ErrorResponse::UnsupportedRequest),
+        }
+    }
+}
+
+impl RequestHandler for ContentServer {
+    fn handle_request(&mut self, _client_id: NodeId, request: &[u8]) -> Option<Vec<u8>> {
+        let response =
+            decode_request(request).map_or_else(|error| error, |request| self.respond(request));
+        Some(response.encode())
+    }
+}
+
+/// Chat server: registered clients can list each other and exchange messages through it.
+#[derive(Debug, Clone, Default)]
+pub struct ChatServer {
+    clients: BTreeMap<NodeId, String>,
+    outbox: Vec<(NodeId, Vec<u8>)>, //Encoded `MessageFrom` for other clients
+}
+
+impl ChatServer {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Registered clients with their names.
+    pub fn clients(&self) -> &BTreeMap<NodeId, String> {
+        &self.clients
+    }
+
+    pub fn respond(&mut self, client_id: NodeId, request: Request) -> Response {
+        match request {
+            Request::ServerType => Response::ServerType(ServerKind::Chat),
+            Request::Register(name) => {
+                self.clients.insert(client_id, name);
+                Response::Registered
+            }
+            Request::ListClients if self.clients.contains_key(&client_id) => Response::ClientList(
+                self.clients
+                    .iter()
+                    .map(|(id, name)| (*id, name.clone()))
+                    .collect(),
+            ),
+            Request::SendMessage { to, message } if self.clients.contains_key(&client_id) => {
+                if !self.clients.contains_key(&to) {
+                    return Response::Error(ErrorResponse::UnknownClient(to));
+                }
+                let from = client_id;
+                self.outbox
+                    .push((to, Response::MessageFrom { from, message }.encode()));
+                Response::MessageSent
+            }
+            Request::ListClients | Request::SendMessage { .. } => {
+                Response::Error(ErrorResponse::NotRegistered)
+            }
+            _ => Response::Error(ErrorResponse::UnsupportedRequest),
+        }
+    }
+}
+
+impl RequestHandler for ChatServer {
+    fn handle_request(&mut self, client_id: NodeId, request: &[u8]) -> Option<Vec<u8>> {
+        let response = decode_request(request)
+            .map_or_else(|error| error, |request| self.respond(client_id, request));
+        Some(response.encode())
+    }
+
+    fn take_messages(&mut self) -> Vec<(NodeId, Vec<u8>)> {
+        std::mem::take(&mut self.outbox)
+    }
+}
@@ -0,0 +1,207 @@
+//! Headless simulation: boots the network of a config file, runs traffic and faults on it for a
+//! fixed duration and prints a summary.
+//!
+//! Exit codes: 0 on success, 1 if the simulation failed at runtime, 2 if the config or the
+//! scenario could not be loaded or is not valid, 3 on wrong arguments.
+
+use dronegowski::{parse_config, ControllerCommand, NetworkGraph, Scenario, SimulationRunner};
+use simplelog::{ColorChoice, LevelFilter, TermLogger, TerminalMode};
+use std::process::ExitCode;
+use std::time::Duration;
+use wg_2024::config::Config;
+use wg_2024::network::NodeId;
+
+const EXIT_RUNTIME_FAILURE: u8 = 1;
+const EXIT_INVALID_CONFIG: u8 = 2;
+const EXIT_USAGE: u8 = 3;
+
+const USAGE: &str = "Usage: dronegowski-sim [CONFIG] [OPTIONS]
+
+  CONFIG                   Network config file, config.toml by default
+  --duration <SECS>        Length of the simulation, 5 or the end of the scenario by default
+  --scenario <FILE>        Runs the steps of a scenario file instead of the built-in traffic
+  --interval <MS>          Time between two messages of a client, 10 by default
+  --seed <SEED>            Master seed of the drops
+  --crash <ID>@<SECS>      Crashes a drone, can be repeated
+  --pdr <ID>=<PDR>@<SECS>  Sets the PDR of a drone, can be repeated
+  --no-faults              Runs without the default crash
+  -v, --verbose            Logs the network activity
+  -h, --help               Prints this message
+
+Without --scenario, --crash, --pdr or --no-faults the first drone that can crash without
+cutting off a client or a server crashes halfway through the simulation.";
+
+struct Options {
+    config: String,
+    scenario: Option<String>,
+    duration: Option<Duration>,
+    interval: Duration,
+    seed: Option<u64>,
+    faults: Vec<(Duration, ControllerCommand)>,
+    no_faults: bool,
+    verbose: bool,
+}
+
+fn main() -> ExitCode {
+    let options = match parse_args(std::env::args().skip(1)) {
+        Ok(Some(options)) => options,
+        Ok(None) => {
+            println!("{USAGE}");
+            return ExitCode::SUCCESS;
+        }
+        Err(error) => {
+            eprintln!("{error}\n\n{USAGE}");
+            return ExitCode::from(EXIT_USAGE);
+        }
+    };
+    if options.verbose {
+        let _ = TermLogger::init(
+            LevelFilter::Info,
+            simplelog::Config::default(),
+            TerminalMode::Stderr,
+            ColorChoice::Auto,
+        );
+    }
+
+    let config = match parse_config(&options.config) {
+        Ok(config) => config,
+        Err(error) => {
+            eprintln!("{}: {error}", options.config);
+            return ExitCode::from(EXIT_INVALID_CONFIG);
+        }
+    };
+
+    let scenario = match &options.scenario {
+        Some(file) => match Scenario::from_file(file) {
+            Ok(scenario) => Some(scenario),
+            Err(error) => {
+                eprintln!("{file}: {error}");
+                return ExitCode::from(EXIT_INVALID_CONFIG);
+            }
+        },
+        None => None,
+    };
+    if let (Some(scenario), Some(file)) = (&scenario, &options.scenario) {
+        if let Err(errors) = scenario.validate(&config) {
+            for error in errors {
+                eprintln!("{file}: {error}");
+            }
+            return ExitCode::from(EXIT_INVALID_CONFIG);
+        }
+    }
+
+    let duration = options.duration.unwrap_or_else(|| {
+        scenario
+            .as_ref()
+            .map_or(Duration::from_secs(5), Scenario::end)
+    });
+    let mut faults = options.faults;
+    if faults.is_empty() && scenario.is_none() && !options.no_faults {
+        if let Some(drone_id) = default_crash(&config) {
+            faults.push((duration / 2, ControllerCommand::Crash(drone_id)));
+        }
+    }
+    let mut runner = SimulationRunner::new(config)
+        .with_duration(duration)
+        .with_message_interval(options.interval);
+    if let Some(scenario) = &scenario {
+        runner = runner.with_scenario(scenario);
+    }
+    if let Some(seed) = options.seed {
+        runner = runner.with_seed(seed);
+    }
+    for (at, command) in faults {
+        runner = runner.with_fault(at, command);
+    }
+
+    match runner.run() {
+        Ok(summary) => {
+            print!("{summary}");
+            ExitCode::SUCCESS
+        }
+        Err(error) if error.is_config_error() => {
+            eprintln!("{}: {error}", options.config);
+            ExitCode::from(EXIT_INVALID_CONFIG)
+        }
+        Err(error) => {
+            eprintln!("Simulation failed: {error}");
+            ExitCode::from(EXIT_RUNTIME_FAILURE)
+        }
+    }
+}
+
+// None if the help was asked for
+fn parse_args(mut args: impl Iterator<Item = String>) -> Result<Option<Options>, String> {
+    let mut options = Options {
+        config: String::from("config.toml"),
+        scenario: None,
+        duration: None,
+        interval: Duration::from_millis(10),
+        seed: None,
+        faults: Vec::new(),
+        no_faults: false,
+        verbose: false,
+    };
+    let mut config = None;
+    while let Some(arg) = args.next() {
+        let mut value = || args.next().ok_or(format!("{arg} needs a value"));
+        match arg.as_str() {
+            "-h" | "--help" => return Ok(None),
+            "-v" | "--verbose" => options.verbose = true,
+            "--no-faults" => options.no_faults = true,
+            "--duration" => options.duration = Some(seconds(&value()?)?),
+            "--scenario" => options.scenario = Some(value()?),
+            "--interval" => options.interval = Duration::from_millis(parse(&value()?)?),
+            "--seed" => options.seed = Some(parse(&value()?)?),
+            "--crash" => {
+                let value = value()?;
+                let (drone_id, at) = split(&value, '@')?;
+                options
+                    .faults
+                    .push((seconds(at)?, ControllerCommand::Crash(parse(drone_id)?)));
+            }
+            "--pdr" => {
+                let value = value()?;
+                let (set, at) = split(&value, '@')?;
+                let (drone_id, pdr) = split(set, '=')?;
+                options.faults.push((
+                    seconds(at)?,
+                    ControllerCommand::SetPdr(parse(drone_id)?, parse(pdr)?),
+                ));
+            }
+            _ if arg.starts_with('-') => return Err(format!("Unknown option {arg}")),
+            _ if config.is_none() => config = Some(arg),
+            _ => return Err(format!("Unexpected argument {arg}")),
+        }
+    }
+    if let Some(config) = config {
+        options.config = config;
+    }
+    Ok(Some(options))
+}
+
+fn parse<T: std::str::FromStr>(value: &str) -> Result<T, String> {
+    value
+        .parse()
+        .map_err(|_| format!("Invalid value {value:?}"))
+}
+
+fn seconds(value: &str) -> Result<Duration, String> {
+    Duration::try_from_secs_f64(parse(value)?).map_err(|_| format!("Invalid time {value:?}"))
+}
+
+fn split(value: &str, separator: char) -> Result<(&str, &str), String> {
+    value
+        .split_once(separator)
+        .ok_or(format!("Expected {separator} in {value:?}"))
+}
+
+// First drone whose crash leaves every client able to reach every server
+fn default_crash(config: &Config) -> Option<NodeId> {
+    let graph = NetworkGraph::from_config(config);
+    config.drone.iter().map(|drone| drone.id).find(|drone_id| {
+        let mut graph = graph.clone();
+        graph.clear_edges(*drone_id);
+        graph.cut_off_nodes().is_empty()
+    })
+}
@@ -0,0 +1,482 @@
+use crate::{
+    ack_for, DeliveryError, DeliveryEvent, FragmentStatus, NetworkGraph, NetworkHandle,
+    Reassembler, ReliableSender,
+};
+use crossbeam_channel::{Receiver, RecvTimeoutError, Sender};
+use std::collections::{HashMap, VecDeque};
+use std::time::{Duration, Instant};
+use thiserror::Error;
+use wg_2024::network::{NodeId, SourceRoutingHeader};
+use wg_2024::packet::{FloodRequest, FloodResponse, NodeType, Packet, PacketType};
+
+const DELIVERY_TICK: Duration = Duration::from_millis(10);
+const REFLOOD_QUIET: Duration = Duration::from_millis(200);
+
+#[derive(Debug, Clone, PartialEq, Error)]
+pub enum ClientError {
+    #[error("Node {0} is not a client of the network.")]
+    NotAClient(NodeId),
+    #[error("The client has no neighbour to send the packet to.")]
+    NoNeighbour,
+    #[error("Node {0} is not a neighbour of the client.")]
+    NotANeighbour(NodeId),
+    #[error("The channel to neighbour {0} is disconnected.")]
+    NeighbourDisconnected(NodeId),
+    #[error("No known route to node {0}.")]
+    NoRoute(NodeId),
+    #[error("The routing header of the packet has no next hop.")]
+    NoNextHop,
+    #[error("The packet channel of the client is disconnected.")]
+    Disconnected,
+    #[error("The message was not delivered: {0}")]
+    Delivery(#[from] DeliveryError),
+    #[error("Node {0} did not answer in time.")]
+    NoResponse(NodeId),
+}
+
+/// Answer of a node that does not forward floods to a `FloodRequest` it received, with itself
+/// added to the path trace. The header is ready to be sent to `hops[1]`, the previous node.
+pub fn flood_response(
+    flood_request: &FloodRequest,
+    session_id: u64,
+    node_id: NodeId,
+    node_type: NodeType,
+) -> Packet {
+    let mut path_trace = flood_request.path_trace.clone();
+    path_trace.push((node_id, node_type));
+    Packet::new_flood_response(
+        SourceRoutingHeader {
+            hop_index: 1,
+            hops: path_trace.iter().map(|(id, _)| *id).rev().collect(),
+        },
+        session_id,
+        FloodResponse {
+            flood_id: flood_request.flood_id,
+            path_trace,
+        },
+    )
+}
+
+/// Reference client node, on the same channel model as `Dronegowski`.
+///
+/// The client learns the topology from the path traces of the `FloodResponse`s to its own
+/// floods, and builds source routes over what it learned. It answers the floods of the other
+/// nodes, so they can learn about it too. Messages sent with `send_message` are delivered by
+/// a `ReliableSender`, and the fragments received are acknowledged and reassembled.
+pub struct Client {
+    id: NodeId,
+    packet_recv: Receiver<Packet>,
+    packet_send: HashMap<NodeId, Sender<Packet>>,
+    topology: NetworkGraph,                //Nodes and links learned from the path traces
+    flood_count: u64,                      //Floods sent so far, part of the next flood id
+    pending: VecDeque<Packet>,             //Packets received meanwhile, not yet returned
+    messages: VecDeque<(NodeId, Vec<u8>)>, //Complete messages with their source
+    delivery: ReliableSender,
+    reassembler: Reassembler,
+}
+
+impl Client {
+    pub fn new(
+        id: NodeId,
+        packet_recv: Receiver<Packet>,
+        packet_send: HashMap<NodeId, Sender<Packet>>,
+    ) -> Self {
+        let mut client = Self {
+            id,
+            packet_recv,
+            packet_send,
+            topology: NetworkGraph::new(),
+            flood_count: 0,
+            pending: VecDeque::new(),
+            delivery: ReliableSender::new(id),
+            reassembler: Reassembler::new(),
+            messages: VecDeque::new(),
+        };
+        client.reset_topology();
+        client
+    }
+
+    /// Client `client_id` of a running network, linked to the drones of its config entry.
+    pub fn from_network(network: &NetworkHandle, client_id: NodeId) -> Result<Self, ClientError> {
+        let client = network
+            .config()
+            .client
+            .iter()
+            .find(|client| client.id == client_id)
+            .ok_or(ClientError::NotAClient(client_id))?;
+        let packet_recv = network
+            .packet_recv()
+            .get(&client_id)
+            .ok_or(ClientError::NotAClient(client_id))?
+            .clone();
+        let packet_send = client
+            .connected_drone_ids
+            .iter()
+            .filter_map(|id| Some((*id, network.packet_send().get(id)?.clone())))
+            .collect();
+        Ok(Self::new(client_id, packet_recv, packet_send))
+    }
+
+    /// Replaces the delivery layer, to change its retry and time budgets.
+    #[must_use]
+    pub fn with_delivery(mut self, delivery: ReliableSender) -> Self {
+        self.delivery = delivery;
+        self
+    }
+
+    #[must_use]
+    pub fn with_reassembler(mut self, reassembler: Reassembler) -> Self {
+        self.reassembler = reassembler;
+        self
+    }
+
+    pub fn id(&self) -> NodeId {
+        self.id
+    }
+
+    pub fn delivery(&self) -> &ReliableSender {
+        &self.delivery
+    }
+
+    /// Topology learned so far, the client and its neighbours are always part of it.
+    pub fn topology(&self) -> &NetworkGraph {
+        &self.topology
+    }
+
+    pub fn neighbours(&self) -> Vec<NodeId> {
+        let mut neighbours: Vec<NodeId> = self.packet_send.keys().copied().collect();
+        neighbours.sort_unstable();
+        neighbours
+    }
+
+    /// Servers learned so far.
+    pub fn servers(&self) -> Vec<NodeId> {
+        self.topology.nodes_of_type(NodeType::Server)
+    }
+
+    pub fn add_neighbour(&mut self, node_id: NodeId, sender: Sender<Packet>) {
+        self.packet_send.insert(node_id, sender);
+        self.topology.add_node(node_id, NodeType::Drone);
+        let _ = self.topology.add_edge(self.id, node_id);
+    }
+
+    pub fn remove_neighbour(&mut self, node_id: NodeId) -> bool {
+        self.topology.remove_edge(self.id, node_id);
+        self.packet_send.remove(&node_id).is_some()
+    }
+
+    /// Sends a `FloodRequest` with a new flood id to every neighbour, returning the flood id.
+    pub fn flood(&mut self) -> Result<u64, ClientError> {
+        if self.packet_send.is_empty() {
+            return Err(ClientError::NoNeighbour);
+        }
+        self.flood_count += 1;
+        let packet = flood_request(self.id, NodeType::Client, self.flood_count);
+        let flood_id = packet.session_id;
+        for (id, sender) in &self.packet_send {
+            sender
+                .send(packet.clone())
+                .map_err(|_| ClientError::NeighbourDisconnected(*id))?;
+        }
+        Ok(flood_id)
+    }
+
+    /// Forgets the learned topology and floods the network again, collecting the responses
+    /// until none arrives for `quiet`. Returns the number of responses to the new flood.
+    ///
+    /// The other packets received meanwhile are kept and returned by `recv_timeout`.
+    pub fn discover(&mut self, quiet: Duration) -> Result<usize, ClientError> {
+        self.reset_topology();
+        let flood_id = self.flood()?;
+        let mut responses = 0;
+        let mut deadline = Instant::now() + quiet;
+        loop {
+            let packet = match self
+                .packet_recv
+                .recv_timeout(deadline.saturating_duration_since(Instant::now()))
+            {
+                Ok(packet) => packet,
+                Err(RecvTimeoutError::Timeout) => break,
+                Err(RecvTimeoutError::Disconnected) => return Err(ClientError::Disconnected),
+            };
+            if let PacketType::FloodResponse(ref flood_response) = packet.pack_type {
+                if flood_response.flood_id == flood_id {
+                    responses += 1;
+                    deadline = Instant::now() + quiet;
+                }
+            }
+            if let Some(packet) = self.handle_packet(packet) {
+                self.pending.push_back(packet);
+            }
+        }
+        Ok(responses)
+    }
+
+    /// Handles the flood packets, learning from the path traces and answering the floods of
+    /// the other nodes, the Acks and Nacks of the messages being sent and the fragments of the
+    /// messages received. Late Acks and Nacks of sessions no longer in progress are dropped
+    /// instead of being returned to the caller.
+    pub fn handle_packet(&mut self, packet: Packet) -> Option<Packet> {
+        match packet.pack_type {
+            PacketType::FloodResponse(ref flood_response) => {
+                if flood_response.path_trace.first().map(|(id, _)| *id) == Some(self.id) {
+                    self.learn(&flood_response.path_trace);
+                }
+                None
+            }
+            PacketType::FloodRequest(ref flood_request) => {
+                if flood_request.initiator_id == self.id {
+                    //One of our floods came back through another neighbour
+                    self.learn(&flood_request.path_trace);
+                } else {
+                    let response =
+                        flood_response(flood_request, packet.session_id, self.id, NodeType::Client);
+                    if let Err(error) = self.send(response) {
+                        log::warn!("Client {} could not answer a flood: {}", self.id, error);
+                    }
+                }
+                None
+            }
+            PacketType::MsgFragment(ref fragment) => {
+                if let Some(ack) = ack_for(&packet) {
+                    if let Err(error) = self.send(ack) {
+                        log::warn!("Client {} could not send an Ack: {}", self.id, error);
+                    }
+                }
+                let source = packet.routing_header.hops.first().copied()?;
+                match self.reassembler.push(source, packet.session_id, fragment) {
+                    Ok(FragmentStatus::Complete(message)) => {
+                        self.messages.push_back((source, message))
+                    }
+                    Ok(_) => {}
+                    Err(error) => log::warn!("Client {} refused a fragment: {}", self.id, error),
+                }
+                None
+            }
+            PacketType::Ack(_) | PacketType::Nack(_) => {
+                if !self.delivery.handle_packet(&packet, &mut self.topology) {
+                    log::debug!(
+                        "Client {} ignored a late Ack or Nack of session {}",
+                        self.id,
+                        packet.session_id
+                    );
+                }
+                None
+            }
+        }
+    }
+
+    /// Next packet that is not part of a flood, waiting at most `timeout` for it.
+    pub fn recv_timeout(&mut self, timeout: Duration) -> Option<Packet> {
+        if let Some(packet) = self.pending.pop_front() {
+            return Some(packet);
+        }
+        let deadline = Instant::now() + timeout;
+        loop {
+            let packet = self
+                .packet_recv
+                .recv_timeout(deadline.saturating_duration_since(Instant::now()))
+                .ok()?;
+            if let Some(packet) = self.handle_packet(packet) {
+                return Some(packet);
+            }
+        }
+    }
+
+    /// Next complete message with its source, waiting at most `timeout` for it.
+    pub fn recv_message(&mut self, timeout: Duration) -> Option<(NodeId, Vec<u8>)> {
+        self.next_message(None, timeout)
+    }
+
+    /// Next complete message of `source`, the messages of other sources stay queued.
+    pub fn recv_message_from(&mut self, source: NodeId, timeout: Duration) -> Option<Vec<u8>> {
+        self.next_message(Some(source), timeout)
+            .map(|(_, message)| message)
+    }
+
+    /// Sends a request with `send_message` and waits at most `timeout` for the next message of
+    /// `destination`, which is taken as the response.
+    pub fn request(
+        &mut self,
+        destination: NodeId,
+        payload: &[u8],
+        timeout: Duration,
+    ) -> Result<Vec<u8>, ClientError> {
+        self.send_message(destination, payload)?;
+        self.recv_message_from(destination, timeout)
+            .ok_or(ClientError::NoResponse(destination))
+    }
+
+    /// Source route to `destination` over the learned topology, ready to be sent.
+    pub fn route_to(&self, destination: NodeId) -> Option<SourceRoutingHeader> {
+        if destination == self.id {
+            return None;
+        }
+        Some(SourceRoutingHeader {
+            hop_index: 1,
+            hops: self.topology.shortest_path(self.id, destination)?,
+        })
+    }
+
+    /// Sends a packet to the neighbour at the current hop of its routing header.
+    pub fn send(&self, packet: Packet) -> Result<(), ClientError> {
+        let next_hop = *packet
+            .routing_header
+            .hops
+            .get(packet.routing_header.hop_index)
+            .ok_or(ClientError::NoNextHop)?;
+        self.packet_send
+            .get(&next_hop)
+            .ok_or(ClientError::NotANeighbour(next_hop))?
+            .send(packet)
+            .map_err(|_| ClientError::NeighbourDisconnected(next_hop))
+    }
+
+    /// Sends `pack_type` to `destination` on the route returned by `route_to`.
+    pub fn send_to(
+        &self,
+        destination: NodeId,
+        session_id: u64,
+        pack_type: PacketType,
+    ) -> Result<(), ClientError> {
+        let routing_header = self
+            .route_to(destination)
+            .ok_or(ClientError::NoRoute(destination))?;
+        self.send(Packet {
+            pack_type,
+            routing_header,
+            session_id,
+        })
+    }
+
+    /// Sends a payload to `destination` as fragments and waits until all of them are acknowledged,
+    /// returning the session id of the message.
+    ///
+    /// Lost fragments are sent again and broken routes replaced, flooding the network again when
+    /// no route is left. The other packets received meanwhile are returned by `recv_timeout`.
+    pub fn send_message(
+        &mut self,
+        destination: NodeId,
+        payload: &[u8],
+    ) -> Result<u64, ClientError> {
+        let session_id = self.delivery.send(destination, payload, &self.topology);
+        loop {
+            self.flush_delivery()?;
+            for event in self.delivery.take_events() {
+                match event {
+                    DeliveryEvent::Delivered(id) if id == session_id => return Ok(session_id),
+                    DeliveryEvent::Failed(id, error) if id == session_id => {
+                        return Err(error.into())
+                    }
+                    _ => {}
+                }
+            }
+            match self.packet_recv.recv_timeout(DELIVERY_TICK) {
+                Ok(packet) => {
+                    if let Some(packet) = self.handle_packet(packet) {
+                        self.pending.push_back(packet);
+                    }
+                }
+                Err(RecvTimeoutError::Timeout) => {}
+                Err(RecvTimeoutError::Disconnected) => return Err(ClientError::Disconnected),
+            }
+            self.delivery.check_timeouts();
+        }
+    }
+
+    // Sends what the delivery layer has queued, flooding again when it lost every route
+    fn flush_delivery(&mut self) -> Result<(), ClientError> {
+        loop {
+            for packet in self.delivery.take_outgoing() {
+                if let Err(error) = self.send(packet.clone()) {
+                    if let ClientError::NeighbourDisconnected(neighbour) = error {
+                        self.packet_send.remove(&neighbour);
+                    }
+                    self.delivery.handle_send_error(&packet, &mut self.topology);
+                }
+            }
+            if self.delivery.needs_flood() {
+                self.discover(REFLOOD_QUIET)?;
+                self.delivery.reroute(&self.topology);
+            } else {
+                return Ok(());
+            }
+        }
+    }
+
+    // Messages of other sources received meanwhile stay queued
+    fn next_message(
+        &mut self,
+        source: Option<NodeId>,
+        timeout: Duration,
+    ) -> Option<(NodeId, Vec<u8>)> {
+        let deadline = Instant::now() + timeout;
+        loop {
+            let position = self
+                .messages
+                .iter()
+                .position(|(id, _)| source.is_none_or(|source| source == *id));
+            if let Some(position) = position {
+                return self.messages.remove(position);
+            }
+            for expired in self.reassembler.expire() {
+                log::warn!(
+                    "Client {} dropped session {} of node {}, missing fragments {:?}",
+                    self.id,
+                    expired.session_id,
+                    expired.source,
+                    expired.missing
+                );
+            }
+            let packet = self
+                .packet_recv
+                .recv_timeout(deadline.saturating_duration_since(Instant::now()))
+                .ok()?;
+            if let Some(packet) = self.handle_packet(packet) {
+                self.pending.push_back(packet);
+            }
+        }
+    }
+
+    fn reset_topology(&mut self) {
+        self.topology = NetworkGraph::new();
+        self.topology.add_node(self.id, NodeType::Client);
+        for neighbour in self.packet_send.keys() {
+            self.topology.add_node(*neighbour, NodeType::Drone);
+            let _ = self.topology.add_edge(self.id, *neighbour);
+        }
+    }
+
+    fn learn(&mut self, path_trace: &[(NodeId, NodeType)]) {
+        learn_path_trace(&mut self.topology, path_trace);
+    }
+}
+
+// Request of the `flood_count`-th flood of a node, with the flood id also as session id.
+// The id of the node is in the top byte of the flood id, so flood ids are unique in the whole
+// network and not only per initiator.
+pub(crate) fn flood_request(node_id: NodeId, node_type: NodeType, flood_count: u64) -> Packet {
+    let flood_id = (u64::from(node_id) << 56) | (flood_count & 0x00FF_FFFF_FFFF_FFFF);
+    Packet {
+        pack_type: PacketType::FloodRequest(FloodRequest {
+            flood_id,
+            initiator_id: node_id,
+            path_trace: vec![(node_id, node_type)],
+        }),
+        routing_header: SourceRoutingHeader {
+            hop_index: 0,
+            hops: Vec::new(),
+        },
+        session_id: flood_id,
+    }
+}
+
+// Adds the nodes of a path trace to a topology, each linked to the next one
+pub(crate) fn learn_path_trace(topology: &mut NetworkGraph, path_trace: &[(NodeId, NodeType)]) {
+    for (id, node_type) in path_trace {
+        topology.add_node(*id, *node_type);
+    }
+    for pair in path_trace.windows(2) {
+        let _ = topology.add_edge(pair[0].0, pair[1].0);
+    }
+}
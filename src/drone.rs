@@ -1,13 +1,34 @@
-use crossbeam_channel::{select_biased, Receiver, Sender};
-use rand::Rng;
+use crossbeam_channel::{at, never, select_biased, Receiver, Sender};
+use rand::rngs::StdRng;
 use std::cmp::PartialEq;
-use std::collections::{HashMap, HashSet};
+use std::collections::HashMap;
+use std::time::{Duration, Instant};
 use wg_2024::controller::{DroneCommand, DroneEvent};
 use wg_2024::drone::Drone;
 use wg_2024::network::{NodeId, SourceRoutingHeader};
 use wg_2024::packet::{FloodResponse, Nack, NackType, NodeType, Packet, PacketType};
 
+mod builder;
 mod components_drone;
+mod drop_model;
+mod error;
+mod flood_cache;
+mod shutdown;
+mod snapshot;
+mod stats;
+
+pub use builder::DronegowskiBuilder;
+pub use drop_model::{
+    DropModel, DropModelHandle, GilbertElliottDrop, PerHopDrop, ScheduledDrop, UniformDrop,
+};
+pub use error::DroneError;
+pub use flood_cache::{FloodCache, DEFAULT_FLOOD_CACHE_CAPACITY};
+pub use shutdown::{ShutdownReason, ShutdownReport, StopToken};
+pub use snapshot::{DroneSnapshot, DroneSnapshotHandle};
+pub use stats::{DroneStats, DroneStatsHandle, NackTypeCounters, PacketTypeCounters};
+
+const DEFAULT_LOG_TARGET: &str = module_path!();
+
 #[derive(Clone, Debug, PartialEq)]
 pub enum DroneState {
     Active,
@@ -22,9 +43,17 @@ pub struct Dronegowski {
     sim_controller_recv: Receiver<DroneCommand>, //Channel used to receive commands from the SC
     packet_recv: Receiver<Packet>,           //Channel used to receive packets from nodes
     packet_send: HashMap<NodeId, Sender<Packet>>, //Map containing the sending channels of neighbour nodes
-    pdr: f32,                                     //PDR
+    drop_model: DropModelHandle,                  //Decides which fragments are dropped, holds the PDR
     state: DroneState,                            //Drone state
-    flood_id_vec: HashSet<(u64, NodeId)>,         //HashSet storing ids of already received flood_id
+    flood_cache: FloodCache,                      //Bounded cache of the already received (flood_id, initiator_id)
+    stats: DroneStatsHandle,                      //Counters shared with whoever holds a handle
+    seed: u64,                                    //Seed of the RNG, logged to reproduce a run
+    rng: StdRng,                                  //RNG used by the drop model
+    log_target: String,                           //Target of every log line of the drone
+    snapshot: DroneSnapshotHandle,                //Introspection data shared with other threads
+    stop: StopToken,                              //Local stop, checked before commands and packets
+    crash_timeout: Option<Duration>,              //Maximum time spent in Crashing state
+    crash_deadline: Option<Instant>,              //Set when entering Crashing state if there is a timeout
 }
 
 impl Drone for Dronegowski {
@@ -36,127 +65,243 @@ impl Drone for Dronegowski {
         packet_send: HashMap<NodeId, Sender<Packet>>,
         pdr: f32,
     ) -> Self {
-        assert!(
-            !packet_send.contains_key(&id),
-            "neighbor with id {} which is the same as drone",
-            id
-        );
-        assert!(!(pdr > 1.0 || pdr < 0.0), "pdr out of bounds");
-        format!("Created drone {id} with PDR: {pdr}");
-
-        Self {
+        DronegowskiBuilder::new(
             id,
-            sim_controller_send: controller_send,
-            sim_controller_recv: controller_recv,
+            controller_send,
+            controller_recv,
             packet_recv,
             packet_send,
-            pdr,
-            state: DroneState::Active,
-            flood_id_vec: HashSet::new(),
-        }
+        )
+        .pdr(pdr)
+        .build()
+        .unwrap_or_else(|error| panic!("{error}"))
     }
 
     fn run(&mut self) {
+        self.run_with_report();
+    }
+}
+
+impl Dronegowski {
+    /// Same as `Drone::new`, with a fixed seed: the same seed and input give the same drops.
+    pub fn new_with_seed(
+        id: NodeId,
+        controller_send: Sender<DroneEvent>,
+        controller_recv: Receiver<DroneCommand>,
+        packet_recv: Receiver<Packet>,
+        packet_send: HashMap<NodeId, Sender<Packet>>,
+        pdr: f32,
+        seed: u64,
+    ) -> Self {
+        DronegowskiBuilder::new(
+            id,
+            controller_send,
+            controller_recv,
+            packet_recv,
+            packet_send,
+        )
+        .pdr(pdr)
+        .seed(seed)
+        .build()
+        .unwrap_or_else(|error| panic!("{error}"))
+    }
+
+    /// Runs the drone like `Drone::run` and returns the final report once it terminates.
+    ///
+    /// The loop ends when crashing completes, when the stop token is triggered, or when the
+    /// crash timeout expires; in the last two cases the packets still in the channel are drained.
+    pub fn run_with_report(&mut self) -> ShutdownReport {
         log::info!(
+            target: &self.log_target,
             "Drone {} entering the run loop in state {:?}",
             self.id,
             self.state
         );
-        loop {
+        let stop_recv = self.stop.receiver().clone();
+        let (reason, drained) = loop {
+            if self.stop.is_stopped() {
+                break (ShutdownReason::Stopped, self.drain());
+            }
             match self.state {
                 DroneState::Active => {
                     select_biased! {
+                        recv(stop_recv) -> _ => {},
                         recv(self.sim_controller_recv) -> command_res => {
                             if let Ok(command) = command_res {
-                                self.handle_command(command);
+                                if let Err(error) = self.handle_command(command) {
+                                    self.report_error(&error);
+                                }
+                                self.publish_snapshot();
                             }
                         },
                         recv(self.packet_recv) -> packet_res => {
                             if let Ok(packet) = packet_res {
                                 self.handle_packet(packet);
+                                self.publish_snapshot();
                             }
                         }
 
                     }
                 }
-                DroneState::Crashing => match self.packet_recv.recv() {
-                    Ok(packet) => {
-                        log::info!("Drone {} processing packet in Crashing state", self.id);
-                        self.handle_packet(packet);
-                    }
-                    Err(_) => {
-                        log::info!(
-                            "Drone {} has completed crashing. Transitioning to Crashed state.",
-                            self.id
-                        );
-                        self.state = DroneState::Crashed;
-                        break;
+                DroneState::Crashing => {
+                    let deadline = self.crash_deadline.map_or_else(never, at);
+                    select_biased! {
+                        recv(stop_recv) -> _ => {},
+                        recv(self.packet_recv) -> packet_res => match packet_res {
+                            Ok(packet) => {
+                                log::info!(
+                                    target: &self.log_target,
+                                    "Drone {} processing packet in Crashing state",
+                                    self.id
+                                );
+                                self.handle_packet(packet);
+                                self.publish_snapshot();
+                            }
+                            Err(_) => {
+                                log::info!(
+                                    target: &self.log_target,
+                                    "Drone {} has completed crashing. Transitioning to Crashed state.",
+                                    self.id
+                                );
+                                self.state = DroneState::Crashed;
+                                self.publish_snapshot();
+                                break (ShutdownReason::Crashed, 0);
+                            }
+                        },
+                        recv(deadline) -> _ => {
+                            log::warn!(
+                                target: &self.log_target,
+                                "Drone {} did not complete crashing before the deadline",
+                                self.id
+                            );
+                            break (ShutdownReason::CrashTimeout, self.drain());
+                        }
                     }
-                },
+                }
                 DroneState::Crashed => {
-                    log::info!("Drone {} is in Crashed state. Exiting loop", self.id);
-                    break;
+                    log::info!(
+                        target: &self.log_target,
+                        "Drone {} is in Crashed state. Exiting loop",
+                        self.id
+                    );
+                    break (ShutdownReason::Crashed, 0);
                 }
             }
+        };
+        log::info!(target: &self.log_target, "Drone {} has terminated execution", self.id);
+        ShutdownReport {
+            id: self.id,
+            reason,
+            drained,
+            stats: self.stats.snapshot(),
+            seed: self.seed,
         }
-        log::info!("Drone {} has terminated execution", self.id);
     }
-}
 
-impl Dronegowski {
-    pub fn get_pdr(self) -> f32 {
-        self.pdr
+    /// Token that stops `run()` from another thread.
+    pub fn stop_token(&self) -> StopToken {
+        self.stop.clone()
+    }
+
+    pub fn get_pdr(&self) -> f32 {
+        self.drop_model.pdr()
     }
 
     #[must_use]
-    pub fn get_sim_controller_recv(self) -> Receiver<DroneCommand> {
-        self.sim_controller_recv
+    pub fn get_sim_controller_recv(&self) -> Receiver<DroneCommand> {
+        self.sim_controller_recv.clone()
     }
 
     #[must_use]
-    pub fn get_sim_controller_send(self) -> Sender<DroneEvent> {
-        self.sim_controller_send
+    pub fn get_sim_controller_send(&self) -> Sender<DroneEvent> {
+        self.sim_controller_send.clone()
     }
 
     #[must_use]
-    pub fn get_packet_recv(self) -> Receiver<Packet> {
-        self.packet_recv
+    pub fn get_packet_recv(&self) -> Receiver<Packet> {
+        self.packet_recv.clone()
     }
 
     #[must_use]
-    pub fn get_packet_send(self) -> HashMap<NodeId, Sender<Packet>> {
-        self.packet_send
+    pub fn get_packet_send(&self) -> HashMap<NodeId, Sender<Packet>> {
+        self.packet_send.clone()
     }
 
-    pub fn set_pdr(&mut self, pdr: f32) {
-        if pdr < 0.0 {
-            panic!("pdr {} is out of bounds because is negative", pdr);
-        } else if pdr > 1.0 {
-            panic!("pdr {} is out of bounds because is too big", pdr);
+    pub fn set_pdr(&mut self, pdr: f32) -> Result<(), DroneError> {
+        if !(0.0..=1.0).contains(&pdr) {
+            return Err(DroneError::PdrOutOfBounds(pdr));
+        }
+        if self.drop_model.with_model(|model| model.set_pdr(pdr)) {
+            log::info!(target: &self.log_target, "Drone {}: PDR updated to {}", self.id, pdr);
         } else {
-            self.pdr = pdr;
-            log::info!("Drone {}: PDR updated to {}", self.id, pdr);
+            log::warn!(
+                target: &self.log_target,
+                "Drone {}: the drop model ignores the new PDR {}",
+                self.id,
+                pdr
+            );
         }
+        Ok(())
+    }
+
+    /// Handle to read the drone counters, also while `run()` is executing.
+    pub fn stats_handle(&self) -> DroneStatsHandle {
+        self.stats.clone()
+    }
+
+    /// Handle to switch the drop model, also while `run()` is executing.
+    pub fn drop_model_handle(&self) -> DropModelHandle {
+        self.drop_model.clone()
+    }
+
+    pub fn get_seed(&self) -> u64 {
+        self.seed
     }
 
-    pub fn get_id(self) -> NodeId {
+    pub fn get_id(&self) -> NodeId {
         self.id
     }
 
-    pub fn get_state(self) -> DroneState {
-        self.state
+    pub fn get_state(&self) -> DroneState {
+        self.state.clone()
+    }
+
+    pub fn get_flood_cache_len(&self) -> usize {
+        self.flood_cache.len()
+    }
+
+    pub fn snapshot(&self) -> DroneSnapshot {
+        let mut neighbours: Vec<NodeId> = self.packet_send.keys().copied().collect();
+        neighbours.sort_unstable();
+        DroneSnapshot {
+            id: self.id,
+            pdr: self.drop_model.pdr(),
+            state: self.state.clone(),
+            neighbours,
+            flood_cache_len: self.flood_cache.len(),
+        }
+    }
+
+    /// Handle to read the snapshot from another thread while `run()` is executing.
+    pub fn snapshot_handle(&self) -> DroneSnapshotHandle {
+        self.snapshot.clone()
+    }
+
+    fn publish_snapshot(&self) {
+        self.snapshot.publish(self.snapshot());
     }
 
     fn handle_packet(&mut self, mut packet: Packet) {
+        self.stats.update(|stats| stats.received.record(&packet.pack_type));
         match packet.pack_type {
             PacketType::FloodRequest(ref mut flood_request) => {
                 if self
-                    .flood_id_vec
-                    .insert((flood_request.flood_id, flood_request.initiator_id))
+                    .flood_cache
+                    .insert(flood_request.flood_id, flood_request.initiator_id)
                 {
-                    let previous_id = flood_request.path_trace.last().unwrap().clone();
+                    let previous_id = flood_request.path_trace.last().map(|(id, _)| *id);
                     flood_request.path_trace.push((self.id, NodeType::Drone));
-                    if !self.packet_send.iter().any(|(id, _)| *id != previous_id.0) {
+                    if !self.packet_send.keys().any(|id| Some(*id) != previous_id) {
                         //There is no other neighbour, proceed to send back a flood response
                         let flood_response = Packet::new_flood_response(
                             SourceRoutingHeader {
@@ -174,19 +319,30 @@ impl Dronegowski {
                                 path_trace: flood_request.path_trace.clone(),
                             },
                         );
+                        self.stats.update(|stats| stats.flood_requests_answered += 1);
                         self.forward_packet_safe(&flood_response);
-                        log::info!("Drone {} correctly sent back a Flood Response because has no neighbour",self.id);
+                        log::info!(
+                            target: &self.log_target,
+                            "Drone {} correctly sent back a Flood Response because has no neighbour",
+                            self.id
+                        );
                     } else {
-                        for neighbour in self.packet_send.clone() {
-                            if neighbour.0 != previous_id.0 {
-                                self.forward_packet_flood_request(
-                                    packet.clone(),
-                                    neighbour.clone(),
-                                );
-                                log::info!(
-                                    "Drone {} correctly sent the Flood Request to neighbor with {} id",
-                                    self.id, neighbour.0
-                                );
+                        self.stats.update(|stats| stats.flood_requests_forwarded += 1);
+                        //Sorted, so that the same run sends the flood requests in the same order
+                        let mut neighbours: Vec<(NodeId, Sender<Packet>)> =
+                            self.packet_send.clone().into_iter().collect();
+                        neighbours.sort_unstable_by_key(|(id, _)| *id);
+                        for neighbour in neighbours {
+                            if Some(neighbour.0) != previous_id {
+                                let neighbour_id = neighbour.0;
+                                match self.forward_packet_flood_request(packet.clone(), neighbour) {
+                                    Ok(()) => log::info!(
+                                        target: &self.log_target,
+                                        "Drone {} correctly sent the Flood Request to neighbor with {} id",
+                                        self.id, neighbour_id
+                                    ),
+                                    Err(error) => self.report_error(&error),
+                                }
                             }
                         }
                     }
@@ -209,12 +365,22 @@ impl Dronegowski {
                             path_trace: flood_request.path_trace.clone(),
                         },
                     );
+                    self.stats.update(|stats| stats.flood_requests_answered += 1);
                     self.forward_packet_safe(&flood_response);
-                    log::info!("Drone {} correctly sent back a Flood Response because has already received this flood request",self.id);
+                    log::info!(
+                        target: &self.log_target,
+                        "Drone {} correctly sent back a Flood Response because has already received this flood request",
+                        self.id
+                    );
                 }
             }
             _ => {
-                log::info!("Drone {}: Received packet {:?}", self.id, packet);
+                log::info!(
+                    target: &self.log_target,
+                    "Drone {}: Received packet {:?}",
+                    self.id,
+                    packet
+                );
                 if let Some(node_id) = packet
                     .routing_header
                     .hops
@@ -228,35 +394,54 @@ impl Dronegowski {
                                 self.forward_packet_safe(&packet);
                             }
                             PacketType::MsgFragment(ref _fragment) => {
-                                log::info!("Drone {}: Received fragment {:?}", self.id, _fragment);
+                                log::info!(
+                                    target: &self.log_target,
+                                    "Drone {}: Received fragment {:?}",
+                                    self.id,
+                                    _fragment
+                                );
                                 if self.state == DroneState::Crashing {
-                                    log::warn!("Drone {}: Drone is crashed, sending Nack", self.id);
-                                    self.handle_forwarding_error(
-                                        &packet,
-                                        NackType::ErrorInRouting(
-                                            *packet
-                                                .routing_header
-                                                .hops
-                                                .get(packet.routing_header.hop_index + 1)
-                                                .unwrap(),
-                                        ),
+                                    log::warn!(
+                                        target: &self.log_target,
+                                        "Drone {}: Drone is crashed, sending Nack",
+                                        self.id
+                                    );
+                                    let nack_type = match packet
+                                        .routing_header
+                                        .hops
+                                        .get(packet.routing_header.hop_index + 1)
+                                    {
+                                        Some(next_hop) => NackType::ErrorInRouting(*next_hop),
+                                        None => NackType::DestinationIsDrone,
+                                    };
+                                    self.handle_forwarding_error(&packet, nack_type);
+                                } else if self.should_drop_packet(&packet) {
+                                    log::warn!(
+                                        target: &self.log_target,
+                                        "Drone {}: packet dropped, sending Nack",
+                                        self.id
                                     );
-                                } else if self.should_drop_packet() {
-                                    log::warn!("Drone {}: packet dropped, sending Nack", self.id);
+                                    self.stats.update(|stats| stats.fragments_dropped += 1);
                                     self.handle_forwarding_error(&packet, NackType::Dropped);
-                                    self.sim_controller_send
-                                        .send(DroneEvent::PacketDropped(packet.clone()))
-                                        .expect("Something wrong");
+                                    self.send_event(DroneEvent::PacketDropped(packet.clone()));
                                 } else {
                                     self.forward_packet_safe(&packet);
                                 }
                             }
                             _ => {
-                                log::warn!("Drone {}: Received Unrecognized packet", self.id);
+                                log::warn!(
+                                    target: &self.log_target,
+                                    "Drone {}: Received Unrecognized packet",
+                                    self.id
+                                );
                             }
                         }
                     } else {
-                        log::warn!("Drone {}: Received packet not directed to me", self.id);
+                        log::warn!(
+                            target: &self.log_target,
+                            "Drone {}: Received packet not directed to me",
+                            self.id
+                        );
                         self.handle_forwarding_error(
                             &packet,
                             NackType::UnexpectedRecipient(self.id),
@@ -267,33 +452,47 @@ impl Dronegowski {
         }
     }
 
-    fn handle_command(&mut self, command: DroneCommand) {
+    // Errors returned here are recoverable: the caller logs them and the drone keeps running
+    fn handle_command(&mut self, command: DroneCommand) -> Result<(), DroneError> {
         match command {
-            DroneCommand::SetPacketDropRate(pdr) => {
-                self.set_pdr(pdr);
-            }
+            DroneCommand::SetPacketDropRate(pdr) => self.set_pdr(pdr),
             DroneCommand::Crash => {
-                log::info!("Drone {} entering Crashing state.", self.id);
+                log::info!(target: &self.log_target, "Drone {} entering Crashing state.", self.id);
                 self.set_drone_state(DroneState::Crashing);
+                Ok(())
             }
-            DroneCommand::AddSender(node_id, sender) => {
-                self.add_neighbor(node_id, sender);
-            }
+            DroneCommand::AddSender(node_id, sender) => self.add_neighbor(node_id, sender),
             DroneCommand::RemoveSender(node_id) => {
-                log::info!("Drone {} removing sender {}.", self.id, node_id);
+                log::info!(
+                    target: &self.log_target,
+                    "Drone {} removing sender {}.",
+                    self.id,
+                    node_id
+                );
                 self.remove_neighbor(&node_id)
             }
         }
     }
 
+    fn report_error(&self, error: &DroneError) {
+        log::warn!(target: &self.log_target, "Drone {}: {}", self.id, error);
+    }
+
+    fn send_event(&self, event: DroneEvent) {
+        if self.sim_controller_send.send(event).is_err() {
+            self.report_error(&DroneError::ControllerDisconnected);
+        }
+    }
+
     // Method used to send packet to the next hop
-    fn forward_packet(&self, mut packet: Packet) -> Result<(), Nack> {
+    fn forward_packet(&mut self, mut packet: Packet) -> Result<(), Nack> {
         packet.routing_header.hop_index += 1;
 
         let next_hop = packet
             .routing_header
             .hops
-            .get(packet.routing_header.hop_index);
+            .get(packet.routing_header.hop_index)
+            .copied();
 
         // println!("Sender of Drone {} -> {:?}", self.id, self.packet_send);
 
@@ -304,28 +503,41 @@ impl Dronegowski {
 
         match next_hop {
             Some(next_node) => {
-                match self.packet_send.get(next_node) {
+                match self.packet_send.get(&next_node) {
                     Some(next_node_channel) => match next_node_channel.send(packet.clone()) {
                         Ok(()) => {
                             log::info!(
+                                target: &self.log_target,
                                 "Drone {}: packet forwarded to next hop {}",
                                 self.id,
                                 next_node
                             );
-                            self.sim_controller_send
-                                .send(DroneEvent::PacketSent(packet.clone()));
+                            if let PacketType::MsgFragment(_) = packet.pack_type {
+                                self.stats.update(|stats| stats.fragments_forwarded += 1);
+                            }
+                            self.send_event(DroneEvent::PacketSent(packet));
                             Ok(())
                         }
+                        // The neighbour is gone: prune it and treat it as a routing error
                         Err(..) => {
-                            panic!("Error occurred while forwarding the packet");
+                            self.report_error(&DroneError::NeighbourDisconnected(next_node));
+                            self.packet_send.remove(&next_node);
+                            Err(Nack {
+                                fragment_index,
+                                nack_type: NackType::ErrorInRouting(next_node),
+                            })
                         }
                     },
                     // None if the next hop is not a drone's neighbour
                     None => {
-                        log::warn!("Drone {}: next hop is not a neighbour", self.id);
+                        log::warn!(
+                            target: &self.log_target,
+                            "Drone {}: next hop is not a neighbour",
+                            self.id
+                        );
                         Err(Nack {
                             fragment_index,
-                            nack_type: NackType::ErrorInRouting(*next_node),
+                            nack_type: NackType::ErrorInRouting(next_node),
                         })
                     }
                 }
@@ -338,17 +550,17 @@ impl Dronegowski {
         }
     }
 
-    fn handle_forwarding_error(&self, packet: &Packet, nack_type: NackType) {
+    fn handle_forwarding_error(&mut self, packet: &Packet, nack_type: NackType) {
         //If the packet is ACK / NACK / FloodResponse it's sent to the Simulation Controller, otherwise a NACK is created and sent
         match packet.pack_type {
             PacketType::Ack(_) | PacketType::Nack(_) | PacketType::FloodResponse(_) => {
-                self.sim_controller_send
-                    .send(DroneEvent::ControllerShortcut(packet.clone()))
-                    .expect("Something wrong");
+                self.stats.update(|stats| stats.controller_shortcuts += 1);
+                self.send_event(DroneEvent::ControllerShortcut(packet.clone()));
             }
 
             //Error in send a MsgFragment, send back a NACK
             _ => {
+                self.stats.update(|stats| stats.nacks_generated.record(&nack_type));
                 let nack_packet = self.packet_nack(
                     packet,
                     Nack {
@@ -361,42 +573,77 @@ impl Dronegowski {
         }
     }
 
-    fn forward_packet_safe(&self, packet: &Packet) {
+    fn forward_packet_safe(&mut self, packet: &Packet) {
         if let Err(nack) = self.forward_packet(packet.clone()) {
             self.handle_forwarding_error(packet, nack.nack_type);
         }
     }
 
-    fn forward_packet_flood_request(&self, packet: Packet, neighbour: (NodeId, Sender<Packet>)) {
+    fn forward_packet_flood_request(
+        &mut self,
+        packet: Packet,
+        neighbour: (NodeId, Sender<Packet>),
+    ) -> Result<(), DroneError> {
         match neighbour.1.send(packet.clone()) {
             Ok(()) => {
-                self.sim_controller_send
-                    .send(DroneEvent::PacketSent(packet.clone()))
-                    .expect("Something wrong");
+                self.send_event(DroneEvent::PacketSent(packet));
+                Ok(())
+            }
+            Err(..) => {
+                self.packet_send.remove(&neighbour.0);
+                Err(DroneError::NeighbourDisconnected(neighbour.0))
             }
-            Err(..) => panic!("Error sending flood request"),
         }
     }
 
-    fn add_neighbor(&mut self, node_id: NodeId, sender: Sender<Packet>) {
+    fn add_neighbor(&mut self, node_id: NodeId, sender: Sender<Packet>) -> Result<(), DroneError> {
+        if node_id == self.id {
+            return Err(DroneError::SelfNeighbour(node_id));
+        }
         if let std::collections::hash_map::Entry::Vacant(e) = self.packet_send.entry(node_id) {
             e.insert(sender);
+            Ok(())
         } else {
-            panic!("Sender for node {node_id} already stored in the map!");
+            Err(DroneError::NeighbourAlreadyPresent(node_id))
         }
     }
 
     fn set_drone_state(&mut self, state: DroneState) {
+        if state == DroneState::Crashing {
+            self.crash_deadline = self.crash_timeout.map(|timeout| Instant::now() + timeout);
+        }
         self.state = state;
     }
 
-    fn should_drop_packet(&self) -> bool {
-        let mut rng = rand::rng();
-        let n: f32 = rng.random_range(0.0..=1.0);
-        if n < self.pdr {
-            return true;
+    // Handles in Crashing state the packets already in the channel, so in-flight fragments
+    // are answered with a Nack instead of being lost, then moves to Crashed
+    fn drain(&mut self) -> usize {
+        self.set_drone_state(DroneState::Crashing);
+        let packet_recv = self.packet_recv.clone();
+        let mut drained = 0;
+        for packet in packet_recv.try_iter() {
+            self.handle_packet(packet);
+            drained += 1;
         }
-        false
+        log::info!(
+            target: &self.log_target,
+            "Drone {} drained {} packets before terminating",
+            self.id,
+            drained
+        );
+        self.state = DroneState::Crashed;
+        self.publish_snapshot();
+        drained
+    }
+
+    fn should_drop_packet(&mut self, packet: &Packet) -> bool {
+        let next_hop = packet
+            .routing_header
+            .hops
+            .get(packet.routing_header.hop_index + 1)
+            .copied();
+        self.drop_model
+            .with_model(|model| model.should_drop(packet, next_hop, &mut self.rng))
     }
 
     fn packet_nack(&self, packet: &Packet, nack: Nack) -> Packet {
@@ -422,11 +669,10 @@ impl Dronegowski {
         }
     }
 
-    fn remove_neighbor(&mut self, node_id: &NodeId) {
-        if self.packet_send.contains_key(node_id) {
-            self.packet_send.remove(node_id);
-        } else {
-            panic!("the {} is not neighbour of the drone {}", node_id, self.id);
+    fn remove_neighbor(&mut self, node_id: &NodeId) -> Result<(), DroneError> {
+        match self.packet_send.remove(node_id) {
+            Some(_) => Ok(()),
+            None => Err(DroneError::NotANeighbour(*node_id)),
         }
     }
 }
@@ -0,0 +1,180 @@
+use super::{
+    DroneError, DroneSnapshot, DroneSnapshotHandle, DroneState, DroneStatsHandle, Dronegowski,
+    DropModel, DropModelHandle, FloodCache, StopToken, UniformDrop, DEFAULT_FLOOD_CACHE_CAPACITY,
+    DEFAULT_LOG_TARGET,
+};
+use crossbeam_channel::{Receiver, Sender};
+use rand::rngs::StdRng;
+use rand::{Rng, SeedableRng};
+use std::collections::HashMap;
+use std::time::Duration;
+use wg_2024::controller::{DroneCommand, DroneEvent};
+use wg_2024::network::NodeId;
+use wg_2024::packet::Packet;
+
+/// Builds a `Dronegowski` from the same channels of `Drone::new` plus optional extensions.
+pub struct DronegowskiBuilder {
+    id: NodeId,
+    controller_send: Sender<DroneEvent>,
+    controller_recv: Receiver<DroneCommand>,
+    packet_recv: Receiver<Packet>,
+    packet_send: HashMap<NodeId, Sender<Packet>>,
+    pdr: Option<f32>,                       //Initial PDR given to the drop model
+    seed: Option<u64>,                      //Random seed if not set
+    drop_model: Option<Box<dyn DropModel>>, //UniformDrop if not set
+    flood_cache_capacity: usize,
+    stats: Option<DroneStatsHandle>,        //Handle already held by the caller
+    log_target: Option<String>,             //Target of every log line of the drone
+    stop: Option<StopToken>,                //Token already held by the caller
+    crash_timeout: Option<Duration>,        //Crashing lasts until all senders are dropped if not set
+}
+
+impl DronegowskiBuilder {
+    pub fn new(
+        id: NodeId,
+        controller_send: Sender<DroneEvent>,
+        controller_recv: Receiver<DroneCommand>,
+        packet_recv: Receiver<Packet>,
+        packet_send: HashMap<NodeId, Sender<Packet>>,
+    ) -> Self {
+        Self {
+            id,
+            controller_send,
+            controller_recv,
+            packet_recv,
+            packet_send,
+            pdr: None,
+            seed: None,
+            drop_model: None,
+            flood_cache_capacity: DEFAULT_FLOOD_CACHE_CAPACITY,
+            stats: None,
+            log_target: None,
+            stop: None,
+            crash_timeout: None,
+        }
+    }
+
+    #[must_use]
+    pub fn pdr(mut self, pdr: f32) -> Self {
+        self.pdr = Some(pdr);
+        self
+    }
+
+    #[must_use]
+    pub fn seed(mut self, seed: u64) -> Self {
+        self.seed = Some(seed);
+        self
+    }
+
+    /// Replaces the default `UniformDrop`, a PDR set with `pdr` is passed to the model.
+    #[must_use]
+    pub fn drop_model<M: DropModel + 'static>(mut self, model: M) -> Self {
+        self.drop_model = Some(Box::new(model));
+        self
+    }
+
+    /// Most floods remembered by the drone, `build` refuses 0.
+    #[must_use]
+    pub fn flood_cache_capacity(mut self, capacity: usize) -> Self {
+        self.flood_cache_capacity = capacity;
+        self
+    }
+
+    /// Makes the drone update the counters of an existing handle.
+    #[must_use]
+    pub fn stats_handle(mut self, stats: DroneStatsHandle) -> Self {
+        self.stats = Some(stats);
+        self
+    }
+
+    #[must_use]
+    pub fn log_target<S: Into<String>>(mut self, target: S) -> Self {
+        self.log_target = Some(target.into());
+        self
+    }
+
+    /// Makes the drone stop when the given token is triggered.
+    #[must_use]
+    pub fn stop_token(mut self, stop: StopToken) -> Self {
+        self.stop = Some(stop);
+        self
+    }
+
+    /// Bounds the time spent in Crashing state, after which the channel is drained and `run()` returns.
+    #[must_use]
+    pub fn crash_timeout(mut self, timeout: Duration) -> Self {
+        self.crash_timeout = Some(timeout);
+        self
+    }
+
+    pub fn build(self) -> Result<Dronegowski, DroneError> {
+        if self.packet_send.contains_key(&self.id) {
+            return Err(DroneError::SelfNeighbour(self.id));
+        }
+        if let Some(pdr) = self.pdr {
+            if !(0.0..=1.0).contains(&pdr) {
+                return Err(DroneError::PdrOutOfBounds(pdr));
+            }
+        }
+        let flood_cache = FloodCache::new(self.flood_cache_capacity)?;
+
+        let mut drop_model = self
+            .drop_model
+            .unwrap_or_else(|| Box::new(UniformDrop::new(0.0)));
+        drop_model.validate()?;
+        let log_target = self
+            .log_target
+            .unwrap_or_else(|| String::from(DEFAULT_LOG_TARGET));
+        if let Some(pdr) = self.pdr {
+            if !drop_model.set_pdr(pdr) {
+                log::warn!(
+                    target: &log_target,
+                    "Drone {}: the drop model ignores the PDR {}",
+                    self.id,
+                    pdr
+                );
+            }
+        }
+        let seed = self.seed.unwrap_or_else(|| rand::rng().random());
+        log::info!(
+            target: &log_target,
+            "Created drone {} with PDR: {} and seed: {}",
+            self.id,
+            drop_model.pdr(),
+            seed
+        );
+
+        let drop_model = DropModelHandle::from_box(drop_model);
+        let snapshot = DroneSnapshotHandle::new(
+            DroneSnapshot {
+                id: self.id,
+                pdr: drop_model.pdr(),
+                state: DroneState::Active,
+                neighbours: Vec::new(),
+                flood_cache_len: 0,
+            },
+            drop_model.clone(),
+        );
+
+        let drone = Dronegowski {
+            id: self.id,
+            sim_controller_send: self.controller_send,
+            sim_controller_recv: self.controller_recv,
+            packet_recv: self.packet_recv,
+            packet_send: self.packet_send,
+            drop_model,
+            state: DroneState::Active,
+            flood_cache,
+            stats: self.stats.unwrap_or_default(),
+            seed,
+            rng: StdRng::seed_from_u64(seed),
+            log_target,
+            snapshot,
+            stop: self.stop.unwrap_or_default(),
+            crash_timeout: self.crash_timeout,
+            crash_deadline: None,
+        };
+        drone.publish_snapshot();
+        Ok(drone)
+    }
+}
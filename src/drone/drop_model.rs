@@ -0,0 +1,267 @@
+use super::DroneError;
+use rand::{Rng, RngCore};
+use std::collections::HashMap;
+use std::fmt::Debug;
+use std::sync::{Arc, Mutex};
+use wg_2024::network::NodeId;
+use wg_2024::packet::Packet;
+
+/// Decides which `MsgFragment` a drone drops.
+pub trait DropModel: Debug + Send {
+    /// Called once per fragment about to be forwarded to `next_hop`.
+    fn should_drop(
+        &mut self,
+        packet: &Packet,
+        next_hop: Option<NodeId>,
+        rng: &mut dyn RngCore,
+    ) -> bool;
+
+    /// Called when the drone receives `DroneCommand::SetPacketDropRate`, `pdr` is already validated.
+    /// Returns false if the model ignores it, so that the drone can log it.
+    fn set_pdr(&mut self, pdr: f32) -> bool;
+
+    /// Nominal drop rate of the model.
+    fn pdr(&self) -> f32;
+
+    /// Called before the drone uses the model, refuses parameters that make no sense.
+    fn validate(&self) -> Result<(), DroneError> {
+        check_probability(self.pdr())
+    }
+}
+
+// Every probability of a model must be in [0, 1], NaN included
+fn check_probability(probability: f32) -> Result<(), DroneError> {
+    if (0.0..=1.0).contains(&probability) {
+        Ok(())
+    } else {
+        Err(DroneError::InvalidDropProbability(probability))
+    }
+}
+
+/// Default model: every fragment is dropped with probability `pdr`, so 1.0 drops them all.
+#[derive(Debug, Clone, PartialEq)]
+pub struct UniformDrop {
+    pdr: f32,
+}
+
+impl UniformDrop {
+    pub fn new(pdr: f32) -> Self {
+        Self { pdr }
+    }
+}
+
+impl DropModel for UniformDrop {
+    fn should_drop(&mut self, _: &Packet, _: Option<NodeId>, rng: &mut dyn RngCore) -> bool {
+        rng.random::<f32>() < self.pdr
+    }
+
+    fn set_pdr(&mut self, pdr: f32) -> bool {
+        self.pdr = pdr;
+        true
+    }
+
+    fn pdr(&self) -> f32 {
+        self.pdr
+    }
+}
+
+/// Bursty loss: a two state Markov chain with a different drop rate in each state.
+///
+/// `SetPacketDropRate` changes the drop rate of the good state.
+#[derive(Debug, Clone, PartialEq)]
+pub struct GilbertElliottDrop {
+    p_good_to_bad: f32, //Probability of moving to the bad state at each fragment
+    p_bad_to_good: f32, //Probability of moving back to the good state at each fragment
+    loss_good: f32,     //Drop rate in the good state
+    loss_bad: f32,      //Drop rate in the bad state
+    bad_state: bool,
+}
+
+impl GilbertElliottDrop {
+    pub fn new(p_good_to_bad: f32, p_bad_to_good: f32, loss_good: f32, loss_bad: f32) -> Self {
+        Self {
+            p_good_to_bad,
+            p_bad_to_good,
+            loss_good,
+            loss_bad,
+            bad_state: false,
+        }
+    }
+
+    pub fn is_bad_state(&self) -> bool {
+        self.bad_state
+    }
+}
+
+impl DropModel for GilbertElliottDrop {
+    fn should_drop(&mut self, _: &Packet, _: Option<NodeId>, rng: &mut dyn RngCore) -> bool {
+        let transition = if self.bad_state {
+            self.p_bad_to_good
+        } else {
+            self.p_good_to_bad
+        };
+        if rng.random::<f32>() < transition {
+            self.bad_state = !self.bad_state;
+        }
+
+        let loss = if self.bad_state {
+            self.loss_bad
+        } else {
+            self.loss_good
+        };
+        rng.random::<f32>() < loss
+    }
+
+    fn set_pdr(&mut self, pdr: f32) -> bool {
+        self.loss_good = pdr;
+        true
+    }
+
+    // Stationary drop rate of the chain
+    fn pdr(&self) -> f32 {
+        let total = self.p_good_to_bad + self.p_bad_to_good;
+        if total == 0.0 {
+            return if self.bad_state {
+                self.loss_bad
+            } else {
+                self.loss_good
+            };
+        }
+        let bad = self.p_good_to_bad / total;
+        (1.0 - bad) * self.loss_good + bad * self.loss_bad
+    }
+
+    fn validate(&self) -> Result<(), DroneError> {
+        check_probability(self.p_good_to_bad)?;
+        check_probability(self.p_bad_to_good)?;
+        check_probability(self.loss_good)?;
+        check_probability(self.loss_bad)
+    }
+}
+
+/// Drop rate depending on the next hop, `default_pdr` for hops without a specific rate.
+///
+/// `SetPacketDropRate` changes `default_pdr`.
+#[derive(Debug, Clone, PartialEq)]
+pub struct PerHopDrop {
+    default_pdr: f32,
+    per_hop: HashMap<NodeId, f32>,
+}
+
+impl PerHopDrop {
+    pub fn new(default_pdr: f32) -> Self {
+        Self {
+            default_pdr,
+            per_hop: HashMap::new(),
+        }
+    }
+
+    #[must_use]
+    pub fn with_hop(mut self, next_hop: NodeId, pdr: f32) -> Self {
+        self.per_hop.insert(next_hop, pdr);
+        self
+    }
+}
+
+impl DropModel for PerHopDrop {
+    fn should_drop(&mut self, _: &Packet, next_hop: Option<NodeId>, rng: &mut dyn RngCore) -> bool {
+        let pdr = next_hop
+            .and_then(|hop| self.per_hop.get(&hop))
+            .copied()
+            .unwrap_or(self.default_pdr);
+        rng.random::<f32>() < pdr
+    }
+
+    fn set_pdr(&mut self, pdr: f32) -> bool {
+        self.default_pdr = pdr;
+        true
+    }
+
+    fn pdr(&self) -> f32 {
+        self.default_pdr
+    }
+
+    fn validate(&self) -> Result<(), DroneError> {
+        check_probability(self.default_pdr)?;
+        self.per_hop.values().try_for_each(|pdr| check_probability(*pdr))
+    }
+}
+
+/// Deterministic schedule: the n-th fragment is dropped if `schedule[n % schedule.len()]` is true.
+///
+/// `SetPacketDropRate` is ignored, the schedule is what reproduces the run.
+#[derive(Debug, Clone, PartialEq)]
+pub struct ScheduledDrop {
+    schedule: Vec<bool>,
+    position: usize,
+}
+
+impl ScheduledDrop {
+    pub fn new(schedule: Vec<bool>) -> Self {
+        Self {
+            schedule,
+            position: 0,
+        }
+    }
+}
+
+impl DropModel for ScheduledDrop {
+    fn should_drop(&mut self, _: &Packet, _: Option<NodeId>, _: &mut dyn RngCore) -> bool {
+        if self.schedule.is_empty() {
+            return false;
+        }
+        let drop = self.schedule[self.position % self.schedule.len()];
+        self.position += 1;
+        drop
+    }
+
+    fn set_pdr(&mut self, _: f32) -> bool {
+        false
+    }
+
+    fn pdr(&self) -> f32 {
+        if self.schedule.is_empty() {
+            return 0.0;
+        }
+        self.schedule.iter().filter(|drop| **drop).count() as f32 / self.schedule.len() as f32
+    }
+}
+
+/// Shared handle to the drop model of a drone, used to switch model while the drone runs.
+#[derive(Debug, Clone)]
+pub struct DropModelHandle {
+    model: Arc<Mutex<Box<dyn DropModel>>>,
+}
+
+impl DropModelHandle {
+    pub fn new<M: DropModel + 'static>(model: M) -> Self {
+        Self {
+            model: Arc::new(Mutex::new(Box::new(model))),
+        }
+    }
+
+    pub fn from_box(model: Box<dyn DropModel>) -> Self {
+        Self {
+            model: Arc::new(Mutex::new(model)),
+        }
+    }
+
+    /// Replaces the model, the next fragment is already handled by the new one.
+    /// Refused if the model does not pass `DropModel::validate`.
+    pub fn set_model<M: DropModel + 'static>(&self, model: M) -> Result<(), DroneError> {
+        model.validate()?;
+        self.with_model(|current| *current = Box::new(model));
+        Ok(())
+    }
+
+    pub fn pdr(&self) -> f32 {
+        self.with_model(|model| model.pdr())
+    }
+
+    pub(crate) fn with_model<T, F: FnOnce(&mut Box<dyn DropModel>) -> T>(&self, f: F) -> T {
+        match self.model.lock() {
+            Ok(mut model) => f(&mut model),
+            Err(poisoned) => f(&mut poisoned.into_inner()),
+        }
+    }
+}
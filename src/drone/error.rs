@@ -0,0 +1,22 @@
+use thiserror::Error;
+use wg_2024::network::NodeId;
+
+#[derive(Debug, Clone, PartialEq, Error)]
+pub enum DroneError {
+    #[error("pdr out of bounds: {0} is not in [0, 1]")]
+    PdrOutOfBounds(f32),
+    #[error("neighbor with id {0} which is the same as drone")]
+    SelfNeighbour(NodeId),
+    #[error("sender for node {0} is already stored in the map")]
+    NeighbourAlreadyPresent(NodeId),
+    #[error("node {0} is not a neighbour of the drone")]
+    NotANeighbour(NodeId),
+    #[error("the channel of neighbour {0} is disconnected")]
+    NeighbourDisconnected(NodeId),
+    #[error("the Simulation Controller channel is disconnected")]
+    ControllerDisconnected,
+    #[error("the flood cache capacity must be positive, got {0}")]
+    InvalidFloodCacheCapacity(usize),
+    #[error("drop model probability out of bounds: {0} is not in [0, 1]")]
+    InvalidDropProbability(f32),
+}
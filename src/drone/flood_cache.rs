@@ -0,0 +1,84 @@
+use super::DroneError;
+use std::collections::{HashMap, VecDeque};
+use wg_2024::network::NodeId;
+
+pub const DEFAULT_FLOOD_CACHE_CAPACITY: usize = 4096;
+
+/// Bounded set of the `(flood_id, initiator_id)` already seen, evicting the least recently seen.
+#[derive(Debug, Clone)]
+pub struct FloodCache {
+    capacity: usize,
+    entries: HashMap<(u64, NodeId), u64>, //Key -> generation of its last use
+    order: VecDeque<((u64, NodeId), u64)>, //Uses in order, stale generations are skipped
+    generation: u64,
+}
+
+impl Default for FloodCache {
+    fn default() -> Self {
+        Self::new(DEFAULT_FLOOD_CACHE_CAPACITY).expect("The default capacity is positive")
+    }
+}
+
+impl FloodCache {
+    /// Refused with `InvalidFloodCacheCapacity` if `capacity` is 0.
+    pub fn new(capacity: usize) -> Result<Self, DroneError> {
+        if capacity == 0 {
+            return Err(DroneError::InvalidFloodCacheCapacity(capacity));
+        }
+        Ok(Self {
+            capacity,
+            entries: HashMap::new(),
+            order: VecDeque::new(),
+            generation: 0,
+        })
+    }
+
+    pub fn capacity(&self) -> usize {
+        self.capacity
+    }
+
+    pub fn len(&self) -> usize {
+        self.entries.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.entries.is_empty()
+    }
+
+    pub fn contains(&self, flood_id: u64, initiator_id: NodeId) -> bool {
+        self.entries.contains_key(&(flood_id, initiator_id))
+    }
+
+    /// Marks the flood as seen, returns true if it was not in the cache (same as `HashSet::insert`).
+    pub fn insert(&mut self, flood_id: u64, initiator_id: NodeId) -> bool {
+        let key = (flood_id, initiator_id);
+        self.generation += 1;
+        let is_new = self.entries.insert(key, self.generation).is_none();
+        self.order.push_back((key, self.generation));
+
+        if is_new && self.entries.len() > self.capacity {
+            self.evict();
+        }
+        if self.order.len() > 2 * self.capacity {
+            self.compact();
+        }
+        is_new
+    }
+
+    // Removes the least recently seen flood
+    fn evict(&mut self) {
+        while let Some((key, generation)) = self.order.pop_front() {
+            if self.entries.get(&key) == Some(&generation) {
+                self.entries.remove(&key);
+                return;
+            }
+        }
+    }
+
+    // Drops the stale uses left in the queue by floods seen more than once
+    fn compact(&mut self) {
+        let entries = &self.entries;
+        self.order
+            .retain(|(key, generation)| entries.get(key) == Some(generation));
+    }
+}
@@ -0,0 +1,65 @@
+use super::DroneStats;
+use crossbeam_channel::{unbounded, Receiver, Sender};
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::Arc;
+use wg_2024::network::NodeId;
+
+/// Local way to stop a running drone, without going through the Simulation Controller.
+///
+/// Every clone refers to the same drone: calling `stop` on any of them makes `run()` drain the
+/// packets still in its channel and return, even if other nodes still hold a sender.
+#[derive(Debug, Clone)]
+pub struct StopToken {
+    stopped: Arc<AtomicBool>,
+    wake_send: Sender<()>, //Wakes up the drone blocked on its channels
+    wake_recv: Receiver<()>,
+}
+
+impl StopToken {
+    pub fn new() -> Self {
+        let (wake_send, wake_recv) = unbounded();
+        Self {
+            stopped: Arc::new(AtomicBool::new(false)),
+            wake_send,
+            wake_recv,
+        }
+    }
+
+    pub fn stop(&self) {
+        if !self.stopped.swap(true, Ordering::SeqCst) {
+            let _ = self.wake_send.send(());
+        }
+    }
+
+    pub fn is_stopped(&self) -> bool {
+        self.stopped.load(Ordering::SeqCst)
+    }
+
+    pub(crate) fn receiver(&self) -> &Receiver<()> {
+        &self.wake_recv
+    }
+}
+
+impl Default for StopToken {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+/// Why `run()` returned.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ShutdownReason {
+    Crashed,      //Crash command and every sender of the packet channel dropped
+    Stopped,      //StopToken triggered
+    CrashTimeout, //Crash command, but the senders were not dropped before the deadline
+}
+
+/// Final report returned by `Dronegowski::run_with_report`.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct ShutdownReport {
+    pub id: NodeId,
+    pub reason: ShutdownReason,
+    pub drained: usize,    //Packets still in the channel handled after the stop or the deadline
+    pub stats: DroneStats, //Counters at the time the drone terminated
+    pub seed: u64,         //Seed of the drop decisions of the drone
+}
@@ -0,0 +1,45 @@
+use super::{DroneState, DropModelHandle};
+use std::sync::{Arc, Mutex};
+use wg_2024::network::NodeId;
+
+/// Introspection data of a drone at a given time.
+#[derive(Debug, Clone, PartialEq)]
+pub struct DroneSnapshot {
+    pub id: NodeId,
+    pub pdr: f32,
+    pub state: DroneState,
+    pub neighbours: Vec<NodeId>, //Sorted ids of the current neighbours
+    pub flood_cache_len: usize,
+}
+
+/// Shared handle to the snapshot of a drone, updated by the drone after every packet and command.
+#[derive(Debug, Clone)]
+pub struct DroneSnapshotHandle {
+    snapshot: Arc<Mutex<DroneSnapshot>>,
+    drop_model: DropModelHandle, //The PDR is read from the model, which can be switched at any time
+}
+
+impl DroneSnapshotHandle {
+    pub(crate) fn new(snapshot: DroneSnapshot, drop_model: DropModelHandle) -> Self {
+        Self {
+            snapshot: Arc::new(Mutex::new(snapshot)),
+            drop_model,
+        }
+    }
+
+    pub fn snapshot(&self) -> DroneSnapshot {
+        let mut snapshot = match self.snapshot.lock() {
+            Ok(snapshot) => snapshot.clone(),
+            Err(poisoned) => poisoned.into_inner().clone(),
+        };
+        snapshot.pdr = self.drop_model.pdr();
+        snapshot
+    }
+
+    pub(crate) fn publish(&self, snapshot: DroneSnapshot) {
+        match self.snapshot.lock() {
+            Ok(mut current) => *current = snapshot,
+            Err(poisoned) => *poisoned.into_inner() = snapshot,
+        }
+    }
+}
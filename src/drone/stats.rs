@@ -0,0 +1,89 @@
+use std::sync::{Arc, Mutex};
+use wg_2024::packet::{NackType, PacketType};
+
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub struct PacketTypeCounters {
+    pub msg_fragment: u64,
+    pub ack: u64,
+    pub nack: u64,
+    pub flood_request: u64,
+    pub flood_response: u64,
+}
+
+impl PacketTypeCounters {
+    pub fn record(&mut self, pack_type: &PacketType) {
+        match pack_type {
+            PacketType::MsgFragment(_) => self.msg_fragment += 1,
+            PacketType::Ack(_) => self.ack += 1,
+            PacketType::Nack(_) => self.nack += 1,
+            PacketType::FloodRequest(_) => self.flood_request += 1,
+            PacketType::FloodResponse(_) => self.flood_response += 1,
+        }
+    }
+
+    pub fn total(&self) -> u64 {
+        self.msg_fragment + self.ack + self.nack + self.flood_request + self.flood_response
+    }
+}
+
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub struct NackTypeCounters {
+    pub error_in_routing: u64,
+    pub destination_is_drone: u64,
+    pub dropped: u64,
+    pub unexpected_recipient: u64,
+}
+
+impl NackTypeCounters {
+    pub fn record(&mut self, nack_type: &NackType) {
+        match nack_type {
+            NackType::ErrorInRouting(_) => self.error_in_routing += 1,
+            NackType::DestinationIsDrone => self.destination_is_drone += 1,
+            NackType::Dropped => self.dropped += 1,
+            NackType::UnexpectedRecipient(_) => self.unexpected_recipient += 1,
+        }
+    }
+
+    pub fn total(&self) -> u64 {
+        self.error_in_routing + self.destination_is_drone + self.dropped + self.unexpected_recipient
+    }
+}
+
+/// Counters of everything a drone did since it was created.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub struct DroneStats {
+    pub received: PacketTypeCounters,      //Packets received per PacketType
+    pub fragments_forwarded: u64,          //MsgFragment sent to the next hop
+    pub fragments_dropped: u64,            //MsgFragment dropped because of the PDR
+    pub nacks_generated: NackTypeCounters, //Nack created by the drone per NackType
+    pub flood_requests_forwarded: u64,     //Flood Request sent on to the neighbours
+    pub flood_requests_answered: u64,      //Flood Request answered with a Flood Response
+    pub controller_shortcuts: u64,         //Packets sent to the Simulation Controller
+}
+
+/// Shared handle to the stats of a drone, readable while the drone is running.
+#[derive(Debug, Clone, Default)]
+pub struct DroneStatsHandle {
+    stats: Arc<Mutex<DroneStats>>,
+}
+
+impl DroneStatsHandle {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Copy of the counters at the time of the call.
+    pub fn snapshot(&self) -> DroneStats {
+        match self.stats.lock() {
+            Ok(stats) => *stats,
+            Err(poisoned) => *poisoned.into_inner(),
+        }
+    }
+
+    pub(crate) fn update<F: FnOnce(&mut DroneStats)>(&self, f: F) {
+        match self.stats.lock() {
+            Ok(mut stats) => f(&mut stats),
+            Err(poisoned) => f(&mut poisoned.into_inner()),
+        }
+    }
+}
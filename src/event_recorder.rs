@@ -0,0 +1,294 @@
+use crate::ControllerCommand;
+use crossbeam_channel::{unbounded, Receiver};
+use serde::{Deserialize, Serialize};
+use std::fs::{self, File};
+use std::io::{BufRead, BufReader, BufWriter, Write};
+use std::path::{Path, PathBuf};
+use std::thread::{self, JoinHandle};
+use std::time::Instant;
+use thiserror::Error;
+use wg_2024::controller::DroneEvent;
+use wg_2024::network::NodeId;
+use wg_2024::packet::{Packet, PacketType};
+
+#[derive(Debug, Error)]
+pub enum EventRecorderError {
+    #[error("Error writing the event log: {0}")]
+    Io(#[from] std::io::Error),
+    #[error("Error serializing the event: {0}")]
+    Json(#[from] serde_json::Error),
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum EventKind {
+    PacketSent,
+    PacketDropped,
+    ControllerShortcut,
+    PacketInjected, //Packet sent into the network by the Simulation Controller
+    Command,        //Command applied by the Simulation Controller
+}
+
+impl EventKind {
+    /// True for the kinds of `DroneEvent`, false for the inputs of the Simulation Controller.
+    pub fn is_drone_event(&self) -> bool {
+        matches!(
+            self,
+            EventKind::PacketSent | EventKind::PacketDropped | EventKind::ControllerShortcut
+        )
+    }
+}
+
+/// One line of the event log.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct EventRecord {
+    pub timestamp_us: u64,     //Microseconds since the recorder was created, monotonic
+    pub drone: Option<NodeId>, //Drone that emitted the event, or node an injected packet was sent to
+    pub kind: EventKind,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub packet: Option<Packet>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub command: Option<ControllerCommand>,
+}
+
+impl EventRecord {
+    pub fn new(timestamp_us: u64, event: &DroneEvent) -> Self {
+        let (kind, packet) = match event {
+            DroneEvent::PacketSent(packet) => (EventKind::PacketSent, packet),
+            DroneEvent::PacketDropped(packet) => (EventKind::PacketDropped, packet),
+            DroneEvent::ControllerShortcut(packet) => (EventKind::ControllerShortcut, packet),
+        };
+        Self {
+            timestamp_us,
+            drone: event_drone(event),
+            kind,
+            packet: Some(packet.clone()),
+            command: None,
+        }
+    }
+
+    pub fn injected(timestamp_us: u64, node_id: NodeId, packet: &Packet) -> Self {
+        Self {
+            timestamp_us,
+            drone: Some(node_id),
+            kind: EventKind::PacketInjected,
+            packet: Some(packet.clone()),
+            command: None,
+        }
+    }
+
+    pub fn command(timestamp_us: u64, command: ControllerCommand) -> Self {
+        Self {
+            timestamp_us,
+            drone: None,
+            kind: EventKind::Command,
+            packet: None,
+            command: Some(command),
+        }
+    }
+
+    /// The recorded `DroneEvent`, None for the inputs of the Simulation Controller.
+    pub fn to_event(&self) -> Option<DroneEvent> {
+        let packet = self.packet.clone()?;
+        match self.kind {
+            EventKind::PacketSent => Some(DroneEvent::PacketSent(packet)),
+            EventKind::PacketDropped => Some(DroneEvent::PacketDropped(packet)),
+            EventKind::ControllerShortcut => Some(DroneEvent::ControllerShortcut(packet)),
+            EventKind::PacketInjected | EventKind::Command => None,
+        }
+    }
+}
+
+/// Drone that emitted an event.
+///
+/// A sent packet already points to the next hop, so the drone is the previous one; a flood
+/// request carries the drone at the end of its path trace. A dropped or shortcut packet still
+/// points to the drone.
+pub fn event_drone(event: &DroneEvent) -> Option<NodeId> {
+    match event {
+        DroneEvent::PacketSent(packet) => match &packet.pack_type {
+            PacketType::FloodRequest(flood_request) => {
+                flood_request.path_trace.last().map(|(id, _)| *id)
+            }
+            _ => packet
+                .routing_header
+                .hop_index
+                .checked_sub(1)
+                .and_then(|index| packet.routing_header.hops.get(index))
+                .copied(),
+        },
+        DroneEvent::PacketDropped(packet) | DroneEvent::ControllerShortcut(packet) => packet
+            .routing_header
+            .hops
+            .get(packet.routing_header.hop_index)
+            .copied(),
+    }
+}
+
+/// Writes one JSON line per `DroneEvent` to a file, together with the packets injected and the
+/// commands applied by the Simulation Controller.
+///
+/// With rotation, once the file would grow past the size limit it is renamed to `<path>.1`
+/// (the older ones shifting to `<path>.2` and so on) and a new file is started. Buffered lines
+/// are flushed when the recorder is dropped.
+#[derive(Debug)]
+pub struct EventRecorder {
+    path: PathBuf,
+    writer: BufWriter<File>,
+    start: Instant,
+    rotation: Option<(u64, usize)>, //Maximum bytes of a file and number of rotated files kept
+    bytes_written: u64,             //Bytes in the current file
+    records: u64,
+}
+
+impl EventRecorder {
+    /// Creates the log file, truncating it if it already exists.
+    pub fn create<P: AsRef<Path>>(path: P) -> Result<Self, EventRecorderError> {
+        let path = path.as_ref().to_path_buf();
+        let writer = BufWriter::new(File::create(&path)?);
+        Ok(Self {
+            path,
+            writer,
+            start: Instant::now(),
+            rotation: None,
+            bytes_written: 0,
+            records: 0,
+        })
+    }
+
+    /// Rotates the file once it reaches `max_bytes`, keeping at most `max_files` old files.
+    #[must_use]
+    pub fn with_rotation(mut self, max_bytes: u64, max_files: usize) -> Self {
+        self.rotation = Some((max_bytes.max(1), max_files));
+        self
+    }
+
+    pub fn path(&self) -> &Path {
+        &self.path
+    }
+
+    /// Number of lines written since the recorder was created.
+    pub fn records(&self) -> u64 {
+        self.records
+    }
+
+    /// Microseconds since the recorder was created.
+    pub fn elapsed_us(&self) -> u64 {
+        u64::try_from(self.start.elapsed().as_micros()).unwrap_or(u64::MAX)
+    }
+
+    pub fn record(&mut self, event: &DroneEvent) -> Result<(), EventRecorderError> {
+        let record = EventRecord::new(self.elapsed_us(), event);
+        self.write_record(&record)
+    }
+
+    pub fn record_injected(
+        &mut self,
+        node_id: NodeId,
+        packet: &Packet,
+    ) -> Result<(), EventRecorderError> {
+        let record = EventRecord::injected(self.elapsed_us(), node_id, packet);
+        self.write_record(&record)
+    }
+
+    pub fn record_command(&mut self, command: ControllerCommand) -> Result<(), EventRecorderError> {
+        let record = EventRecord::command(self.elapsed_us(), command);
+        self.write_record(&record)
+    }
+
+    fn write_record(&mut self, record: &EventRecord) -> Result<(), EventRecorderError> {
+        let mut line = serde_json::to_vec(record)?;
+        line.push(b'\n');
+
+        if let Some((max_bytes, _)) = self.rotation {
+            if self.bytes_written > 0 && self.bytes_written + line.len() as u64 > max_bytes {
+                self.rotate()?;
+            }
+        }
+        self.writer.write_all(&line)?;
+        self.bytes_written += line.len() as u64;
+        self.records += 1;
+        Ok(())
+    }
+
+    pub fn flush(&mut self) -> Result<(), EventRecorderError> {
+        self.writer.flush()?;
+        Ok(())
+    }
+
+    /// Records every event of `events` on a new thread and forwards it to the returned receiver.
+    ///
+    /// The thread ends, returning the recorder, once every sender of `events` is dropped.
+    pub fn tap(
+        mut self,
+        events: Receiver<DroneEvent>,
+    ) -> (Receiver<DroneEvent>, JoinHandle<EventRecorder>) {
+        let (event_send, event_recv) = unbounded();
+        let handle = thread::spawn(move || {
+            for event in events {
+                if let Err(error) = self.record(&event) {
+                    log::warn!("Event recorder: {error}");
+                }
+                //The reader may be gone, recording goes on anyway
+                let _ = event_send.send(event);
+            }
+            if let Err(error) = self.flush() {
+                log::warn!("Event recorder: {error}");
+            }
+            self
+        });
+        (event_recv, handle)
+    }
+
+    fn rotate(&mut self) -> Result<(), EventRecorderError> {
+        self.writer.flush()?;
+        let Some((_, max_files)) = self.rotation else {
+            return Ok(());
+        };
+
+        if max_files == 0 {
+            fs::remove_file(&self.path)?;
+        } else {
+            let _ = fs::remove_file(rotated_path(&self.path, max_files));
+            for index in (1..max_files).rev() {
+                let from = rotated_path(&self.path, index);
+                if from.exists() {
+                    fs::rename(&from, rotated_path(&self.path, index + 1))?;
+                }
+            }
+            fs::rename(&self.path, rotated_path(&self.path, 1))?;
+        }
+
+        self.writer = BufWriter::new(File::create(&self.path)?);
+        self.bytes_written = 0;
+        Ok(())
+    }
+}
+
+impl Drop for EventRecorder {
+    fn drop(&mut self) {
+        if let Err(error) = self.writer.flush() {
+            log::error!("Event recorder: could not flush {:?}: {error}", self.path);
+        }
+    }
+}
+
+/// Path of the `index`-th rotated file of a log.
+pub fn rotated_path(path: &Path, index: usize) -> PathBuf {
+    let mut name = path.as_os_str().to_os_string();
+    name.push(format!(".{index}"));
+    PathBuf::from(name)
+}
+
+/// Reads back every record of an event log.
+pub fn read_event_log<P: AsRef<Path>>(path: P) -> Result<Vec<EventRecord>, EventRecorderError> {
+    let reader = BufReader::new(File::open(path)?);
+    let mut records = Vec::new();
+    for line in reader.lines() {
+        let line = line?;
+        if !line.trim().is_empty() {
+            records.push(serde_json::from_str(&line)?);
+        }
+    }
+    Ok(records)
+}
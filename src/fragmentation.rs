@@ -0,0 +1,263 @@
+use std::collections::{BTreeMap, HashMap, HashSet, VecDeque};
+use std::time::{Duration, Instant};
+use thiserror::Error;
+use wg_2024::network::{NodeId, SourceRoutingHeader};
+use wg_2024::packet::{Fragment, Packet, PacketType, FRAGMENT_DSIZE};
+
+pub const DEFAULT_REASSEMBLY_TIMEOUT: Duration = Duration::from_secs(10);
+pub const DEFAULT_MAX_SESSION_BYTES: usize = 1 << 20;
+const COMPLETED_SESSIONS_KEPT: usize = 1024;
+
+#[derive(Debug, Clone, PartialEq, Error)]
+pub enum ReassemblyError {
+    #[error("Fragment {1} of session {0} is not valid: {2}")]
+    InvalidFragment(u64, u64, &'static str),
+    #[error("Fragment {1} of session {0} says {3} fragments, the session has {2}.")]
+    InconsistentTotal(u64, u64, u64, u64),
+    #[error("Fragment {1} of session {0} was already received with different data.")]
+    ConflictingDuplicate(u64, u64),
+    #[error("Session {0} needs {1} bytes, more than the cap of {2}.")]
+    SessionTooLarge(u64, usize, usize),
+}
+
+/// Splits a payload into numbered fragments of `FRAGMENT_DSIZE` bytes, the last one holding
+/// the rest. An empty payload gives a single empty fragment.
+pub fn fragment(payload: &[u8]) -> Vec<Fragment> {
+    if payload.is_empty() {
+        return vec![Fragment {
+            fragment_index: 0,
+            total_n_fragments: 1,
+            length: 0,
+            data: [0; FRAGMENT_DSIZE],
+        }];
+    }
+    let total_n_fragments = payload.len().div_ceil(FRAGMENT_DSIZE) as u64;
+    payload
+        .chunks(FRAGMENT_DSIZE)
+        .enumerate()
+        .map(|(index, chunk)| {
+            let mut data = [0; FRAGMENT_DSIZE];
+            data[..chunk.len()].copy_from_slice(chunk);
+            Fragment {
+                fragment_index: index as u64,
+                total_n_fragments,
+                length: chunk.len() as u8,
+                data,
+            }
+        })
+        .collect()
+}
+
+/// The fragments of a payload as `MsgFragment` packets of a session, all on the same route.
+pub fn message_packets(
+    session_id: u64,
+    routing_header: &SourceRoutingHeader,
+    payload: &[u8],
+) -> Vec<Packet> {
+    fragment(payload)
+        .into_iter()
+        .map(|fragment| Packet {
+            pack_type: PacketType::MsgFragment(fragment),
+            routing_header: routing_header.clone(),
+            session_id,
+        })
+        .collect()
+}
+
+/// What a fragment accepted by the `Reassembler` did to its session.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum FragmentStatus {
+    Complete(Vec<u8>), //The payload of the whole message
+    Incomplete { received: u64, total: u64 },
+    Duplicate,
+}
+
+/// Session dropped by `Reassembler::expire` before all its fragments arrived.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct ExpiredSession {
+    pub source: NodeId,
+    pub session_id: u64,
+    pub missing: Vec<u64>, //Indexes of the fragments never received
+}
+
+struct Session {
+    total: u64,
+    fragments: BTreeMap<u64, Vec<u8>>,
+    last_update: Instant,
+}
+
+/// Rebuilds the messages from their fragments, which can arrive out of order and more than once.
+///
+/// Sessions are identified by their source and `session_id`. A session that gets no new fragment
+/// for the timeout is dropped by `expire`, and sessions bigger than the memory cap are refused
+/// from their first fragment.
+pub struct Reassembler {
+    sessions: HashMap<(NodeId, u64), Session>,
+    completed: HashSet<(NodeId, u64)>, //Recent complete sessions, to spot late duplicates
+    completed_order: VecDeque<(NodeId, u64)>,
+    timeout: Duration,
+    max_session_bytes: usize,
+}
+
+impl Default for Reassembler {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl Reassembler {
+    pub fn new() -> Self {
+        Self {
+            sessions: HashMap::new(),
+            completed: HashSet::new(),
+            completed_order: VecDeque::new(),
+            timeout: DEFAULT_REASSEMBLY_TIMEOUT,
+            max_session_bytes: DEFAULT_MAX_SESSION_BYTES,
+        }
+    }
+
+    #[must_use]
+    pub fn with_timeout(mut self, timeout: Duration) -> Self {
+        self.timeout = timeout;
+        self
+    }
+
+    #[must_use]
+    pub fn with_max_session_bytes(mut self, max_session_bytes: usize) -> Self {
+        self.max_session_bytes = max_session_bytes;
+        self
+    }
+
+    /// Adds a fragment to its session, returning the payload once the message is complete.
+    ///
+    /// A refused fragment leaves its session as it was.
+    pub fn push(
+        &mut self,
+        source: NodeId,
+        session_id: u64,
+        fragment: &Fragment,
+    ) -> Result<FragmentStatus, ReassemblyError> {
+        let key = (source, session_id);
+        let index = fragment.fragment_index;
+        let invalid = |reason| ReassemblyError::InvalidFragment(session_id, index, reason);
+        if fragment.total_n_fragments == 0 {
+            return Err(invalid("the message has no fragments"));
+        }
+        if index >= fragment.total_n_fragments {
+            return Err(invalid("the index is past the last fragment"));
+        }
+        if usize::from(fragment.length) > FRAGMENT_DSIZE {
+            return Err(invalid("the length is over the fragment size"));
+        }
+        let full = usize::from(fragment.length) == FRAGMENT_DSIZE;
+        if index + 1 < fragment.total_n_fragments && !full {
+            return Err(invalid("only the last fragment can be shorter"));
+        }
+        if self.completed.contains(&key) {
+            return Ok(FragmentStatus::Duplicate);
+        }
+
+        let data = fragment.data[..usize::from(fragment.length)].to_vec();
+        if !self.sessions.contains_key(&key) {
+            let bytes = usize::try_from(fragment.total_n_fragments)
+                .ok()
+                .and_then(|total| total.checked_mul(FRAGMENT_DSIZE))
+                .unwrap_or(usize::MAX);
+            if bytes > self.max_session_bytes {
+                return Err(ReassemblyError::SessionTooLarge(
+                    session_id,
+                    bytes,
+                    self.max_session_bytes,
+                ));
+            }
+        }
+        let session = self.sessions.entry(key).or_insert_with(|| Session {
+            total: fragment.total_n_fragments,
+            fragments: BTreeMap::new(),
+            last_update: Instant::now(),
+        });
+        if fragment.total_n_fragments != session.total {
+            return Err(ReassemblyError::InconsistentTotal(
+                session_id,
+                index,
+                session.total,
+                fragment.total_n_fragments,
+            ));
+        }
+        if let Some(received) = session.fragments.get(&index) {
+            return if *received == data {
+                Ok(FragmentStatus::Duplicate)
+            } else {
+                Err(ReassemblyError::ConflictingDuplicate(session_id, index))
+            };
+        }
+
+        session.fragments.insert(index, data);
+        session.last_update = Instant::now();
+        let (received, total) = (session.fragments.len() as u64, session.total);
+        if received < total {
+            return Ok(FragmentStatus::Incomplete { received, total });
+        }
+
+        let session = self
+            .sessions
+            .remove(&key)
+            .expect("The session is in progress");
+        self.mark_completed(key);
+        Ok(FragmentStatus::Complete(
+            session.fragments.into_values().flatten().collect(),
+        ))
+    }
+
+    /// Indexes of the fragments still missing from a session in progress.
+    pub fn missing(&self, source: NodeId, session_id: u64) -> Option<Vec<u64>> {
+        self.sessions
+            .get(&(source, session_id))
+            .map(missing_indexes)
+    }
+
+    pub fn sessions_in_progress(&self) -> usize {
+        self.sessions.len()
+    }
+
+    /// Drops the sessions that got no fragment for the timeout.
+    pub fn expire(&mut self) -> Vec<ExpiredSession> {
+        let now = Instant::now();
+        let expired: Vec<(NodeId, u64)> = self
+            .sessions
+            .iter()
+            .filter(|(_, session)| now.duration_since(session.last_update) >= self.timeout)
+            .map(|(key, _)| *key)
+            .collect();
+        let mut sessions: Vec<ExpiredSession> = expired
+            .into_iter()
+            .filter_map(|key| {
+                let session = self.sessions.remove(&key)?;
+                Some(ExpiredSession {
+                    source: key.0,
+                    session_id: key.1,
+                    missing: missing_indexes(&session),
+                })
+            })
+            .collect();
+        sessions.sort_by_key(|session| (session.source, session.session_id));
+        sessions
+    }
+
+    fn mark_completed(&mut self, key: (NodeId, u64)) {
+        if self.completed.insert(key) {
+            self.completed_order.push_back(key);
+        }
+        if self.completed_order.len() > COMPLETED_SESSIONS_KEPT {
+            if let Some(oldest) = self.completed_order.pop_front() {
+                self.completed.remove(&oldest);
+            }
+        }
+    }
+}
+
+fn missing_indexes(session: &Session) -> Vec<u64> {
+    (0..session.total)
+        .filter(|index| !session.fragments.contains_key(index))
+        .collect()
+}
@@ -1,4 +1,31 @@
+mod application;
+mod client;
 mod drone;
+mod event_recorder;
+mod fragmentation;
+mod network_graph;
 mod network_initializer;
+mod reliable_delivery;
+mod replay;
+mod scenario;
+mod server;
+mod simulation_controller;
+mod simulation_runner;
+mod topology_generator;
+mod topology_view;
 
-pub use drone::*;
\ No newline at end of file
+pub use application::*;
+pub use client::*;
+pub use drone::*;
+pub use event_recorder::*;
+pub use fragmentation::*;
+pub use network_graph::*;
+pub use network_initializer::*;
+pub use reliable_delivery::*;
+pub use replay::*;
+pub use scenario::*;
+pub use server::*;
+pub use simulation_controller::*;
+pub use simulation_runner::*;
+pub use topology_generator::*;
+pub use topology_view::*;
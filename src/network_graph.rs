@@ -0,0 +1,513 @@
+use std::collections::{BTreeMap, BTreeSet, HashMap, VecDeque};
+use thiserror::Error;
+use wg_2024::config::Config;
+use wg_2024::network::NodeId;
+use wg_2024::packet::NodeType;
+
+#[derive(Debug, Clone, PartialEq, Error)]
+pub enum GraphError {
+    #[error("node {0} is not part of the graph")]
+    UnknownNode(NodeId),
+    #[error("node {0} cannot be linked to itself")]
+    SelfLoop(NodeId),
+}
+
+/// Longest shortest path of a graph, see `NetworkGraph::diameter`.
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct DiameterReport {
+    pub diameter: usize,                       //Hops of the longest shortest path
+    pub endpoints: Option<(NodeId, NodeId)>,   //Ends of that path, None without reachable pairs
+    pub eccentricity: BTreeMap<NodeId, usize>, //Hops to the farthest reachable node
+    pub unreachable_pairs: Vec<(NodeId, NodeId)>,
+}
+
+/// Undirected topology of a network, with the `NodeType` of every node.
+///
+/// Paths follow the AP routing rule: only drones forward packets, so every node in the middle
+/// of a path is a drone. Articulation points and bridges are computed on the plain graph.
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct NetworkGraph {
+    nodes: BTreeMap<NodeId, NodeType>,
+    edges: BTreeMap<NodeId, BTreeSet<NodeId>>,
+}
+
+impl NetworkGraph {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Builds the graph of a config: links to unknown nodes and self loops are skipped, a link
+    /// listed by only one of its ends is added anyway.
+    pub fn from_config(config: &Config) -> Self {
+        let mut graph = Self::new();
+        for drone in &config.drone {
+            graph.add_node(drone.id, NodeType::Drone);
+        }
+        for client in &config.client {
+            graph.add_node(client.id, NodeType::Client);
+        }
+        for server in &config.server {
+            graph.add_node(server.id, NodeType::Server);
+        }
+
+        let links = config
+            .drone
+            .iter()
+            .map(|drone| (drone.id, &drone.connected_node_ids))
+            .chain(
+                config
+                    .client
+                    .iter()
+                    .map(|client| (client.id, &client.connected_drone_ids)),
+            )
+            .chain(
+                config
+                    .server
+                    .iter()
+                    .map(|server| (server.id, &server.connected_drone_ids)),
+            );
+        for (id, neighbours) in links {
+            for neighbour in neighbours {
+                let _ = graph.add_edge(id, *neighbour);
+            }
+        }
+        graph
+    }
+
+    /// Adds a node without links, returns false if the id was already used.
+    pub fn add_node(&mut self, node_id: NodeId, node_type: NodeType) -> bool {
+        if self.nodes.contains_key(&node_id) {
+            return false;
+        }
+        self.nodes.insert(node_id, node_type);
+        self.edges.insert(node_id, BTreeSet::new());
+        true
+    }
+
+    /// Removes a node and all its links.
+    pub fn remove_node(&mut self, node_id: NodeId) -> Option<NodeType> {
+        self.clear_edges(node_id);
+        self.edges.remove(&node_id);
+        self.nodes.remove(&node_id)
+    }
+
+    /// Adds a link, returns false if it was already present.
+    pub fn add_edge(&mut self, a: NodeId, b: NodeId) -> Result<bool, GraphError> {
+        if a == b {
+            return Err(GraphError::SelfLoop(a));
+        }
+        for node_id in [a, b] {
+            if !self.contains_node(node_id) {
+                return Err(GraphError::UnknownNode(node_id));
+            }
+        }
+        let added = self.edges.entry(a).or_default().insert(b);
+        self.edges.entry(b).or_default().insert(a);
+        Ok(added)
+    }
+
+    /// Removes a link, returns false if it was not present.
+    pub fn remove_edge(&mut self, a: NodeId, b: NodeId) -> bool {
+        let removed = self
+            .edges
+            .get_mut(&a)
+            .is_some_and(|neighbours| neighbours.remove(&b));
+        if let Some(neighbours) = self.edges.get_mut(&b) {
+            neighbours.remove(&a);
+        }
+        removed
+    }
+
+    /// Removes every link of a node, keeping the node, and returns its former neighbours.
+    pub fn clear_edges(&mut self, node_id: NodeId) -> Vec<NodeId> {
+        let neighbours: Vec<NodeId> = self
+            .edges
+            .get_mut(&node_id)
+            .map(std::mem::take)
+            .unwrap_or_default()
+            .into_iter()
+            .collect();
+        for neighbour in &neighbours {
+            if let Some(links) = self.edges.get_mut(neighbour) {
+                links.remove(&node_id);
+            }
+        }
+        neighbours
+    }
+
+    pub fn contains_node(&self, node_id: NodeId) -> bool {
+        self.nodes.contains_key(&node_id)
+    }
+
+    pub fn has_edge(&self, a: NodeId, b: NodeId) -> bool {
+        self.edges
+            .get(&a)
+            .is_some_and(|neighbours| neighbours.contains(&b))
+    }
+
+    pub fn node_type(&self, node_id: NodeId) -> Option<NodeType> {
+        self.nodes.get(&node_id).copied()
+    }
+
+    /// Ids of every node, sorted.
+    pub fn nodes(&self) -> Vec<NodeId> {
+        self.nodes.keys().copied().collect()
+    }
+
+    /// Ids of the nodes of the given type, sorted.
+    pub fn nodes_of_type(&self, node_type: NodeType) -> Vec<NodeId> {
+        self.nodes
+            .iter()
+            .filter(|(_, t)| **t == node_type)
+            .map(|(id, _)| *id)
+            .collect()
+    }
+
+    /// Neighbours of a node, sorted.
+    pub fn neighbours(&self, node_id: NodeId) -> Option<Vec<NodeId>> {
+        self.edges
+            .get(&node_id)
+            .map(|neighbours| neighbours.iter().copied().collect())
+    }
+
+    pub fn degree(&self, node_id: NodeId) -> usize {
+        self.edges.get(&node_id).map_or(0, BTreeSet::len)
+    }
+
+    /// Every link once, as (smaller id, bigger id), sorted.
+    pub fn edges(&self) -> Vec<(NodeId, NodeId)> {
+        self.edges
+            .iter()
+            .flat_map(|(a, neighbours)| {
+                neighbours
+                    .iter()
+                    .filter(move |b| a < *b)
+                    .map(move |b| (*a, *b))
+            })
+            .collect()
+    }
+
+    pub fn len(&self) -> usize {
+        self.nodes.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.nodes.is_empty()
+    }
+
+    /// Nodes reachable from `start` (itself included), going only through drones.
+    pub fn reachable_from(&self, start: NodeId) -> BTreeSet<NodeId> {
+        self.distances_from(start).into_keys().collect()
+    }
+
+    /// Shortest path from `from` to `to`, preferring the smallest ids among paths of equal length.
+    pub fn shortest_path(&self, from: NodeId, to: NodeId) -> Option<Vec<NodeId>> {
+        if !self.contains_node(from) || !self.contains_node(to) {
+            return None;
+        }
+        let mut previous: HashMap<NodeId, NodeId> = HashMap::new();
+        let mut queue = VecDeque::from([from]);
+        let mut visited = BTreeSet::from([from]);
+        while let Some(node_id) = queue.pop_front() {
+            if node_id == to {
+                let mut path = vec![to];
+                let mut current = to;
+                while let Some(prev) = previous.get(&current) {
+                    path.push(*prev);
+                    current = *prev;
+                }
+                path.reverse();
+                return Some(path);
+            }
+            if !self.forwards(node_id, from) {
+                continue;
+            }
+            for neighbour in &self.edges[&node_id] {
+                if visited.insert(*neighbour) {
+                    previous.insert(*neighbour, node_id);
+                    queue.push_back(*neighbour);
+                }
+            }
+        }
+        None
+    }
+
+    /// Every simple path from `from` to `to` with at most `max_hops` links, shortest first.
+    pub fn simple_paths(&self, from: NodeId, to: NodeId, max_hops: usize) -> Vec<Vec<NodeId>> {
+        let mut paths = Vec::new();
+        if self.contains_node(from) && self.contains_node(to) {
+            let mut path = vec![from];
+            self.collect_paths(to, max_hops, &mut path, &mut paths);
+        }
+        paths.sort_by_key(Vec::len);
+        paths
+    }
+
+    /// Nodes whose removal disconnects the plain graph, sorted (Tarjan).
+    pub fn articulation_points(&self) -> Vec<NodeId> {
+        let mut search = LowLink::default();
+        for node_id in self.nodes.keys() {
+            if !search.discovery.contains_key(node_id) {
+                self.low_link(*node_id, None, &mut search);
+            }
+        }
+        search.articulation_points.into_iter().collect()
+    }
+
+    /// Links whose removal disconnects the plain graph, as (smaller id, bigger id), sorted.
+    pub fn bridges(&self) -> Vec<(NodeId, NodeId)> {
+        let mut search = LowLink::default();
+        for node_id in self.nodes.keys() {
+            if !search.discovery.contains_key(node_id) {
+                self.low_link(*node_id, None, &mut search);
+            }
+        }
+        search.bridges.into_iter().collect()
+    }
+
+    /// Number of paths from `a` to `b` sharing no node but their ends (Menger), meant for a
+    /// client and a server: how many drones must crash before they are disconnected.
+    ///
+    /// A direct link between `a` and `b` counts as one path.
+    pub fn vertex_connectivity(&self, a: NodeId, b: NodeId) -> usize {
+        if a == b || !self.contains_node(a) || !self.contains_node(b) {
+            return 0;
+        }
+
+        //Every node is split in (in, out) joined by a capacity 1 arc, so it is used at most once
+        let index: HashMap<NodeId, usize> = self
+            .nodes
+            .keys()
+            .enumerate()
+            .map(|(i, id)| (*id, i))
+            .collect();
+        let node_in = |id: NodeId| 2 * index[&id];
+        let node_out = |id: NodeId| 2 * index[&id] + 1;
+        let mut capacity: HashMap<(usize, usize), u32> = HashMap::new();
+        let mut arcs: HashMap<usize, Vec<usize>> = HashMap::new();
+        let mut add_arc = |from: usize, to: usize| {
+            *capacity.entry((from, to)).or_default() += 1;
+            capacity.entry((to, from)).or_default();
+            arcs.entry(from).or_default().push(to);
+            arcs.entry(to).or_default().push(from);
+        };
+
+        for node_id in self.nodes.keys() {
+            if *node_id != a && *node_id != b && self.forwards(*node_id, a) {
+                add_arc(node_in(*node_id), node_out(*node_id));
+            }
+        }
+        for (u, v) in self.edges() {
+            add_arc(node_out(u), node_in(v));
+            add_arc(node_out(v), node_in(u));
+        }
+
+        let (source, sink) = (node_out(a), node_in(b));
+        let mut flow = 0;
+        loop {
+            //BFS for an augmenting path in the residual graph
+            let mut previous: HashMap<usize, usize> = HashMap::new();
+            let mut queue = VecDeque::from([source]);
+            while let Some(current) = queue.pop_front() {
+                if current == sink {
+                    break;
+                }
+                for next in arcs.get(&current).into_iter().flatten() {
+                    if *next != source
+                        && !previous.contains_key(next)
+                        && capacity[&(current, *next)] > 0
+                    {
+                        previous.insert(*next, current);
+                        queue.push_back(*next);
+                    }
+                }
+            }
+            if !previous.contains_key(&sink) {
+                return flow;
+            }
+            let mut current = sink;
+            while current != source {
+                let prev = previous[&current];
+                *capacity.get_mut(&(prev, current)).expect("Arc in the graph") -= 1;
+                *capacity.get_mut(&(current, prev)).expect("Arc in the graph") += 1;
+                current = prev;
+            }
+            flow += 1;
+        }
+    }
+
+    /// Longest shortest path between any two nodes, going only through drones.
+    pub fn diameter(&self) -> DiameterReport {
+        let mut report = DiameterReport::default();
+        for node_id in self.nodes.keys() {
+            let distances = self.distances_from(*node_id);
+            let mut eccentricity = 0;
+            for (other, distance) in &distances {
+                if *distance > eccentricity {
+                    eccentricity = *distance;
+                }
+                if *distance > report.diameter {
+                    report.diameter = *distance;
+                    report.endpoints = Some((*node_id, *other));
+                }
+            }
+            report.eccentricity.insert(*node_id, eccentricity);
+            for other in self.nodes.keys() {
+                if node_id < other && !distances.contains_key(other) {
+                    report.unreachable_pairs.push((*node_id, *other));
+                }
+            }
+        }
+        report
+    }
+
+    /// Clients and servers that are cut off, sorted.
+    ///
+    /// A node is cut off if it has no drone neighbour, if it is a client that cannot reach every
+    /// server, or if it is a server that no client can reach. Without clients a server is only
+    /// cut off if it has no drone neighbour.
+    pub fn cut_off_nodes(&self) -> Vec<NodeId> {
+        let clients = self.nodes_of_type(NodeType::Client);
+        let servers = self.nodes_of_type(NodeType::Server);
+        let mut cut_off = BTreeSet::new();
+
+        for node_id in clients.iter().chain(&servers) {
+            if !self.edges[node_id]
+                .iter()
+                .any(|neighbour| self.node_type(*neighbour) == Some(NodeType::Drone))
+            {
+                cut_off.insert(*node_id);
+            }
+        }
+
+        if clients.is_empty() {
+            return cut_off.into_iter().collect();
+        }
+        let mut reached_servers: BTreeSet<NodeId> = BTreeSet::new();
+        for client in &clients {
+            let reachable = self.reachable_from(*client);
+            if !servers.iter().all(|server| reachable.contains(server)) {
+                cut_off.insert(*client);
+            }
+            reached_servers.extend(servers.iter().filter(|server| reachable.contains(*server)));
+        }
+        cut_off.extend(
+            servers
+                .iter()
+                .filter(|server| !reached_servers.contains(*server)),
+        );
+        cut_off.into_iter().collect()
+    }
+
+    /// Nodes that are cut off in `changed` but not in this graph, sorted.
+    pub fn newly_cut_off(&self, changed: &NetworkGraph) -> Vec<NodeId> {
+        let before = self.cut_off_nodes();
+        changed
+            .cut_off_nodes()
+            .into_iter()
+            .filter(|node_id| !before.contains(node_id))
+            .collect()
+    }
+
+    // Only drones forward packets, the start of a path sends them
+    fn forwards(&self, node_id: NodeId, start: NodeId) -> bool {
+        node_id == start || self.node_type(node_id) == Some(NodeType::Drone)
+    }
+
+    // BFS hops from `start` to every node reachable going only through drones
+    fn distances_from(&self, start: NodeId) -> BTreeMap<NodeId, usize> {
+        let mut distances = BTreeMap::new();
+        if !self.contains_node(start) {
+            return distances;
+        }
+        distances.insert(start, 0);
+        let mut queue = VecDeque::from([start]);
+        while let Some(node_id) = queue.pop_front() {
+            if !self.forwards(node_id, start) {
+                continue;
+            }
+            let distance = distances[&node_id] + 1;
+            for neighbour in &self.edges[&node_id] {
+                if !distances.contains_key(neighbour) {
+                    distances.insert(*neighbour, distance);
+                    queue.push_back(*neighbour);
+                }
+            }
+        }
+        distances
+    }
+
+    fn collect_paths(
+        &self,
+        to: NodeId,
+        max_hops: usize,
+        path: &mut Vec<NodeId>,
+        paths: &mut Vec<Vec<NodeId>>,
+    ) {
+        let last = *path.last().expect("The path starts with a node");
+        if last == to {
+            paths.push(path.clone());
+            return;
+        }
+        if path.len() > max_hops || !self.forwards(last, path[0]) {
+            return;
+        }
+        for neighbour in &self.edges[&last] {
+            if !path.contains(neighbour) {
+                path.push(*neighbour);
+                self.collect_paths(to, max_hops, path, paths);
+                path.pop();
+            }
+        }
+    }
+
+    fn low_link(&self, node_id: NodeId, parent: Option<NodeId>, search: &mut LowLink) {
+        let discovery = search.discovery.len();
+        search.discovery.insert(node_id, discovery);
+        search.low.insert(node_id, discovery);
+        let mut children = 0;
+
+        for neighbour in &self.edges[&node_id] {
+            if Some(*neighbour) == parent {
+                continue;
+            }
+            if let Some(neighbour_discovery) = search.discovery.get(neighbour).copied() {
+                let low = search.low[&node_id].min(neighbour_discovery);
+                search.low.insert(node_id, low);
+            } else {
+                children += 1;
+                self.low_link(*neighbour, Some(node_id), search);
+                let neighbour_low = search.low[neighbour];
+                let low = search.low[&node_id].min(neighbour_low);
+                search.low.insert(node_id, low);
+
+                if parent.is_some() && neighbour_low >= discovery {
+                    search.articulation_points.insert(node_id);
+                }
+                if neighbour_low > discovery {
+                    search
+                        .bridges
+                        .insert((node_id.min(*neighbour), node_id.max(*neighbour)));
+                }
+            }
+        }
+        if parent.is_none() && children > 1 {
+            search.articulation_points.insert(node_id);
+        }
+    }
+}
+
+impl From<&Config> for NetworkGraph {
+    fn from(config: &Config) -> Self {
+        Self::from_config(config)
+    }
+}
+
+// State of the DFS shared by articulation points and bridges
+#[derive(Default)]
+struct LowLink {
+    discovery: HashMap<NodeId, usize>,
+    low: HashMap<NodeId, usize>,
+    articulation_points: BTreeSet<NodeId>,
+    bridges: BTreeSet<(NodeId, NodeId)>,
+}
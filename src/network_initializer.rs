@@ -1,170 +1,280 @@
-//
-// use crate::Dronegowski;
-// use crossbeam_channel::{unbounded, Receiver, Sender};
-// use std::collections::{HashMap, HashSet};
-// use std::fs;
-// use std::thread;
-// use thiserror::Error;
-// use wg_2024::config::Config;
-// use wg_2024::controller::DroneCommand;
-// use wg_2024::drone::Drone;
-// use wg_2024::network::NodeId;
-// use wg_2024::packet::Packet;
-//
-// /// Parsing file  config.toml
-// pub fn parse_config(file: &str) -> Config {
-//     let file_str = fs::read_to_string(file).expect("error reading config file");
-//     println!("Parsing configuration file...");
-//     toml::from_str(&file_str).expect("Error occurred during config file parsing")
-// }
-//
-// #[test]
-// fn test_initialization() {
-//     let config = parse_config("tests/common/config.toml");
-//
-//     match validate_config(&config) {
-//         Ok(_) => println!("Config validation passed!"),
-//         Err(e) => {
-//             println!("Config validation failed: {e:?}");
-//             panic!("Validation failed.");
-//         }
-//     }
-//
-//     let (node_event_send, _node_event_recv) = unbounded();
-//     let mut controller_drones: HashMap<NodeId, Sender<DroneCommand>> = HashMap::new();
-//     let mut packet_channels: HashMap<NodeId, (Sender<Packet>, Receiver<Packet>)> = HashMap::new();
-//
-//     for drone in &config.drone {
-//         packet_channels.insert(drone.id, unbounded());
-//     }
-//     for client in &config.client {
-//         packet_channels.insert(client.id, unbounded());
-//     }
-//     for server in &config.server {
-//         packet_channels.insert(server.id, unbounded());
-//     }
-//     let mut handles = Vec::new();
-//
-//     for drone in config.drone {
-//         let (controller_drone_send, controller_drone_recv) = unbounded();
-//         controller_drones.insert(drone.id, controller_drone_send.clone());
-//         let node_event_send = node_event_send.clone();
-//
-//         let packet_recv = packet_channels[&drone.id].1.clone();
-//         let packet_send: HashMap<NodeId, Sender<Packet>> = drone
-//             .connected_node_ids
-//             .into_iter()
-//             .map(|id| (id, packet_channels[&id].0.clone()))
-//             .collect();
-//
-//         handles.push(thread::spawn(move || {
-//             let mut drone = Dronegowski::new(
-//                 drone.id,
-//                 node_event_send,
-//                 controller_drone_recv,
-//                 packet_recv,
-//                 packet_send,
-//                 drone.pdr,
-//             );
-//
-//             drone.run();
-//         }));
-//     }
-//
-//     // pretend a short execution time
-//     std::thread::sleep(std::time::Duration::from_secs(2));
-//
-//     // sending pdr update to all drones
-//     for sender in controller_drones.values() {
-//         sender
-//             .send(DroneCommand::SetPacketDropRate(0.3))
-//             .expect("Error occurred during PDR setting!");
-//     }
-//
-//     // sending crash command to all drones
-//     for sender in controller_drones.values() {
-//         sender
-//             .send(DroneCommand::Crash)
-//             .expect("Error occurred sending crash command");
-//     }
-//
-//     // wait all the drone threads
-//     while let Some(handle) = handles.pop() {
-//         handle
-//             .join()
-//             .expect("Error occured while exiting a drone");
-//     }
-// }
-//
-// #[derive(Debug, Error)]
-// pub enum ValidationError {
-//     #[error("The connection between node {0} and node {1} is not bidirectional.")]
-//     NotBidirectional(NodeId, NodeId),
-//     #[error("The graph is not connected.")]
-//     NotConnected,
-//     // Other errors needed can be added here.
-// }
-//
-// pub fn validate_config(config: &Config) -> Result<(), ValidationError> {
-//     let mut graph: HashMap<NodeId, HashSet<NodeId>> = HashMap::new();
-//
-//     // building the graph
-//     for drone in &config.drone {
-//         for &connected_id in &drone.connected_node_ids {
-//             graph.entry(drone.id).or_default().insert(connected_id);
-//             graph.entry(connected_id).or_default(); // insert the neighbour node in the graph if not there
-//         }
-//     }
-//     for client in &config.client {
-//         for &connected_id in &client.connected_drone_ids {
-//             graph.entry(client.id).or_default().insert(connected_id);
-//             graph.entry(connected_id).or_default();
-//         }
-//     }
-//     for server in &config.server {
-//         for &connected_id in &server.connected_drone_ids {
-//             graph.entry(server.id).or_default().insert(connected_id);
-//             graph.entry(connected_id).or_default();
-//         }
-//     }
-//
-//     // bidirectional links checking
-//     for (&node, connections) in &graph {
-//         for &connected_node in connections {
-//             //checking of the opposite link
-//             if !graph
-//                 .get(&connected_node)
-//                 .map_or(false, |set| set.contains(&node))
-//             {
-//                 return Err(ValidationError::NotBidirectional(node, connected_node));
-//             }
-//         }
-//     }
-//
-//     // connected graph checking
-//     let all_nodes: HashSet<_> = graph.keys().copied().collect();
-//     let mut visited = HashSet::new();
-//     // takes any node as starting point
-//     let start_node = *all_nodes.iter().next().unwrap();
-//
-//     dfs(start_node, &graph, &mut visited);
-//
-//     if visited != all_nodes {
-//         return Err(ValidationError::NotConnected);
-//     }
-//
-//     Ok(())
-// }
-//
-// // DFS function used in the connected graph checking
-// fn dfs(node: NodeId, graph: &HashMap<NodeId, HashSet<NodeId>>, visited: &mut HashSet<NodeId>) {
-//     if visited.contains(&node) {
-//         return;
-//     }
-//     visited.insert(node);
-//     if let Some(neighbors) = graph.get(&node) {
-//         for &neighbor in neighbors {
-//             dfs(neighbor, graph, visited);
-//         }
-//     }
-// }
+use crate::{DronegowskiBuilder, ShutdownReport, StopToken};
+use crossbeam_channel::{unbounded, Receiver, Sender};
+use std::collections::HashMap;
+use std::fs;
+use std::path::Path;
+use std::thread::{self, JoinHandle};
+use thiserror::Error;
+use wg_2024::config::Config;
+use wg_2024::controller::{DroneCommand, DroneEvent};
+use wg_2024::network::NodeId;
+use wg_2024::packet::Packet;
+
+mod validation;
+pub use validation::*;
+
+#[derive(Debug, Error)]
+pub enum NetworkInitializerError {
+    #[error("Error reading the config file: {0}")]
+    Io(#[from] std::io::Error),
+    #[error("Error parsing the config file: {0}")]
+    Parse(#[from] toml::de::Error),
+    #[error("The config is not valid: {0:?}")]
+    Validation(Vec<ValidationError>),
+    #[error("The thread of drone {0} panicked.")]
+    DronePanicked(NodeId),
+}
+
+/// Parsing config.toml file.
+pub fn parse_config<P: AsRef<Path>>(file: P) -> Result<Config, NetworkInitializerError> {
+    let file_str = fs::read_to_string(file)?;
+    Ok(toml::from_str(&file_str)?)
+}
+
+/// Derives the seed of a drone from the master seed of the network (SplitMix64 step).
+pub fn drone_seed(master_seed: u64, drone_id: NodeId) -> u64 {
+    let mut z =
+        master_seed.wrapping_add(0x9E37_79B9_7F4A_7C15_u64.wrapping_mul(drone_id as u64 + 1));
+    z = (z ^ (z >> 30)).wrapping_mul(0xBF58_476D_1CE4_E5B9);
+    z = (z ^ (z >> 27)).wrapping_mul(0x94D0_49BB_1331_11EB);
+    z ^ (z >> 31)
+}
+
+/// Boots a whole network from a `Config`, spawning one `Dronegowski` thread per drone.
+pub struct NetworkInitializer {
+    config: Config,
+    seed: Option<u64>, //Master seed, every drone gets its own seed derived from it
+}
+
+impl NetworkInitializer {
+    pub fn new(config: Config) -> Self {
+        Self { config, seed: None }
+    }
+
+    /// Makes the drops of the whole network reproducible.
+    #[must_use]
+    pub fn with_seed(mut self, seed: u64) -> Self {
+        self.seed = Some(seed);
+        self
+    }
+
+    pub fn from_file<P: AsRef<Path>>(file: P) -> Result<Self, NetworkInitializerError> {
+        Ok(Self::new(parse_config(file)?))
+    }
+
+    pub fn config(&self) -> &Config {
+        &self.config
+    }
+
+    /// Validates the config, then creates every channel of the network and runs all the drones.
+    pub fn start(self) -> Result<NetworkHandle, NetworkInitializerError> {
+        validate_config(&self.config).map_err(NetworkInitializerError::Validation)?;
+        let config = self.config;
+        let master_seed = self.seed;
+        let (event_send, event_recv) = unbounded();
+        let mut packet_channels: HashMap<NodeId, (Sender<Packet>, Receiver<Packet>)> =
+            HashMap::new();
+
+        for drone in &config.drone {
+            packet_channels.insert(drone.id, unbounded());
+        }
+        for client in &config.client {
+            packet_channels.insert(client.id, unbounded());
+        }
+        for server in &config.server {
+            packet_channels.insert(server.id, unbounded());
+        }
+
+        let mut command_send = HashMap::new();
+        let mut neighbours = HashMap::new();
+        let mut stop_tokens = HashMap::new();
+        let mut handles = HashMap::new();
+
+        //Running all the drones
+        for drone in &config.drone {
+            let (controller_drone_send, controller_drone_recv) = unbounded();
+            command_send.insert(drone.id, controller_drone_send);
+            neighbours.insert(drone.id, drone.connected_node_ids.clone());
+
+            let stop = StopToken::new();
+            stop_tokens.insert(drone.id, stop.clone());
+
+            let event_send = event_send.clone();
+            let packet_recv = packet_channels[&drone.id].1.clone();
+            let packet_send: HashMap<NodeId, Sender<Packet>> = drone
+                .connected_node_ids
+                .iter()
+                .map(|id| (*id, packet_channels[id].0.clone()))
+                .collect();
+
+            let handle = thread::Builder::new()
+                .name(format!("drone-{}", drone.id))
+                .spawn({
+                    let (id, pdr) = (drone.id, drone.pdr);
+                    move || {
+                        let mut builder = DronegowskiBuilder::new(
+                            id,
+                            event_send,
+                            controller_drone_recv,
+                            packet_recv,
+                            packet_send,
+                        )
+                        .pdr(pdr)
+                        .stop_token(stop);
+                        if let Some(seed) = master_seed {
+                            builder = builder.seed(drone_seed(seed, id));
+                        }
+                        let mut drone = builder.build().unwrap_or_else(|error| panic!("{error}"));
+                        drone.run_with_report()
+                    }
+                })?;
+            handles.insert(drone.id, handle);
+        }
+
+        let packet_send = packet_channels
+            .iter()
+            .map(|(id, (sender, _))| (*id, sender.clone()))
+            .collect();
+        //Only clients and servers keep their receiver, drones own theirs
+        let packet_recv = packet_channels
+            .into_iter()
+            .filter(|(id, _)| !command_send.contains_key(id))
+            .map(|(id, (_, receiver))| (id, receiver))
+            .collect();
+
+        Ok(NetworkHandle {
+            config,
+            command_send,
+            packet_send,
+            packet_recv,
+            event_recv,
+            neighbours,
+            stop_tokens,
+            handles,
+        })
+    }
+}
+
+/// Handle over a running network returned by `NetworkInitializer::start`.
+pub struct NetworkHandle {
+    config: Config,                                      //Config the network was started from
+    command_send: HashMap<NodeId, Sender<DroneCommand>>, //Command channel of every drone
+    packet_send: HashMap<NodeId, Sender<Packet>>,        //Packet channel of every node
+    packet_recv: HashMap<NodeId, Receiver<Packet>>,      //Packet receiver of clients and servers
+    event_recv: Receiver<DroneEvent>,                    //Events sent by all the drones
+    neighbours: HashMap<NodeId, Vec<NodeId>>,            //Current neighbours of every drone
+    stop_tokens: HashMap<NodeId, StopToken>,             //Local stop of every drone
+    handles: HashMap<NodeId, JoinHandle<ShutdownReport>>,
+}
+
+impl NetworkHandle {
+    pub fn config(&self) -> &Config {
+        &self.config
+    }
+
+    pub fn command_send(&self) -> &HashMap<NodeId, Sender<DroneCommand>> {
+        &self.command_send
+    }
+
+    pub fn packet_send(&self) -> &HashMap<NodeId, Sender<Packet>> {
+        &self.packet_send
+    }
+
+    pub fn packet_recv(&self) -> &HashMap<NodeId, Receiver<Packet>> {
+        &self.packet_recv
+    }
+
+    pub fn event_recv(&self) -> &Receiver<DroneEvent> {
+        &self.event_recv
+    }
+
+    pub fn drone_ids(&self) -> Vec<NodeId> {
+        self.handles.keys().copied().collect()
+    }
+
+    pub fn send_command(&self, drone_id: NodeId, command: DroneCommand) -> bool {
+        self.command_send
+            .get(&drone_id)
+            .is_some_and(|sender| sender.send(command).is_ok())
+    }
+
+    pub fn stop_token(&self, drone_id: NodeId) -> Option<&StopToken> {
+        self.stop_tokens.get(&drone_id)
+    }
+
+    // Drops the sender kept by the handle, so a crashing drone is not kept alive by it
+    pub(crate) fn remove_packet_send(&mut self, node_id: NodeId) -> Option<Sender<Packet>> {
+        self.packet_send.remove(&node_id)
+    }
+
+    // Keeps the neighbours used by shutdown in sync with the live topology
+    pub(crate) fn set_neighbours(&mut self, drone_id: NodeId, neighbours: Vec<NodeId>) {
+        if self.neighbours.contains_key(&drone_id) {
+            self.neighbours.insert(drone_id, neighbours);
+        }
+    }
+
+    /// Waits for every drone thread to terminate.
+    pub fn join(self) -> Result<HashMap<NodeId, ShutdownReport>, NetworkInitializerError> {
+        NetworkHandle::join_all(self.handles)
+    }
+
+    /// Stops every drone through its stop token and waits for all of them to terminate.
+    ///
+    /// Unlike `shutdown`, this does not depend on the senders held by other nodes: every drone
+    /// drains its channel, answering the in-flight fragments with a Nack, and returns its report.
+    pub fn stop(self) -> Result<HashMap<NodeId, ShutdownReport>, NetworkInitializerError> {
+        for stop in self.stop_tokens.values() {
+            stop.stop();
+        }
+        NetworkHandle::join_all(self.handles)
+    }
+
+    /// Crashes every drone and waits for all of them to terminate.
+    ///
+    /// Drones only complete crashing once every sender of their packet channel is dropped,
+    /// so the links between drones are removed before the crash command is sent.
+    pub fn shutdown(self) -> Result<HashMap<NodeId, ShutdownReport>, NetworkInitializerError> {
+        for (id, sender) in &self.command_send {
+            for neighbour in self.neighbours.get(id).into_iter().flatten() {
+                let _ = sender.send(DroneCommand::RemoveSender(*neighbour));
+            }
+            let _ = sender.send(DroneCommand::Crash);
+        }
+
+        let NetworkHandle {
+            command_send,
+            packet_send,
+            packet_recv,
+            handles,
+            ..
+        } = self;
+        drop(packet_send);
+        drop(packet_recv);
+        drop(command_send);
+
+        NetworkHandle::join_all(handles)
+    }
+
+    fn join_all(
+        handles: HashMap<NodeId, JoinHandle<ShutdownReport>>,
+    ) -> Result<HashMap<NodeId, ShutdownReport>, NetworkInitializerError> {
+        let mut reports = HashMap::new();
+        let mut panicked = None;
+        for (id, handle) in handles {
+            match handle.join() {
+                Ok(report) => {
+                    reports.insert(id, report);
+                }
+                Err(_) => {
+                    log::error!("Drone {id} panicked");
+                    panicked = Some(id);
+                }
+            }
+        }
+        match panicked {
+            Some(id) => Err(NetworkInitializerError::DronePanicked(id)),
+            None => Ok(reports),
+        }
+    }
+}
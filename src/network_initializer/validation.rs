@@ -0,0 +1,146 @@
+use crate::NetworkGraph;
+use std::collections::{HashMap, HashSet};
+use thiserror::Error;
+use wg_2024::config::Config;
+use wg_2024::network::NodeId;
+use wg_2024::packet::NodeType;
+
+#[derive(Debug, Clone, PartialEq, Error)]
+pub enum ValidationError {
+    #[error("The id {0} is used by more than one node.")]
+    DuplicateNodeId(NodeId),
+    #[error("Drone {0} lists itself as neighbour.")]
+    SelfNeighbour(NodeId),
+    #[error("Drone {0} has PDR {1} which is outside [0, 1].")]
+    PdrOutOfBounds(NodeId, f32),
+    #[error("Client {0} is connected to {1} drones, it must be connected to one or two.")]
+    InvalidClientDegree(NodeId, usize),
+    #[error("Server {0} is connected to {1} drones, it must be connected to at least two.")]
+    InvalidServerDegree(NodeId, usize),
+    #[error("Node {0} is connected to node {1} which is not a drone.")]
+    NotConnectedToDrone(NodeId, NodeId),
+    #[error("Node {0} is connected to node {1} which is not defined.")]
+    UnknownNeighbour(NodeId, NodeId),
+    #[error("The connection between node {0} and node {1} is not bidirectional.")]
+    NotBidirectional(NodeId, NodeId),
+    #[error("The drones {0:?} are not reachable from the rest of the drone network.")]
+    NotConnected(Vec<NodeId>),
+}
+
+/// Checks the config against the AP protocol rules, reporting every violation found.
+pub fn validate_config(config: &Config) -> Result<(), Vec<ValidationError>> {
+    let mut errors = Vec::new();
+
+    let declared: Vec<(NodeId, NodeType, &Vec<NodeId>)> = config
+        .drone
+        .iter()
+        .map(|drone| (drone.id, NodeType::Drone, &drone.connected_node_ids))
+        .chain(
+            config
+                .client
+                .iter()
+                .map(|client| (client.id, NodeType::Client, &client.connected_drone_ids)),
+        )
+        .chain(
+            config
+                .server
+                .iter()
+                .map(|server| (server.id, NodeType::Server, &server.connected_drone_ids)),
+        )
+        .collect();
+
+    //Duplicated ids
+    let mut nodes: HashMap<NodeId, (NodeType, &Vec<NodeId>)> = HashMap::new();
+    let mut duplicates = HashSet::new();
+    for (id, node_type, neighbours) in &declared {
+        if nodes.insert(*id, (*node_type, neighbours)).is_some() && duplicates.insert(*id) {
+            errors.push(ValidationError::DuplicateNodeId(*id));
+        }
+    }
+
+    //Drone rules
+    for drone in &config.drone {
+        if drone.connected_node_ids.contains(&drone.id) {
+            errors.push(ValidationError::SelfNeighbour(drone.id));
+        }
+        if !(0.0..=1.0).contains(&drone.pdr) {
+            errors.push(ValidationError::PdrOutOfBounds(drone.id, drone.pdr));
+        }
+    }
+
+    //Client and server degree rules
+    for client in &config.client {
+        let degree = distinct(&client.connected_drone_ids);
+        if let Err(error) = check_degree(client.id, NodeType::Client, degree) {
+            errors.push(error);
+        }
+    }
+    for server in &config.server {
+        let degree = distinct(&server.connected_drone_ids);
+        if let Err(error) = check_degree(server.id, NodeType::Server, degree) {
+            errors.push(error);
+        }
+    }
+
+    //Links checking
+    for (id, node_type, neighbours) in &declared {
+        for neighbour in neighbours.iter() {
+            if *neighbour == *id && *node_type == NodeType::Drone {
+                continue; //Already reported as SelfNeighbour
+            }
+            match nodes.get(neighbour) {
+                None => errors.push(ValidationError::UnknownNeighbour(*id, *neighbour)),
+                Some((neighbour_type, neighbour_links)) => {
+                    if *node_type != NodeType::Drone && *neighbour_type != NodeType::Drone {
+                        errors.push(ValidationError::NotConnectedToDrone(*id, *neighbour));
+                    } else if !neighbour_links.contains(id) {
+                        errors.push(ValidationError::NotBidirectional(*id, *neighbour));
+                    }
+                }
+            }
+        }
+    }
+
+    //The drones must stay connected without clients and servers
+    let unreachable = unreachable_drones(config);
+    if !unreachable.is_empty() {
+        errors.push(ValidationError::NotConnected(unreachable));
+    }
+
+    if errors.is_empty() {
+        Ok(())
+    } else {
+        Err(errors)
+    }
+}
+
+/// Degree rule of the protocol for a node linked to `degree` drones: one or two for a client,
+/// at least two for a server.
+pub fn check_degree(id: NodeId, node_type: NodeType, degree: usize) -> Result<(), ValidationError> {
+    match node_type {
+        NodeType::Client if !(1..=2).contains(&degree) => {
+            Err(ValidationError::InvalidClientDegree(id, degree))
+        }
+        NodeType::Server if degree < 2 => Err(ValidationError::InvalidServerDegree(id, degree)),
+        _ => Ok(()),
+    }
+}
+
+fn distinct(ids: &[NodeId]) -> usize {
+    ids.iter().collect::<HashSet<_>>().len()
+}
+
+// Drones not reached from the first one, only drones forward packets so the search stays on
+// the drone-to-drone links
+fn unreachable_drones(config: &Config) -> Vec<NodeId> {
+    let Some(start) = config.drone.first() else {
+        return Vec::new();
+    };
+    let graph = NetworkGraph::from_config(config);
+    let reachable = graph.reachable_from(start.id);
+    graph
+        .nodes_of_type(NodeType::Drone)
+        .into_iter()
+        .filter(|id| !reachable.contains(id))
+        .collect()
+}
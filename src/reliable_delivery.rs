@@ -0,0 +1,448 @@
+use crate::{fragment, NetworkGraph};
+use std::collections::{BTreeMap, VecDeque};
+use std::time::{Duration, Instant};
+use thiserror::Error;
+use wg_2024::network::{NodeId, SourceRoutingHeader};
+use wg_2024::packet::{Ack, Nack, NackType, Packet, PacketType};
+
+pub const DEFAULT_MAX_RETRIES: u32 = 16;
+pub const DEFAULT_ACK_TIMEOUT: Duration = Duration::from_millis(500);
+pub const DEFAULT_SESSION_TIMEOUT: Duration = Duration::from_secs(10);
+
+#[derive(Debug, Clone, PartialEq, Error)]
+pub enum DeliveryError {
+    #[error("No route to node {0}, even after flooding the network.")]
+    NoRoute(NodeId),
+    #[error("Node {0} is a drone, drones cannot be the destination of a message.")]
+    DestinationIsDrone(NodeId),
+    #[error("Fragment {1} of session {0} was sent more than {2} times.")]
+    RetriesExhausted(u64, u64, u32),
+    #[error("Session {0} was not delivered within {1:?}.")]
+    TimedOut(u64, Duration),
+}
+
+/// Outcome of a message, see `ReliableSender::take_events`.
+#[derive(Debug, Clone, PartialEq)]
+pub enum DeliveryEvent {
+    Delivered(u64),
+    Failed(u64, DeliveryError),
+}
+
+/// Counters of a `ReliableSender`.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub struct DeliveryStats {
+    pub fragments_sent: u64,  //First transmissions
+    pub retransmissions: u64, //Fragments sent again after a Nack or a missing Ack
+    pub reroutes: u64,        //Sessions moved to a new route after a broken link
+    pub floods: u64,          //Floods asked through `needs_flood`
+    pub delivered: u64,
+    pub failed: u64,
+}
+
+struct OutgoingFragment {
+    packet: Packet,
+    retries: u32,
+    sent_at: Option<Instant>, //None while waiting for a route
+}
+
+struct OutgoingSession {
+    destination: NodeId,
+    route: Option<SourceRoutingHeader>,
+    fragments: BTreeMap<u64, OutgoingFragment>, //Fragments not acknowledged yet
+    started: Instant,
+}
+
+/// Delivers messages over the unreliable drone network, without doing any I/O itself.
+///
+/// Every fragment is tracked by `(session_id, fragment_index)` until its Ack. The node feeds
+/// the Acks and Nacks it receives to `handle_packet`, sends the packets of `take_outgoing`,
+/// floods the network when `needs_flood` says so and then calls `reroute`. Reactions to the
+/// Nacks:
+/// - `Dropped`: the fragment is sent again on the same route;
+/// - `ErrorInRouting`: the broken link is removed from the topology and the session moves to a
+///   new route, or waits for a flood if there is none;
+/// - `UnexpectedRecipient`: the route is stale, the session waits for a flood;
+/// - `DestinationIsDrone`: the message fails.
+///
+/// A message fails once a fragment is sent more than `max_retries` times or the whole session
+/// takes longer than `session_timeout`. Fragments without an Ack for `ack_timeout` are resent.
+pub struct ReliableSender {
+    id: NodeId,
+    sessions: BTreeMap<u64, OutgoingSession>,
+    next_session_id: u64,
+    outgoing: VecDeque<Packet>,
+    events: VecDeque<DeliveryEvent>,
+    needs_flood: bool,
+    max_retries: u32,
+    ack_timeout: Duration,
+    session_timeout: Duration,
+    stats: DeliveryStats,
+}
+
+impl ReliableSender {
+    pub fn new(id: NodeId) -> Self {
+        Self {
+            id,
+            sessions: BTreeMap::new(),
+            next_session_id: 1,
+            outgoing: VecDeque::new(),
+            events: VecDeque::new(),
+            needs_flood: false,
+            max_retries: DEFAULT_MAX_RETRIES,
+            ack_timeout: DEFAULT_ACK_TIMEOUT,
+            session_timeout: DEFAULT_SESSION_TIMEOUT,
+            stats: DeliveryStats::default(),
+        }
+    }
+
+    #[must_use]
+    pub fn with_max_retries(mut self, max_retries: u32) -> Self {
+        self.max_retries = max_retries;
+        self
+    }
+
+    #[must_use]
+    pub fn with_ack_timeout(mut self, ack_timeout: Duration) -> Self {
+        self.ack_timeout = ack_timeout;
+        self
+    }
+
+    #[must_use]
+    pub fn with_session_timeout(mut self, session_timeout: Duration) -> Self {
+        self.session_timeout = session_timeout;
+        self
+    }
+
+    pub fn id(&self) -> NodeId {
+        self.id
+    }
+
+    pub fn stats(&self) -> DeliveryStats {
+        self.stats
+    }
+
+    /// Sessions still waiting for some Ack.
+    pub fn in_progress(&self) -> Vec<u64> {
+        self.sessions.keys().copied().collect()
+    }
+
+    pub fn is_idle(&self) -> bool {
+        self.sessions.is_empty() && self.outgoing.is_empty()
+    }
+
+    /// Starts delivering a payload, returning its session id.
+    pub fn send(&mut self, destination: NodeId, payload: &[u8], topology: &NetworkGraph) -> u64 {
+        let session_id = self.next_session_id;
+        self.next_session_id += 1;
+        let route = route(self.id, destination, topology);
+        let now = Instant::now();
+        let mut fragments = BTreeMap::new();
+        for fragment in fragment(payload) {
+            let index = fragment.fragment_index;
+            let packet = Packet {
+                pack_type: PacketType::MsgFragment(fragment),
+                //Without a route the hops stay empty until `reroute`
+                routing_header: route.clone().unwrap_or(SourceRoutingHeader {
+                    hop_index: 1,
+                    hops: Vec::new(),
+                }),
+                session_id,
+            };
+            if route.is_some() {
+                self.outgoing.push_back(packet.clone());
+                self.stats.fragments_sent += 1;
+            }
+            fragments.insert(
+                index,
+                OutgoingFragment {
+                    packet,
+                    retries: 0,
+                    sent_at: route.as_ref().map(|_| now),
+                },
+            );
+        }
+        if route.is_none() {
+            self.ask_flood();
+        }
+        self.sessions.insert(
+            session_id,
+            OutgoingSession {
+                destination,
+                route,
+                fragments,
+                started: now,
+            },
+        );
+        session_id
+    }
+
+    /// Handles an Ack or a Nack of a session in progress, returning false for any other packet.
+    ///
+    /// Links reported broken by `ErrorInRouting` are removed from `topology`.
+    pub fn handle_packet(&mut self, packet: &Packet, topology: &mut NetworkGraph) -> bool {
+        if !self.sessions.contains_key(&packet.session_id) {
+            return false;
+        }
+        match packet.pack_type {
+            PacketType::Ack(ref ack) => {
+                self.acknowledge(packet.session_id, ack.fragment_index);
+                true
+            }
+            PacketType::Nack(ref nack) => {
+                self.handle_nack(packet, nack, topology);
+                true
+            }
+            _ => false,
+        }
+    }
+
+    /// Reacts to a packet that could not even reach the first hop, as if the first link
+    /// were reported broken.
+    pub fn handle_send_error(&mut self, packet: &Packet, topology: &mut NetworkGraph) {
+        let hops = &packet.routing_header.hops;
+        if let (Some(from), Some(to)) = (hops.first(), hops.get(1)) {
+            topology.remove_edge(*from, *to);
+        }
+        if let PacketType::MsgFragment(ref fragment) = packet.pack_type {
+            self.move_session(packet.session_id, fragment.fragment_index, topology);
+        }
+    }
+
+    /// Routes again the sessions waiting for a flood, once the topology is learned again.
+    /// Those that still have no route fail.
+    pub fn reroute(&mut self, topology: &NetworkGraph) {
+        self.needs_flood = false;
+        let waiting: Vec<u64> = self
+            .sessions
+            .iter()
+            .filter(|(_, session)| {
+                session.route.is_none()
+                    || session
+                        .fragments
+                        .values()
+                        .any(|fragment| fragment.sent_at.is_none())
+            })
+            .map(|(session_id, _)| *session_id)
+            .collect();
+        for session_id in waiting {
+            let destination = self.sessions[&session_id].destination;
+            match route(self.id, destination, topology) {
+                Some(route) => self.resend_waiting(session_id, route),
+                None => self.fail(session_id, DeliveryError::NoRoute(destination)),
+            }
+        }
+    }
+
+    /// Resends the fragments without an Ack for too long and fails the sessions over budget.
+    pub fn check_timeouts(&mut self) {
+        let now = Instant::now();
+        let session_ids: Vec<u64> = self.sessions.keys().copied().collect();
+        for session_id in session_ids {
+            let session = &self.sessions[&session_id];
+            if now.duration_since(session.started) > self.session_timeout {
+                self.fail(
+                    session_id,
+                    DeliveryError::TimedOut(session_id, self.session_timeout),
+                );
+                continue;
+            }
+            let late: Vec<u64> = session
+                .fragments
+                .iter()
+                .filter(|(_, fragment)| {
+                    fragment
+                        .sent_at
+                        .is_some_and(|sent_at| now.duration_since(sent_at) >= self.ack_timeout)
+                })
+                .map(|(index, _)| *index)
+                .collect();
+            for index in late {
+                self.retransmit(session_id, index);
+            }
+        }
+    }
+
+    /// Packets to send, in order.
+    pub fn take_outgoing(&mut self) -> Vec<Packet> {
+        self.outgoing.drain(..).collect()
+    }
+
+    /// Whether some session has no route left: the node should flood and call `reroute`.
+    pub fn needs_flood(&self) -> bool {
+        self.needs_flood
+    }
+
+    pub fn take_events(&mut self) -> Vec<DeliveryEvent> {
+        self.events.drain(..).collect()
+    }
+
+    fn acknowledge(&mut self, session_id: u64, index: u64) {
+        let Some(session) = self.sessions.get_mut(&session_id) else {
+            return;
+        };
+        session.fragments.remove(&index);
+        if session.fragments.is_empty() {
+            self.sessions.remove(&session_id);
+            self.stats.delivered += 1;
+            self.events.push_back(DeliveryEvent::Delivered(session_id));
+        }
+    }
+
+    fn handle_nack(&mut self, packet: &Packet, nack: &Nack, topology: &mut NetworkGraph) {
+        let session_id = packet.session_id;
+        match nack.nack_type {
+            NackType::Dropped => self.retransmit(session_id, nack.fragment_index),
+            NackType::ErrorInRouting(next_hop) => {
+                //The Nack comes back from the drone that could not reach `next_hop`
+                if let Some(drone_id) = packet.routing_header.hops.first() {
+                    topology.remove_edge(*drone_id, next_hop);
+                }
+                self.move_session(session_id, nack.fragment_index, topology);
+            }
+            NackType::UnexpectedRecipient(_) => {
+                self.wait_for_route(session_id, nack.fragment_index);
+            }
+            NackType::DestinationIsDrone => {
+                let destination = self.sessions[&session_id].destination;
+                self.fail(session_id, DeliveryError::DestinationIsDrone(destination));
+            }
+        }
+    }
+
+    // Moves a session to a new route after a broken link and resends the fragment that hit it
+    fn move_session(&mut self, session_id: u64, index: u64, topology: &NetworkGraph) {
+        let Some(session) = self.sessions.get_mut(&session_id) else {
+            return;
+        };
+        match route(self.id, session.destination, topology) {
+            Some(route) => {
+                if session.route.as_ref() != Some(&route) {
+                    session.route = Some(route);
+                    self.stats.reroutes += 1;
+                }
+                self.retransmit(session_id, index);
+            }
+            None => self.wait_for_route(session_id, index),
+        }
+    }
+
+    fn wait_for_route(&mut self, session_id: u64, index: u64) {
+        let Some(session) = self.sessions.get_mut(&session_id) else {
+            return;
+        };
+        session.route = None;
+        if let Some(fragment) = session.fragments.get_mut(&index) {
+            fragment.sent_at = None;
+        }
+        self.ask_flood();
+    }
+
+    fn ask_flood(&mut self) {
+        if !self.needs_flood {
+            self.needs_flood = true;
+            self.stats.floods += 1;
+        }
+    }
+
+    fn resend_waiting(&mut self, session_id: u64, route: SourceRoutingHeader) {
+        let Some(session) = self.sessions.get_mut(&session_id) else {
+            return;
+        };
+        session.route = Some(route);
+        let waiting: Vec<u64> = session
+            .fragments
+            .iter()
+            .filter(|(_, fragment)| fragment.sent_at.is_none())
+            .map(|(index, _)| *index)
+            .collect();
+        for index in waiting {
+            self.retransmit(session_id, index);
+        }
+    }
+
+    // Sends a fragment again on the current route of its session, within the retry budget
+    fn retransmit(&mut self, session_id: u64, index: u64) {
+        let max_retries = self.max_retries;
+        let Some(session) = self.sessions.get_mut(&session_id) else {
+            return;
+        };
+        let Some(fragment) = session.fragments.get_mut(&index) else {
+            return;
+        };
+        let Some(route) = session.route.clone() else {
+            fragment.sent_at = None;
+            self.ask_flood();
+            return;
+        };
+        let first_send = fragment.packet.routing_header.hops.is_empty();
+        if !first_send {
+            if fragment.retries >= max_retries {
+                self.fail(
+                    session_id,
+                    DeliveryError::RetriesExhausted(session_id, index, max_retries),
+                );
+                return;
+            }
+            fragment.retries += 1;
+        }
+        fragment.packet.routing_header = route;
+        fragment.sent_at = Some(Instant::now());
+        self.outgoing.push_back(fragment.packet.clone());
+        if first_send {
+            self.stats.fragments_sent += 1;
+        } else {
+            self.stats.retransmissions += 1;
+        }
+    }
+
+    fn fail(&mut self, session_id: u64, error: DeliveryError) {
+        if self.sessions.remove(&session_id).is_some() {
+            //Fragments of the session still queued are not sent anymore
+            self.outgoing
+                .retain(|packet| packet.session_id != session_id);
+            self.stats.failed += 1;
+            self.events
+                .push_back(DeliveryEvent::Failed(session_id, error));
+        }
+    }
+}
+
+fn route(
+    from: NodeId,
+    destination: NodeId,
+    topology: &NetworkGraph,
+) -> Option<SourceRoutingHeader> {
+    if from == destination {
+        return None;
+    }
+    Some(SourceRoutingHeader {
+        hop_index: 1,
+        hops: topology.shortest_path(from, destination)?,
+    })
+}
+
+/// Ack of a received fragment, on the reversed route it came from and ready to be sent to
+/// `hops[1]`. None if the packet is not a fragment.
+pub fn ack_for(packet: &Packet) -> Option<Packet> {
+    let PacketType::MsgFragment(ref fragment) = packet.pack_type else {
+        return None;
+    };
+    let hop_index = packet.routing_header.hop_index;
+    Some(Packet {
+        pack_type: PacketType::Ack(Ack {
+            fragment_index: fragment.fragment_index,
+        }),
+        routing_header: SourceRoutingHeader {
+            hop_index: 1,
+            hops: packet
+                .routing_header
+                .hops
+                .iter()
+                .take(hop_index + 1)
+                .rev()
+                .copied()
+                .collect(),
+        },
+        session_id: packet.session_id,
+    })
+}
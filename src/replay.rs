@@ -0,0 +1,285 @@
+use crate::{
+    read_event_log, EventKind, EventRecord, EventRecorderError, NetworkInitializer,
+    NetworkInitializerError, SimulationController, SimulationControllerError,
+};
+use std::collections::BTreeMap;
+use std::path::Path;
+use std::time::{Duration, Instant};
+use thiserror::Error;
+use wg_2024::config::Config;
+use wg_2024::network::NodeId;
+
+/// Time given to the network to settle after the last input, if not set.
+pub const DEFAULT_SETTLE_TIME: Duration = Duration::from_millis(500);
+
+#[derive(Debug, Error)]
+pub enum ReplayError {
+    #[error("Error starting or stopping the network: {0}")]
+    Network(#[from] NetworkInitializerError),
+    #[error("Error reading the event log: {0}")]
+    EventLog(#[from] EventRecorderError),
+    #[error("Record {0} of the event log cannot be replayed: {1}")]
+    Input(usize, SimulationControllerError),
+    #[error("Record {0} of the event log is an input without packet or command")]
+    MalformedInput(usize),
+}
+
+/// First event of a drone that differs between the recording and the replay.
+#[derive(Debug, Clone, PartialEq)]
+pub struct Divergence {
+    pub drone: Option<NodeId>,
+    pub index: usize,                  //Position in the events of the drone
+    pub expected: Option<EventRecord>, //None if the replay has more events than the recording
+    pub actual: Option<EventRecord>,   //None if the replay has fewer events than the recording
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct ReplayReport {
+    pub inputs_replayed: usize,
+    pub expected_events: usize,
+    pub actual_events: usize,
+    pub divergence: Option<Divergence>,
+}
+
+impl ReplayReport {
+    pub fn is_identical(&self) -> bool {
+        self.divergence.is_none()
+    }
+}
+
+/// Rebuilds a network from its `Config` and drives it with the packets injected and the commands
+/// applied in a recorded event log, keeping their order and relative timing.
+///
+/// The `DroneEvent`s of every drone are then compared with the recorded ones, see
+/// `first_divergence`. Drones run on their own threads, so only the events of the same drone are
+/// compared with each other. A network with drops must be replayed with the seed it was recorded
+/// with.
+pub struct ReplayEngine {
+    config: Config,
+    records: Vec<EventRecord>,
+    seed: Option<u64>,
+    settle_time: Duration,
+}
+
+impl ReplayEngine {
+    pub fn new(config: Config, records: Vec<EventRecord>) -> Self {
+        Self {
+            config,
+            records,
+            seed: None,
+            settle_time: DEFAULT_SETTLE_TIME,
+        }
+    }
+
+    pub fn from_log<P: AsRef<Path>>(config: Config, log: P) -> Result<Self, ReplayError> {
+        Ok(Self::new(config, read_event_log(log)?))
+    }
+
+    /// Master seed of the network, see `NetworkInitializer::with_seed`.
+    #[must_use]
+    pub fn with_seed(mut self, seed: u64) -> Self {
+        self.seed = Some(seed);
+        self
+    }
+
+    /// Time to wait for the last events after the last input.
+    #[must_use]
+    pub fn with_settle_time(mut self, settle_time: Duration) -> Self {
+        self.settle_time = settle_time;
+        self
+    }
+
+    pub fn run(self) -> Result<ReplayReport, ReplayError> {
+        let mut initializer = NetworkInitializer::new(self.config);
+        if let Some(seed) = self.seed {
+            initializer = initializer.with_seed(seed);
+        }
+        let mut controller = SimulationController::new(initializer.start()?);
+
+        let first_timestamp = self
+            .records
+            .iter()
+            .find(|record| !record.kind.is_drone_event())
+            .map_or(0, |record| record.timestamp_us);
+        let start = Instant::now();
+        let mut actual = Vec::new();
+        let mut inputs_replayed = 0;
+
+        for (index, record) in self.records.iter().enumerate() {
+            if record.kind.is_drone_event() {
+                continue;
+            }
+            let due = Duration::from_micros(record.timestamp_us.saturating_sub(first_timestamp));
+            let elapsed = start.elapsed();
+            if due > elapsed {
+                collect_events(&mut controller, start, &mut actual, due - elapsed);
+            }
+
+            match (record.kind, record.drone, &record.packet, record.command) {
+                (EventKind::PacketInjected, Some(node_id), Some(packet), _) => {
+                    controller.inject(node_id, packet.clone())
+                }
+                (EventKind::Command, _, _, Some(command)) => controller.apply(command),
+                _ => return Err(ReplayError::MalformedInput(index)),
+            }
+            .map_err(|error| ReplayError::Input(index, error))?;
+            actual.push(record.clone()); //Marks the start of a new step
+            inputs_replayed += 1;
+        }
+        collect_events(&mut controller, start, &mut actual, self.settle_time);
+        controller.stop()?;
+
+        Ok(ReplayReport {
+            inputs_replayed,
+            expected_events: self
+                .records
+                .iter()
+                .filter(|record| record.kind.is_drone_event())
+                .count(),
+            actual_events: actual
+                .iter()
+                .filter(|record| record.kind.is_drone_event())
+                .count(),
+            divergence: first_divergence(&self.records, &actual),
+        })
+    }
+}
+
+/// First divergence between two event logs, comparing the kind and packet of the events of
+/// every drone; among the drones that diverge, the earliest recorded event is reported.
+///
+/// The inputs of a log split it in steps. Steps are compared in order, but the events of a drone
+/// in the same step are compared as a multiset: drones run on their own threads, so the replies
+/// a drone forwards for the same input can reach it in any order.
+pub fn first_divergence(expected: &[EventRecord], actual: &[EventRecord]) -> Option<Divergence> {
+    let expected = drone_streams(expected);
+    let mut actual = drone_streams(actual);
+    let mut drones: Vec<Option<NodeId>> = expected.keys().copied().collect();
+    drones.extend(actual.keys().filter(|drone| !expected.contains_key(drone)));
+
+    let mut first: Option<Divergence> = None;
+    for drone in drones {
+        let expected = expected.get(&drone).cloned().unwrap_or_default();
+        let actual = actual.remove(&drone).unwrap_or_default();
+        let Some((index, expected, actual)) = stream_divergence(&expected, &actual) else {
+            continue;
+        };
+
+        let divergence = Divergence {
+            drone,
+            index,
+            expected,
+            actual,
+        };
+        let timestamp = |divergence: &Divergence| {
+            divergence
+                .expected
+                .as_ref()
+                .or(divergence.actual.as_ref())
+                .map_or(u64::MAX, |record| record.timestamp_us)
+        };
+        if first
+            .as_ref()
+            .is_none_or(|first| timestamp(&divergence) < timestamp(first))
+        {
+            first = Some(divergence);
+        }
+    }
+    first
+}
+
+// Events of every drone, each with its step: the number of inputs before it in the log
+fn drone_streams(records: &[EventRecord]) -> BTreeMap<Option<NodeId>, Vec<(usize, EventRecord)>> {
+    let mut streams: BTreeMap<Option<NodeId>, Vec<(usize, EventRecord)>> = BTreeMap::new();
+    let mut step = 0;
+    for record in records {
+        if record.kind.is_drone_event() {
+            streams
+                .entry(record.drone)
+                .or_default()
+                .push((step, record.clone()));
+        } else {
+            step += 1;
+        }
+    }
+    streams
+}
+
+// First event of a drone without a match in the same step of the other log, as its index in the
+// events of the drone with the unmatched expected and actual events
+fn stream_divergence(
+    expected: &[(usize, EventRecord)],
+    actual: &[(usize, EventRecord)],
+) -> Option<(usize, Option<EventRecord>, Option<EventRecord>)> {
+    let same_event = |a: &EventRecord, b: &EventRecord| a.kind == b.kind && a.packet == b.packet;
+    let (mut expected_start, mut actual_start) = (0, 0);
+    while expected_start < expected.len() || actual_start < actual.len() {
+        let step = expected
+            .get(expected_start)
+            .into_iter()
+            .chain(actual.get(actual_start))
+            .map(|(step, _)| *step)
+            .min()?;
+        let step_end = |events: &[(usize, EventRecord)], start: usize| {
+            start
+                + events[start..]
+                    .iter()
+                    .take_while(|(event_step, _)| *event_step == step)
+                    .count()
+        };
+        let expected_end = step_end(expected, expected_start);
+        let actual_end = step_end(actual, actual_start);
+
+        let mut unmatched: Vec<usize> = (actual_start..actual_end).collect();
+        let mut missing = None;
+        for (index, (_, record)) in expected
+            .iter()
+            .enumerate()
+            .take(expected_end)
+            .skip(expected_start)
+        {
+            match unmatched
+                .iter()
+                .position(|other| same_event(record, &actual[*other].1))
+            {
+                Some(position) => {
+                    unmatched.remove(position);
+                }
+                None => {
+                    missing.get_or_insert(index);
+                }
+            }
+        }
+        let extra = unmatched.first().copied();
+        if let Some(index) = missing.or(extra) {
+            return Some((
+                index,
+                missing.map(|index| expected[index].1.clone()),
+                extra.map(|index| actual[index].1.clone()),
+            ));
+        }
+        expected_start = expected_end;
+        actual_start = actual_end;
+    }
+    None
+}
+
+// Handles the events of the network for `duration`, stamping them with the time since `start`
+fn collect_events(
+    controller: &mut SimulationController,
+    start: Instant,
+    events: &mut Vec<EventRecord>,
+    duration: Duration,
+) {
+    let deadline = Instant::now() + duration;
+    loop {
+        let now = Instant::now();
+        if now >= deadline {
+            break;
+        }
+        if let Some(event) = controller.recv_event_timeout(deadline - now) {
+            let timestamp_us = u64::try_from(start.elapsed().as_micros()).unwrap_or(u64::MAX);
+            events.push(EventRecord::new(timestamp_us, &event));
+        }
+    }
+}
@@ -0,0 +1,263 @@
+use crate::ControllerCommand;
+use serde::{Deserialize, Serialize};
+use std::fmt;
+use std::fs;
+use std::path::Path;
+use std::time::Duration;
+use thiserror::Error;
+use wg_2024::config::Config;
+use wg_2024::network::NodeId;
+use wg_2024::packet::NodeType;
+
+#[derive(Debug, Error)]
+pub enum ScenarioError {
+    #[error("Error reading the scenario file: {0}")]
+    Io(#[from] std::io::Error),
+    #[error("Error parsing the scenario file: {0}")]
+    Parse(#[from] toml::de::Error),
+    #[error("Step {0} has a time that is negative or not a number.")]
+    InvalidTime(usize),
+    #[error("Step {0} ends before it starts.")]
+    EmptyWindow(usize),
+    #[error("Step {0} sends no messages.")]
+    NoMessages(usize),
+    #[error("Step {0} uses node {1} which is not defined.")]
+    UnknownNode(usize, NodeId),
+    #[error("Step {0} uses node {1} as a {2:?}, but it is not one.")]
+    WrongNodeType(usize, NodeId, NodeType),
+}
+
+/// What a step of the scenario file does.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(tag = "action", rename_all = "snake_case")]
+pub enum ScenarioAction {
+    SetPdr {
+        drone: NodeId,
+        pdr: f32,
+    },
+    Crash {
+        drone: NodeId,
+    },
+    AddLink {
+        link: (NodeId, NodeId),
+    },
+    RemoveLink {
+        link: (NodeId, NodeId),
+    },
+    SendMessages {
+        client: NodeId,
+        server: NodeId,
+        count: u64,
+        until: f64, //Seconds, the messages are spread evenly from `at` to `until`
+    },
+}
+
+/// One `[[step]]` of the scenario file.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct ScenarioStep {
+    pub at: f64, //Seconds since the start of the run
+    #[serde(flatten)]
+    pub action: ScenarioAction,
+}
+
+/// Action of the scenario due at a given time, see `Scenario::schedule`.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum ScheduledAction {
+    Command(ControllerCommand),
+    SendMessage {
+        client: NodeId,
+        server: NodeId,
+        index: u64, //Position of the message in its step, from 1
+        count: u64,
+    },
+}
+
+impl fmt::Display for ScheduledAction {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            ScheduledAction::Command(ControllerCommand::SetPdr(drone_id, pdr)) => {
+                write!(f, "set drone {drone_id} pdr to {pdr}")
+            }
+            ScheduledAction::Command(ControllerCommand::Crash(drone_id)) => {
+                write!(f, "crash drone {drone_id}")
+            }
+            ScheduledAction::Command(ControllerCommand::AddLink(a, b)) => {
+                write!(f, "add link {a}-{b}")
+            }
+            ScheduledAction::Command(ControllerCommand::RemoveLink(a, b)) => {
+                write!(f, "remove link {a}-{b}")
+            }
+            ScheduledAction::SendMessage {
+                client,
+                server,
+                index,
+                count,
+            } => write!(
+                f,
+                "client {client} sends message {index}/{count} to server {server}"
+            ),
+        }
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct ScheduledStep {
+    pub at: Duration,
+    pub step: usize, //Index of the step in the scenario file
+    pub action: ScheduledAction,
+}
+
+/// Timed commands and traffic for a network, read from a TOML file of `[[step]]` tables:
+///
+/// ```toml
+/// [[step]]
+/// at = 2
+/// action = "set_pdr"
+/// drone = 3
+/// pdr = 0.5
+///
+/// [[step]]
+/// at = 0
+/// action = "send_messages"
+/// client = 4
+/// server = 6
+/// count = 100
+/// until = 10
+/// ```
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize)]
+pub struct Scenario {
+    #[serde(default, rename = "step")]
+    pub steps: Vec<ScenarioStep>,
+}
+
+impl Scenario {
+    /// Parses a scenario, checking the times of every step.
+    pub fn parse(scenario: &str) -> Result<Self, ScenarioError> {
+        let scenario: Scenario = toml::from_str(scenario)?;
+        for (index, step) in scenario.steps.iter().enumerate() {
+            if !(step.at >= 0.0 && step.at.is_finite()) {
+                return Err(ScenarioError::InvalidTime(index));
+            }
+            if let ScenarioAction::SendMessages { count, until, .. } = step.action {
+                if !until.is_finite() {
+                    return Err(ScenarioError::InvalidTime(index));
+                }
+                if until < step.at {
+                    return Err(ScenarioError::EmptyWindow(index));
+                }
+                if count == 0 {
+                    return Err(ScenarioError::NoMessages(index));
+                }
+            }
+        }
+        Ok(scenario)
+    }
+
+    pub fn from_file<P: AsRef<Path>>(file: P) -> Result<Self, ScenarioError> {
+        Self::parse(&fs::read_to_string(file)?)
+    }
+
+    /// Checks that every node of the scenario is defined in the config with the right type.
+    ///
+    /// Links are checked only for their ends, whether a change is allowed depends on the live
+    /// topology and is decided by the `SimulationController`.
+    pub fn validate(&self, config: &Config) -> Result<(), Vec<ScenarioError>> {
+        let node_type = |node_id: NodeId| {
+            if config.drone.iter().any(|drone| drone.id == node_id) {
+                Some(NodeType::Drone)
+            } else if config.client.iter().any(|client| client.id == node_id) {
+                Some(NodeType::Client)
+            } else if config.server.iter().any(|server| server.id == node_id) {
+                Some(NodeType::Server)
+            } else {
+                None
+            }
+        };
+
+        let mut errors = Vec::new();
+        for (index, step) in self.steps.iter().enumerate() {
+            let expected: Vec<(NodeId, Option<NodeType>)> = match step.action {
+                ScenarioAction::SetPdr { drone, .. } | ScenarioAction::Crash { drone } => {
+                    vec![(drone, Some(NodeType::Drone))]
+                }
+                ScenarioAction::AddLink { link: (a, b) }
+                | ScenarioAction::RemoveLink { link: (a, b) } => vec![(a, None), (b, None)],
+                ScenarioAction::SendMessages { client, server, .. } => vec![
+                    (client, Some(NodeType::Client)),
+                    (server, Some(NodeType::Server)),
+                ],
+            };
+            for (node_id, expected_type) in expected {
+                match (node_type(node_id), expected_type) {
+                    (None, _) => errors.push(ScenarioError::UnknownNode(index, node_id)),
+                    (Some(actual), Some(expected)) if actual != expected => {
+                        errors.push(ScenarioError::WrongNodeType(index, node_id, expected))
+                    }
+                    _ => {}
+                }
+            }
+        }
+
+        if errors.is_empty() {
+            Ok(())
+        } else {
+            Err(errors)
+        }
+    }
+
+    /// Every action of the scenario with its time, sorted by time and then by step.
+    pub fn schedule(&self) -> Vec<ScheduledStep> {
+        let mut schedule = Vec::new();
+        for (index, step) in self.steps.iter().enumerate() {
+            let at = seconds(step.at);
+            let command = match step.action {
+                ScenarioAction::SetPdr { drone, pdr } => ControllerCommand::SetPdr(drone, pdr),
+                ScenarioAction::Crash { drone } => ControllerCommand::Crash(drone),
+                ScenarioAction::AddLink { link: (a, b) } => ControllerCommand::AddLink(a, b),
+                ScenarioAction::RemoveLink { link: (a, b) } => ControllerCommand::RemoveLink(a, b),
+                ScenarioAction::SendMessages {
+                    client,
+                    server,
+                    count,
+                    until,
+                } => {
+                    let window = seconds(until).saturating_sub(at);
+                    schedule.extend((0..count).map(|message| ScheduledStep {
+                        at: at + window.mul_f64(message as f64 / count as f64),
+                        step: index,
+                        action: ScheduledAction::SendMessage {
+                            client,
+                            server,
+                            index: message + 1,
+                            count,
+                        },
+                    }));
+                    continue;
+                }
+            };
+            schedule.push(ScheduledStep {
+                at,
+                step: index,
+                action: ScheduledAction::Command(command),
+            });
+        }
+        schedule.sort_by_key(|scheduled| (scheduled.at, scheduled.step));
+        schedule
+    }
+
+    /// Time of the last action, or the end of the last message window if later.
+    pub fn end(&self) -> Duration {
+        self.steps
+            .iter()
+            .map(|step| match step.action {
+                ScenarioAction::SendMessages { until, .. } => until.max(step.at),
+                _ => step.at,
+            })
+            .fold(Duration::ZERO, |end, time| end.max(seconds(time)))
+    }
+}
+
+// Times that are not valid count as the start of the run
+fn seconds(time: f64) -> Duration {
+    Duration::try_from_secs_f64(time).unwrap_or_default()
+}
@@ -0,0 +1,327 @@
+use crate::client::{flood_request, learn_path_trace};
+use crate::{
+    ack_for, flood_response, DeliveryEvent, FragmentStatus, NetworkGraph, NetworkHandle,
+    Reassembler, ReliableSender, StopToken,
+};
+use crossbeam_channel::{select_biased, Receiver, Sender};
+use std::collections::HashMap;
+use std::time::{Duration, Instant};
+use thiserror::Error;
+use wg_2024::network::NodeId;
+use wg_2024::packet::{NodeType, Packet, PacketType};
+
+const SERVER_TICK: Duration = Duration::from_millis(10);
+const REFLOOD_QUIET: Duration = Duration::from_millis(200);
+
+#[derive(Debug, Clone, PartialEq, Error)]
+pub enum ServerError {
+    #[error("Node {0} is not a server of the network.")]
+    NotAServer(NodeId),
+}
+
+/// Application logic of a `Server`.
+pub trait RequestHandler {
+    /// Answers the complete request of `client_id`, `None` sends nothing back.
+    fn handle_request(&mut self, client_id: NodeId, request: &[u8]) -> Option<Vec<u8>>;
+
+    /// Messages for other clients, taken after every request.
+    fn take_messages(&mut self) -> Vec<(NodeId, Vec<u8>)> {
+        Vec::new()
+    }
+}
+
+impl<F> RequestHandler for F
+where
+    F: FnMut(NodeId, &[u8]) -> Option<Vec<u8>>,
+{
+    fn handle_request(&mut self, client_id: NodeId, request: &[u8]) -> Option<Vec<u8>> {
+        self(client_id, request)
+    }
+}
+
+/// Counters of a `Server`, the delivery of the responses is counted by its `ReliableSender`.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub struct ServerStats {
+    pub fragments_received: u64,
+    pub fragments_refused: u64, //Not valid for the `Reassembler`
+    pub acks_sent: u64,
+    pub requests_handled: u64,
+    pub responses_sent: u64,
+    pub messages_sent: u64,     //Taken from `RequestHandler::take_messages`
+}
+
+/// Reference server node, on the same channel model as `Dronegowski`.
+///
+/// Every fragment received is acknowledged on its reversed route and reassembled. Complete
+/// requests go to the `RequestHandler`, and its responses and messages are sent as fragmented
+/// messages by a `ReliableSender`. The routes to the clients are learned from the requests themselves,
+/// and the server floods the network only when the delivery layer runs out of routes.
+pub struct Server<H: RequestHandler> {
+    id: NodeId,
+    packet_recv: Receiver<Packet>,
+    packet_send: HashMap<NodeId, Sender<Packet>>,
+    handler: H,
+    topology: NetworkGraph,         //Nodes and links learned from requests and floods
+    reassembler: Reassembler,
+    delivery: ReliableSender,
+    flood_count: u64,               //Floods sent so far, part of the next flood id
+    flood_started: Option<Instant>, //Start of the flood the delivery layer is waiting for
+    stop: StopToken,
+    stats: ServerStats,
+}
+
+impl<H: RequestHandler> Server<H> {
+    pub fn new(
+        id: NodeId,
+        packet_recv: Receiver<Packet>,
+        packet_send: HashMap<NodeId, Sender<Packet>>,
+        handler: H,
+    ) -> Self {
+        let mut topology = NetworkGraph::new();
+        topology.add_node(id, NodeType::Server);
+        for neighbour in packet_send.keys() {
+            topology.add_node(*neighbour, NodeType::Drone);
+            let _ = topology.add_edge(id, *neighbour);
+        }
+        Self {
+            id,
+            packet_recv,
+            packet_send,
+            handler,
+            topology,
+            reassembler: Reassembler::new(),
+            delivery: ReliableSender::new(id),
+            flood_count: 0,
+            flood_started: None,
+            stop: StopToken::new(),
+            stats: ServerStats::default(),
+        }
+    }
+
+    /// Server `server_id` of a running network, linked to the drones of its config entry.
+    pub fn from_network(
+        network: &NetworkHandle,
+        server_id: NodeId,
+        handler: H,
+    ) -> Result<Self, ServerError> {
+        let server = network
+            .config()
+            .server
+            .iter()
+            .find(|server| server.id == server_id)
+            .ok_or(ServerError::NotAServer(server_id))?;
+        let packet_recv = network
+            .packet_recv()
+            .get(&server_id)
+            .ok_or(ServerError::NotAServer(server_id))?
+            .clone();
+        let packet_send = server
+            .connected_drone_ids
+            .iter()
+            .filter_map(|id| Some((*id, network.packet_send().get(id)?.clone())))
+            .collect();
+        Ok(Self::new(server_id, packet_recv, packet_send, handler))
+    }
+
+    #[must_use]
+    pub fn with_reassembler(mut self, reassembler: Reassembler) -> Self {
+        self.reassembler = reassembler;
+        self
+    }
+
+    /// Replaces the delivery layer of the responses, to change its retry and time budgets.
+    #[must_use]
+    pub fn with_delivery(mut self, delivery: ReliableSender) -> Self {
+        self.delivery = delivery;
+        self
+    }
+
+    #[must_use]
+    pub fn with_stop_token(mut self, stop: StopToken) -> Self {
+        self.stop = stop;
+        self
+    }
+
+    pub fn id(&self) -> NodeId {
+        self.id
+    }
+
+    pub fn topology(&self) -> &NetworkGraph {
+        &self.topology
+    }
+
+    pub fn handler(&self) -> &H {
+        &self.handler
+    }
+
+    pub fn delivery(&self) -> &ReliableSender {
+        &self.delivery
+    }
+
+    pub fn stats(&self) -> ServerStats {
+        self.stats
+    }
+
+    /// Token that stops `run()` from another thread.
+    pub fn stop_token(&self) -> StopToken {
+        self.stop.clone()
+    }
+
+    /// Handles packets until the stop token is triggered or every sender of the channel is dropped.
+    pub fn run(&mut self) {
+        let stop_recv = self.stop.receiver().clone();
+        while !self.stop.is_stopped() {
+            select_biased! {
+                recv(stop_recv) -> _ => {},
+                recv(self.packet_recv) -> packet_res => match packet_res {
+                    Ok(packet) => self.handle_packet(packet),
+                    Err(_) => break,
+                },
+                default(SERVER_TICK) => {},
+            }
+            self.tick();
+        }
+        log::info!("Server {} has terminated execution", self.id);
+    }
+
+    pub fn handle_packet(&mut self, packet: Packet) {
+        match packet.pack_type {
+            PacketType::MsgFragment(ref fragment) => {
+                self.stats.fragments_received += 1;
+                if ack_for(&packet).is_some_and(|ack| self.send(ack)) {
+                    self.stats.acks_sent += 1;
+                }
+                let hops = &packet.routing_header.hops;
+                let Some(source) = hops.first().copied() else {
+                    return;
+                };
+                //The route of the request is also a route back to the client
+                let path_trace: Vec<(NodeId, NodeType)> = hops
+                    .iter()
+                    .take(packet.routing_header.hop_index + 1)
+                    .enumerate()
+                    .map(|(index, id)| match index {
+                        0 => (*id, NodeType::Client),
+                        _ if *id == self.id => (*id, NodeType::Server),
+                        _ => (*id, NodeType::Drone),
+                    })
+                    .collect();
+                learn_path_trace(&mut self.topology, &path_trace);
+
+                match self.reassembler.push(source, packet.session_id, fragment) {
+                    Ok(FragmentStatus::Complete(request)) => self.handle_request(source, &request),
+                    Ok(_) => {}
+                    Err(error) => {
+                        self.stats.fragments_refused += 1;
+                        log::warn!("Server {} refused a fragment: {}", self.id, error);
+                    }
+                }
+            }
+            PacketType::Ack(_) | PacketType::Nack(_) => {
+                self.delivery.handle_packet(&packet, &mut self.topology);
+            }
+            PacketType::FloodRequest(ref flood_request) => {
+                if flood_request.initiator_id == self.id {
+                    learn_path_trace(&mut self.topology, &flood_request.path_trace);
+                } else {
+                    self.send(flood_response(
+                        flood_request,
+                        packet.session_id,
+                        self.id,
+                        NodeType::Server,
+                    ));
+                }
+            }
+            PacketType::FloodResponse(ref flood_response) => {
+                if flood_response.path_trace.first().map(|(id, _)| *id) == Some(self.id) {
+                    learn_path_trace(&mut self.topology, &flood_response.path_trace);
+                }
+            }
+        }
+        self.flush();
+    }
+
+    /// Time-driven work: retransmissions, expired sessions and the end of a flood.
+    pub fn tick(&mut self) {
+        self.delivery.check_timeouts();
+        for expired in self.reassembler.expire() {
+            log::warn!(
+                "Server {} dropped session {} of node {}, missing fragments {:?}",
+                self.id,
+                expired.session_id,
+                expired.source,
+                expired.missing
+            );
+        }
+        if self
+            .flood_started
+            .is_some_and(|started| started.elapsed() >= REFLOOD_QUIET)
+        {
+            self.flood_started = None;
+            self.delivery.reroute(&self.topology);
+        }
+        self.flush();
+    }
+
+    fn handle_request(&mut self, client_id: NodeId, request: &[u8]) {
+        self.stats.requests_handled += 1;
+        if let Some(response) = self.handler.handle_request(client_id, request) {
+            self.delivery.send(client_id, &response, &self.topology);
+            self.stats.responses_sent += 1;
+        }
+        for (destination, message) in self.handler.take_messages() {
+            self.delivery.send(destination, &message, &self.topology);
+            self.stats.messages_sent += 1;
+        }
+    }
+
+    // Sends what the delivery layer has queued, starting a flood when it lost every route
+    fn flush(&mut self) {
+        for packet in self.delivery.take_outgoing() {
+            if !self.send(packet.clone()) {
+                self.delivery.handle_send_error(&packet, &mut self.topology);
+            }
+        }
+        if self.delivery.needs_flood() && self.flood_started.is_none() {
+            self.flood_count += 1;
+            let packet = flood_request(self.id, NodeType::Server, self.flood_count);
+            for sender in self.packet_send.values() {
+                let _ = sender.send(packet.clone());
+            }
+            self.flood_started = Some(Instant::now());
+        }
+        for event in self.delivery.take_events() {
+            match event {
+                DeliveryEvent::Delivered(session_id) => {
+                    log::info!("Server {} delivered response {}", self.id, session_id)
+                }
+                DeliveryEvent::Failed(session_id, error) => log::warn!(
+                    "Server {} could not deliver response {}: {}",
+                    self.id,
+                    session_id,
+                    error
+                ),
+            }
+        }
+    }
+
+    // Sends a packet to the neighbour at the current hop of its routing header
+    fn send(&self, packet: Packet) -> bool {
+        let Some(next_hop) = packet
+            .routing_header
+            .hops
+            .get(packet.routing_header.hop_index)
+            .copied()
+        else {
+            return false;
+        };
+        let sent = self
+            .packet_send
+            .get(&next_hop)
+            .is_some_and(|sender| sender.send(packet).is_ok());
+        if !sent {
+            log::warn!("Server {} could not send to node {}", self.id, next_hop);
+        }
+        sent
+    }
+}
@@ -0,0 +1,453 @@
+use crate::{
+    check_degree, EventRecorder, NetworkGraph, NetworkHandle, NetworkInitializerError,
+    ShutdownReport, ValidationError,
+};
+use serde::{Deserialize, Serialize};
+use std::collections::{HashMap, HashSet};
+use std::time::Duration;
+use thiserror::Error;
+use wg_2024::controller::{DroneCommand, DroneEvent};
+use wg_2024::network::NodeId;
+use wg_2024::packet::{NodeType, Packet};
+
+mod shortcut;
+pub use shortcut::*;
+
+#[derive(Debug, Clone, PartialEq, Error)]
+pub enum SimulationControllerError {
+    #[error("node {0} is not part of the network")]
+    UnknownNode(NodeId),
+    #[error("node {0} is not a drone")]
+    NotADrone(NodeId),
+    #[error("drone {0} has already crashed")]
+    AlreadyCrashed(NodeId),
+    #[error("pdr out of bounds: {1} is not in [0, 1] for drone {0}")]
+    PdrOutOfBounds(NodeId, f32),
+    #[error("node {0} cannot be linked to itself")]
+    SelfLink(NodeId),
+    #[error("nodes {0} and {1} are not drones, at least one end of a link must be a drone")]
+    NoDroneInLink(NodeId, NodeId),
+    #[error("nodes {0} and {1} are already linked")]
+    LinkAlreadyPresent(NodeId, NodeId),
+    #[error("nodes {0} and {1} are not linked")]
+    LinkNotPresent(NodeId, NodeId),
+    #[error("the command channel of drone {0} is disconnected")]
+    DroneDisconnected(NodeId),
+    #[error("the packet channel of node {0} is disconnected")]
+    NodeDisconnected(NodeId),
+    #[error("the change would cut off the nodes {0:?} from the rest of the network")]
+    WouldCutOff(Vec<NodeId>),
+    #[error("the change would break a rule of the protocol: {0}")]
+    WouldBreakRule(ValidationError),
+}
+
+/// Change of the network applied through the Simulation Controller, recorded in the event log.
+#[derive(Debug, Clone, Copy, PartialEq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum ControllerCommand {
+    Crash(NodeId),
+    SetPdr(NodeId, f32),
+    AddLink(NodeId, NodeId),
+    RemoveLink(NodeId, NodeId),
+}
+
+/// Traffic seen by the Simulation Controller through the `DroneEvent`s.
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct TrafficStats {
+    pub packets_sent: u64,
+    pub packets_dropped: u64,
+    pub controller_shortcuts: u64,
+    pub shortcuts_delivered: u64,
+    pub sent_per_link: HashMap<(NodeId, NodeId), u64>, //PacketSent per (sender, receiver) when known
+    pub dropped_per_drone: HashMap<NodeId, u64>,       //PacketDropped per drone that dropped the fragment
+}
+
+/// Drives a running network: keeps a live model of the topology and sends the `DroneCommand`s
+/// needed by every change to the affected drones.
+///
+/// Clients and servers are not driven by commands: for them only the model is updated, and the
+/// channels to use are the ones of the `NetworkHandle`.
+pub struct SimulationController {
+    network: NetworkHandle,
+    topology: NetworkGraph,    //Live links of every node
+    pdr: HashMap<NodeId, f32>, //Last PDR set on every drone
+    crashed: HashSet<NodeId>,
+    traffic: TrafficStats,
+    shortcuts: ShortcutDispatcher,
+    undelivered: Vec<(Packet, ShortcutError)>, //Shortcuts the dispatcher could not deliver
+    recorder: Option<EventRecorder>,           //Log of every handled event
+}
+
+impl SimulationController {
+    pub fn new(network: NetworkHandle) -> Self {
+        let topology = NetworkGraph::from_config(network.config());
+        let pdr = network
+            .config()
+            .drone
+            .iter()
+            .map(|drone| (drone.id, drone.pdr))
+            .collect();
+
+        let shortcuts = ShortcutDispatcher::from_graph(network.packet_send(), &topology);
+        Self {
+            network,
+            topology,
+            pdr,
+            crashed: HashSet::new(),
+            traffic: TrafficStats::default(),
+            shortcuts,
+            undelivered: Vec::new(),
+            recorder: None,
+        }
+    }
+
+    /// Writes every event handled by the controller to the recorder.
+    #[must_use]
+    pub fn with_recorder(mut self, recorder: EventRecorder) -> Self {
+        self.recorder = Some(recorder);
+        self
+    }
+
+    pub fn recorder(&self) -> Option<&EventRecorder> {
+        self.recorder.as_ref()
+    }
+
+    pub fn network(&self) -> &NetworkHandle {
+        &self.network
+    }
+
+    pub fn node_type(&self, node_id: NodeId) -> Option<NodeType> {
+        self.topology.node_type(node_id)
+    }
+
+    /// Current neighbours of a node, sorted by id.
+    pub fn neighbours(&self, node_id: NodeId) -> Option<Vec<NodeId>> {
+        self.topology.neighbours(node_id)
+    }
+
+    pub fn topology(&self) -> &NetworkGraph {
+        &self.topology
+    }
+
+    pub fn pdr(&self, drone_id: NodeId) -> Option<f32> {
+        self.pdr.get(&drone_id).copied()
+    }
+
+    pub fn is_crashed(&self, drone_id: NodeId) -> bool {
+        self.crashed.contains(&drone_id)
+    }
+
+    pub fn traffic(&self) -> &TrafficStats {
+        &self.traffic
+    }
+
+    /// Shortcuts that could not be delivered since the last call, with the reason.
+    pub fn take_undelivered(&mut self) -> Vec<(Packet, ShortcutError)> {
+        std::mem::take(&mut self.undelivered)
+    }
+
+    /// Applies a command with the method of the same name, recording it if it succeeds.
+    pub fn apply(&mut self, command: ControllerCommand) -> Result<(), SimulationControllerError> {
+        match command {
+            ControllerCommand::Crash(drone_id) => self.crash(drone_id),
+            ControllerCommand::SetPdr(drone_id, pdr) => self.set_pdr(drone_id, pdr),
+            ControllerCommand::AddLink(a, b) => self.add_link(a, b),
+            ControllerCommand::RemoveLink(a, b) => self.remove_link(a, b),
+        }
+    }
+
+    /// Sends a packet to the channel of a node, as if a neighbour had sent it.
+    pub fn inject(
+        &mut self,
+        node_id: NodeId,
+        packet: Packet,
+    ) -> Result<(), SimulationControllerError> {
+        let sender = self
+            .network
+            .packet_send()
+            .get(&node_id)
+            .ok_or(SimulationControllerError::UnknownNode(node_id))?;
+        if let Some(recorder) = &mut self.recorder {
+            if let Err(error) = recorder.record_injected(node_id, &packet) {
+                log::warn!("Simulation Controller: injected packet not recorded, {error}");
+            }
+        }
+        sender
+            .send(packet)
+            .map_err(|_| SimulationControllerError::NodeDisconnected(node_id))
+    }
+
+    /// Crashes a drone and removes it from the senders of all its neighbours.
+    ///
+    /// Refused with `WouldCutOff` if a client or server that was reachable would be cut off.
+    /// Once the drone has crashed the change is kept: a neighbour that cannot be told is
+    /// reported with `DroneDisconnected` after all the others.
+    pub fn crash(&mut self, drone_id: NodeId) -> Result<(), SimulationControllerError> {
+        self.check_active_drone(drone_id)?;
+        let mut topology = self.topology.clone();
+        let neighbours = topology.clear_edges(drone_id);
+        self.check_connectivity(&topology)?;
+
+        self.send_command(drone_id, DroneCommand::Crash)?;
+        self.topology = topology;
+        self.sync_neighbours(drone_id);
+        self.network.remove_packet_send(drone_id);
+        self.crashed.insert(drone_id);
+        log::info!("Simulation Controller: drone {drone_id} crashed");
+        self.record_command(ControllerCommand::Crash(drone_id));
+
+        let mut result = Ok(());
+        for neighbour in neighbours {
+            self.sync_neighbours(neighbour);
+            if !self.is_active_drone(neighbour) {
+                continue;
+            }
+            let command = DroneCommand::RemoveSender(drone_id);
+            if let Err(error) = self.send_command(neighbour, command) {
+                log::warn!("Simulation Controller: drone {neighbour} still has {drone_id}");
+                if result.is_ok() {
+                    result = Err(error);
+                }
+            }
+        }
+        result
+    }
+
+    pub fn set_pdr(&mut self, drone_id: NodeId, pdr: f32) -> Result<(), SimulationControllerError> {
+        self.check_active_drone(drone_id)?;
+        if !(0.0..=1.0).contains(&pdr) {
+            return Err(SimulationControllerError::PdrOutOfBounds(drone_id, pdr));
+        }
+        self.send_command(drone_id, DroneCommand::SetPacketDropRate(pdr))?;
+        self.pdr.insert(drone_id, pdr);
+        log::info!("Simulation Controller: PDR of drone {drone_id} set to {pdr}");
+        self.record_command(ControllerCommand::SetPdr(drone_id, pdr));
+        Ok(())
+    }
+
+    /// Links two nodes, giving every drone end the sender of the other end.
+    ///
+    /// Refused with `WouldBreakRule` if a client would be linked to more than two drones.
+    pub fn add_link(&mut self, a: NodeId, b: NodeId) -> Result<(), SimulationControllerError> {
+        self.check_link_ends(a, b)?;
+        if self.topology.has_edge(a, b) {
+            return Err(SimulationControllerError::LinkAlreadyPresent(a, b));
+        }
+        for node_id in [a, b] {
+            //The other end is a drone, checked above
+            let node_type = self.node_type(node_id);
+            if let Some(node_type @ (NodeType::Client | NodeType::Server)) = node_type {
+                let degree = self.neighbours(node_id).unwrap_or_default().len() + 1;
+                check_degree(node_id, node_type, degree)
+                    .map_err(SimulationControllerError::WouldBreakRule)?;
+            }
+        }
+
+        //Every sender is resolved before the first command, so that a missing one changes nothing
+        let mut commands = Vec::new();
+        for (from, to) in [(a, b), (b, a)] {
+            if self.node_type(from) == Some(NodeType::Drone) {
+                let sender = self
+                    .network
+                    .packet_send()
+                    .get(&to)
+                    .cloned()
+                    .ok_or(SimulationControllerError::UnknownNode(to))?;
+                commands.push((from, to, sender));
+            }
+        }
+        let mut sent = Vec::new();
+        for (from, to, sender) in commands {
+            if let Err(error) = self.send_command(from, DroneCommand::AddSender(to, sender)) {
+                //Rolls back the drones that already got the new sender
+                for (from, to) in sent {
+                    if self.send_command(from, DroneCommand::RemoveSender(to)).is_err() {
+                        log::warn!("Simulation Controller: drone {from} still has {to}");
+                    }
+                }
+                return Err(error);
+            }
+            sent.push((from, to));
+        }
+
+        let _ = self.topology.add_edge(a, b);
+        self.sync_neighbours(a);
+        self.sync_neighbours(b);
+        log::info!("Simulation Controller: added link {a} <-> {b}");
+        self.record_command(ControllerCommand::AddLink(a, b));
+        Ok(())
+    }
+
+    /// Removes the link between two nodes from the senders of every drone end.
+    ///
+    /// Refused with `WouldCutOff` if a client or server that was reachable would be cut off.
+    pub fn remove_link(&mut self, a: NodeId, b: NodeId) -> Result<(), SimulationControllerError> {
+        self.check_link_ends(a, b)?;
+        let mut topology = self.topology.clone();
+        if !topology.remove_edge(a, b) {
+            return Err(SimulationControllerError::LinkNotPresent(a, b));
+        }
+        self.check_connectivity(&topology)?;
+
+        for (from, to) in [(a, b), (b, a)] {
+            if self.node_type(from) == Some(NodeType::Drone) {
+                self.send_command(from, DroneCommand::RemoveSender(to))?;
+            }
+        }
+        self.topology = topology;
+        self.sync_neighbours(a);
+        self.sync_neighbours(b);
+        log::info!("Simulation Controller: removed link {a} <-> {b}");
+        self.record_command(ControllerCommand::RemoveLink(a, b));
+        Ok(())
+    }
+
+    /// Updates the traffic stats with an event sent by a drone.
+    ///
+    /// The packet of a `ControllerShortcut` is delivered to the client or server at its final hop.
+    pub fn handle_event(&mut self, event: &DroneEvent) {
+        if let Some(recorder) = &mut self.recorder {
+            if let Err(error) = recorder.record(event) {
+                log::warn!("Simulation Controller: event not recorded, {error}");
+            }
+        }
+        match event {
+            DroneEvent::PacketSent(packet) => {
+                self.traffic.packets_sent += 1;
+                if let Some(link) = sent_link(packet) {
+                    *self.traffic.sent_per_link.entry(link).or_default() += 1;
+                }
+            }
+            DroneEvent::PacketDropped(packet) => {
+                self.traffic.packets_dropped += 1;
+                if let Some(drone_id) = packet
+                    .routing_header
+                    .hops
+                    .get(packet.routing_header.hop_index)
+                {
+                    *self.traffic.dropped_per_drone.entry(*drone_id).or_default() += 1;
+                }
+            }
+            DroneEvent::ControllerShortcut(packet) => {
+                self.traffic.controller_shortcuts += 1;
+                match self.shortcuts.dispatch(packet.clone()) {
+                    Ok(destination) => {
+                        self.traffic.shortcuts_delivered += 1;
+                        log::info!(
+                            "Simulation Controller: shortcut delivered to node {destination}"
+                        );
+                    }
+                    Err(error) => {
+                        log::warn!("Simulation Controller: shortcut not delivered, {error}");
+                        self.undelivered.push((packet.clone(), error));
+                    }
+                }
+            }
+        }
+    }
+
+    /// Handles every event already received and returns them.
+    pub fn poll_events(&mut self) -> Vec<DroneEvent> {
+        let events: Vec<DroneEvent> = self.network.event_recv().try_iter().collect();
+        for event in &events {
+            self.handle_event(event);
+        }
+        events
+    }
+
+    /// Waits at most `timeout` for the next event and handles it.
+    pub fn recv_event_timeout(&mut self, timeout: Duration) -> Option<DroneEvent> {
+        let event = self.network.event_recv().recv_timeout(timeout).ok()?;
+        self.handle_event(&event);
+        Some(event)
+    }
+
+    /// Stops every drone, see `NetworkHandle::stop`.
+    pub fn stop(self) -> Result<HashMap<NodeId, ShutdownReport>, NetworkInitializerError> {
+        self.network.stop()
+    }
+
+    /// Crashes every drone, see `NetworkHandle::shutdown`.
+    pub fn shutdown(self) -> Result<HashMap<NodeId, ShutdownReport>, NetworkInitializerError> {
+        self.network.shutdown()
+    }
+
+    fn is_active_drone(&self, node_id: NodeId) -> bool {
+        self.node_type(node_id) == Some(NodeType::Drone) && !self.is_crashed(node_id)
+    }
+
+    fn check_active_drone(&self, node_id: NodeId) -> Result<(), SimulationControllerError> {
+        match self.node_type(node_id) {
+            None => Err(SimulationControllerError::UnknownNode(node_id)),
+            Some(NodeType::Drone) if self.is_crashed(node_id) => {
+                Err(SimulationControllerError::AlreadyCrashed(node_id))
+            }
+            Some(NodeType::Drone) => Ok(()),
+            Some(_) => Err(SimulationControllerError::NotADrone(node_id)),
+        }
+    }
+
+    fn check_link_ends(&self, a: NodeId, b: NodeId) -> Result<(), SimulationControllerError> {
+        if a == b {
+            return Err(SimulationControllerError::SelfLink(a));
+        }
+        for node_id in [a, b] {
+            match self.node_type(node_id) {
+                None => return Err(SimulationControllerError::UnknownNode(node_id)),
+                Some(NodeType::Drone) if self.is_crashed(node_id) => {
+                    return Err(SimulationControllerError::AlreadyCrashed(node_id))
+                }
+                _ => {}
+            }
+        }
+        if self.node_type(a) != Some(NodeType::Drone) && self.node_type(b) != Some(NodeType::Drone)
+        {
+            return Err(SimulationControllerError::NoDroneInLink(a, b));
+        }
+        Ok(())
+    }
+
+    fn check_connectivity(&self, topology: &NetworkGraph) -> Result<(), SimulationControllerError> {
+        //Nodes that were already cut off do not block unrelated changes
+        let cut_off = self.topology.newly_cut_off(topology);
+        if cut_off.is_empty() {
+            Ok(())
+        } else {
+            Err(SimulationControllerError::WouldCutOff(cut_off))
+        }
+    }
+
+    fn send_command(
+        &self,
+        drone_id: NodeId,
+        command: DroneCommand,
+    ) -> Result<(), SimulationControllerError> {
+        if self.network.send_command(drone_id, command) {
+            Ok(())
+        } else {
+            Err(SimulationControllerError::DroneDisconnected(drone_id))
+        }
+    }
+
+    fn record_command(&mut self, command: ControllerCommand) {
+        if let Some(recorder) = &mut self.recorder {
+            if let Err(error) = recorder.record_command(command) {
+                log::warn!("Simulation Controller: command not recorded, {error}");
+            }
+        }
+    }
+
+    fn sync_neighbours(&mut self, node_id: NodeId) {
+        let neighbours = self.neighbours(node_id).unwrap_or_default();
+        self.network.set_neighbours(node_id, neighbours);
+    }
+}
+
+// (sender, receiver) of a packet in a PacketSent event, the hop_index already points to the receiver
+pub(crate) fn sent_link(packet: &Packet) -> Option<(NodeId, NodeId)> {
+    let hops = &packet.routing_header.hops;
+    let index = packet.routing_header.hop_index;
+    let receiver = *hops.get(index)?;
+    let sender = *hops.get(index.checked_sub(1)?)?;
+    Some((sender, receiver))
+}
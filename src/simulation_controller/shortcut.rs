@@ -0,0 +1,78 @@
+use crate::NetworkGraph;
+use crossbeam_channel::Sender;
+use std::collections::HashMap;
+use thiserror::Error;
+use wg_2024::network::NodeId;
+use wg_2024::packet::{NodeType, Packet, PacketType};
+
+#[derive(Debug, Clone, PartialEq, Error)]
+pub enum ShortcutError {
+    #[error("only Ack, Nack and FloodResponse can be delivered through the Simulation Controller")]
+    NotAllowed,
+    #[error("the routing header of the packet is empty")]
+    EmptyRoute,
+    #[error("node {0} is not a client or a server of the network")]
+    UnknownDestination(NodeId),
+    #[error("the channel of node {0} is disconnected")]
+    DestinationDisconnected(NodeId),
+}
+
+/// Delivers the packets of `DroneEvent::ControllerShortcut` to the client or server at the
+/// final hop of their routing header.
+#[derive(Debug, Clone, Default)]
+pub struct ShortcutDispatcher {
+    packet_send: HashMap<NodeId, Sender<Packet>>, //Packet channel of every client and server
+}
+
+impl ShortcutDispatcher {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Adds every node of the map that is a client or a server of the graph.
+    pub fn from_graph(packet_send: &HashMap<NodeId, Sender<Packet>>, graph: &NetworkGraph) -> Self {
+        let mut dispatcher = Self::new();
+        for (id, sender) in packet_send {
+            if matches!(
+                graph.node_type(*id),
+                Some(NodeType::Client | NodeType::Server)
+            ) {
+                dispatcher.add_destination(*id, sender.clone());
+            }
+        }
+        dispatcher
+    }
+
+    pub fn add_destination(&mut self, node_id: NodeId, sender: Sender<Packet>) {
+        self.packet_send.insert(node_id, sender);
+    }
+
+    pub fn remove_destination(&mut self, node_id: NodeId) -> Option<Sender<Packet>> {
+        self.packet_send.remove(&node_id)
+    }
+
+    /// Sends the packet to its final hop, with the hop index pointing to it, and returns its id.
+    pub fn dispatch(&self, mut packet: Packet) -> Result<NodeId, ShortcutError> {
+        if matches!(
+            packet.pack_type,
+            PacketType::MsgFragment(_) | PacketType::FloodRequest(_)
+        ) {
+            return Err(ShortcutError::NotAllowed);
+        }
+        let destination = *packet
+            .routing_header
+            .hops
+            .last()
+            .ok_or(ShortcutError::EmptyRoute)?;
+        let sender = self
+            .packet_send
+            .get(&destination)
+            .ok_or(ShortcutError::UnknownDestination(destination))?;
+
+        packet.routing_header.hop_index = packet.routing_header.hops.len() - 1;
+        sender
+            .send(packet)
+            .map_err(|_| ShortcutError::DestinationDisconnected(destination))?;
+        Ok(destination)
+    }
+}
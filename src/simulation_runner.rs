@@ -0,0 +1,315 @@
+use crate::{
+    validate_config, ControllerCommand, NackTypeCounters, NetworkInitializer,
+    NetworkInitializerError, Scenario, ScheduledAction, SimulationController,
+    SimulationControllerError,
+};
+use std::collections::BTreeMap;
+use std::fmt;
+use std::thread;
+use std::time::{Duration, Instant};
+use thiserror::Error;
+use wg_2024::config::Config;
+use wg_2024::network::{NodeId, SourceRoutingHeader};
+use wg_2024::packet::{Fragment, NodeType, Packet, PacketType, FRAGMENT_DSIZE};
+
+/// Time the runner waits for the last packets once the traffic stops.
+pub const DEFAULT_DRAIN_TIME: Duration = Duration::from_millis(200);
+
+#[derive(Debug, Error)]
+pub enum SimulationRunnerError {
+    #[error(transparent)]
+    Network(#[from] NetworkInitializerError),
+    #[error("Error applying {0:?}: {1}")]
+    Fault(ControllerCommand, SimulationControllerError),
+    #[error("Error sending a message: {0}")]
+    Traffic(SimulationControllerError),
+}
+
+impl SimulationRunnerError {
+    /// True if the run failed because the config could not be read or is not valid.
+    pub fn is_config_error(&self) -> bool {
+        matches!(
+            self,
+            SimulationRunnerError::Network(
+                NetworkInitializerError::Io(_)
+                    | NetworkInitializerError::Parse(_)
+                    | NetworkInitializerError::Validation(_)
+            )
+        )
+    }
+}
+
+/// Outcome of a `SimulationRunner` run.
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct SimulationSummary {
+    pub duration: Duration,
+    pub fragments_sent: u64,      //Fragments sent by the clients
+    pub fragments_delivered: u64, //Fragments received by the servers
+    pub unroutable: u64,          //Messages not sent because no route reached the server
+    pub dropped_per_drone: BTreeMap<NodeId, u64>,
+    pub nacks: NackTypeCounters, //Nacks received by the clients
+    pub controller_shortcuts: u64,
+    pub faults_applied: Vec<ControllerCommand>,
+    pub faults_refused: Vec<(ControllerCommand, SimulationControllerError)>,
+}
+
+impl SimulationSummary {
+    /// Share of the fragments sent that reached their server, 0 without traffic.
+    pub fn delivery_ratio(&self) -> f64 {
+        if self.fragments_sent == 0 {
+            0.0
+        } else {
+            self.fragments_delivered as f64 / self.fragments_sent as f64
+        }
+    }
+}
+
+impl fmt::Display for SimulationSummary {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        writeln!(f, "Simulation of {:.1}s", self.duration.as_secs_f64())?;
+        writeln!(
+            f,
+            "Delivery ratio: {:.3} ({} of {} fragments, {} unroutable)",
+            self.delivery_ratio(),
+            self.fragments_delivered,
+            self.fragments_sent,
+            self.unroutable
+        )?;
+        writeln!(f, "Drops per drone:")?;
+        if self.dropped_per_drone.is_empty() {
+            writeln!(f, "  none")?;
+        }
+        for (drone_id, dropped) in &self.dropped_per_drone {
+            writeln!(f, "  drone {drone_id}: {dropped}")?;
+        }
+        writeln!(
+            f,
+            "Nacks: {} (ErrorInRouting {}, DestinationIsDrone {}, Dropped {}, UnexpectedRecipient {})",
+            self.nacks.total(),
+            self.nacks.error_in_routing,
+            self.nacks.destination_is_drone,
+            self.nacks.dropped,
+            self.nacks.unexpected_recipient
+        )?;
+        writeln!(f, "Controller shortcuts: {}", self.controller_shortcuts)?;
+        writeln!(f, "Faults applied: {:?}", self.faults_applied)?;
+        for (command, error) in &self.faults_refused {
+            writeln!(f, "Fault refused: {command:?}, {error}")?;
+        }
+        Ok(())
+    }
+}
+
+/// Boots a network and runs a traffic and fault scenario on it for a fixed duration.
+///
+/// Every client sends a single-fragment message to the servers in turn, along the shortest
+/// route of the live topology, and the faults are applied through the `SimulationController`
+/// at their time. With a `Scenario`, its messages and commands replace the built-in traffic.
+/// Commands the controller refuses are reported, not treated as failures.
+pub struct SimulationRunner {
+    config: Config,
+    seed: Option<u64>,
+    duration: Duration,
+    message_interval: Option<Duration>, //Time between two messages of a client, None with a scenario
+    schedule: Vec<(Duration, ScheduledAction)>,
+}
+
+impl SimulationRunner {
+    pub fn new(config: Config) -> Self {
+        Self {
+            config,
+            seed: None,
+            duration: Duration::from_secs(5),
+            message_interval: Some(Duration::from_millis(10)),
+            schedule: Vec::new(),
+        }
+    }
+
+    #[must_use]
+    pub fn with_seed(mut self, seed: u64) -> Self {
+        self.seed = Some(seed);
+        self
+    }
+
+    #[must_use]
+    pub fn with_duration(mut self, duration: Duration) -> Self {
+        self.duration = duration;
+        self
+    }
+
+    #[must_use]
+    pub fn with_message_interval(mut self, message_interval: Duration) -> Self {
+        self.message_interval = Some(message_interval.max(Duration::from_micros(100)));
+        self
+    }
+
+    /// Applies a command `at` the given time since the start of the run.
+    #[must_use]
+    pub fn with_fault(mut self, at: Duration, command: ControllerCommand) -> Self {
+        self.schedule.push((at, ScheduledAction::Command(command)));
+        self
+    }
+
+    /// Runs the commands and messages of a scenario instead of the built-in traffic.
+    ///
+    /// The duration is not changed, `Scenario::end` gives the time the scenario needs.
+    #[must_use]
+    pub fn with_scenario(mut self, scenario: &Scenario) -> Self {
+        self.message_interval = None;
+        self.schedule.extend(
+            scenario
+                .schedule()
+                .into_iter()
+                .map(|scheduled| (scheduled.at, scheduled.action)),
+        );
+        self
+    }
+
+    pub fn run(self) -> Result<SimulationSummary, SimulationRunnerError> {
+        validate_config(&self.config).map_err(NetworkInitializerError::Validation)?;
+        let clients: Vec<NodeId> = self.config.client.iter().map(|client| client.id).collect();
+        let servers: Vec<NodeId> = self.config.server.iter().map(|server| server.id).collect();
+        let mut schedule = self.schedule;
+        schedule.sort_by_key(|(at, _)| *at);
+        let mut schedule = schedule.into_iter().peekable();
+
+        let mut initializer = NetworkInitializer::new(self.config);
+        if let Some(seed) = self.seed {
+            initializer = initializer.with_seed(seed);
+        }
+        let mut controller = SimulationController::new(initializer.start()?);
+        let mut summary = SimulationSummary {
+            duration: self.duration,
+            ..SimulationSummary::default()
+        };
+
+        let start = Instant::now();
+        let mut next_message = Duration::ZERO;
+        let mut session_id = 0;
+        let mut round = 0;
+        while start.elapsed() < self.duration {
+            while let Some((at, action)) = schedule.next_if(|(at, _)| *at <= start.elapsed()) {
+                log::info!("Simulation runner: at {:.3}s {action}", at.as_secs_f64());
+                let result = match action {
+                    ScheduledAction::Command(command) => {
+                        apply_fault(&mut controller, command, &mut summary)
+                    }
+                    ScheduledAction::SendMessage { client, server, .. } => {
+                        session_id += 1;
+                        send_message(&mut controller, client, server, session_id, &mut summary)
+                    }
+                };
+                if let Err(error) = result {
+                    let _ = controller.stop();
+                    return Err(error);
+                }
+            }
+
+            let message_due = self
+                .message_interval
+                .is_some_and(|_| start.elapsed() >= next_message);
+            if message_due && !servers.is_empty() {
+                for (index, client) in clients.iter().enumerate() {
+                    let server = servers[(round + index) % servers.len()];
+                    session_id += 1;
+                    if let Err(error) =
+                        send_message(&mut controller, *client, server, session_id, &mut summary)
+                    {
+                        let _ = controller.stop();
+                        return Err(error);
+                    }
+                }
+                round += 1;
+                next_message += self.message_interval.unwrap_or_default();
+            }
+
+            controller.poll_events();
+            collect_packets(&controller, &mut summary);
+            thread::sleep(Duration::from_micros(200));
+        }
+
+        let drain_until = Instant::now() + DEFAULT_DRAIN_TIME;
+        while Instant::now() < drain_until {
+            controller.poll_events();
+            collect_packets(&controller, &mut summary);
+            thread::sleep(Duration::from_millis(1));
+        }
+
+        let traffic = controller.traffic();
+        summary.dropped_per_drone = traffic
+            .dropped_per_drone
+            .iter()
+            .map(|(drone_id, dropped)| (*drone_id, *dropped))
+            .collect();
+        summary.controller_shortcuts = traffic.controller_shortcuts;
+        controller.stop()?;
+        Ok(summary)
+    }
+}
+
+// Applies a command, only a disconnected channel is a failure
+fn apply_fault(
+    controller: &mut SimulationController,
+    command: ControllerCommand,
+    summary: &mut SimulationSummary,
+) -> Result<(), SimulationRunnerError> {
+    match controller.apply(command) {
+        Ok(()) => summary.faults_applied.push(command),
+        Err(
+            error @ (SimulationControllerError::DroneDisconnected(_)
+            | SimulationControllerError::NodeDisconnected(_)),
+        ) => return Err(SimulationRunnerError::Fault(command, error)),
+        Err(error) => {
+            log::warn!("Simulation runner: {command:?} refused, {error}");
+            summary.faults_refused.push((command, error));
+        }
+    }
+    Ok(())
+}
+
+// Sends a one-fragment message from a client to a server along the shortest route
+fn send_message(
+    controller: &mut SimulationController,
+    client: NodeId,
+    server: NodeId,
+    session_id: u64,
+    summary: &mut SimulationSummary,
+) -> Result<(), SimulationRunnerError> {
+    let Some(hops) = controller.topology().shortest_path(client, server) else {
+        summary.unroutable += 1;
+        return Ok(());
+    };
+    let packet = Packet {
+        pack_type: PacketType::MsgFragment(Fragment {
+            fragment_index: 0,
+            total_n_fragments: 1,
+            length: FRAGMENT_DSIZE as u8,
+            data: [0; FRAGMENT_DSIZE],
+        }),
+        routing_header: SourceRoutingHeader { hop_index: 1, hops },
+        session_id,
+    };
+    let first_hop = packet.routing_header.hops[1];
+    controller
+        .inject(first_hop, packet)
+        .map_err(SimulationRunnerError::Traffic)?;
+    summary.fragments_sent += 1;
+    Ok(())
+}
+
+// Empties the channels of clients and servers, counting delivered fragments and received Nacks
+fn collect_packets(controller: &SimulationController, summary: &mut SimulationSummary) {
+    for (node_id, receiver) in controller.network().packet_recv() {
+        for packet in receiver.try_iter() {
+            match (&packet.pack_type, controller.node_type(*node_id)) {
+                (PacketType::MsgFragment(_), Some(NodeType::Server)) => {
+                    summary.fragments_delivered += 1
+                }
+                (PacketType::Nack(nack), Some(NodeType::Client)) => {
+                    summary.nacks.record(&nack.nack_type)
+                }
+                _ => {}
+            }
+        }
+    }
+}
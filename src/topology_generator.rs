@@ -0,0 +1,315 @@
+use crate::{validate_config, ValidationError};
+use rand::rngs::StdRng;
+use rand::seq::{IndexedRandom, SliceRandom};
+use rand::{Rng, SeedableRng};
+use std::collections::BTreeSet;
+use thiserror::Error;
+use wg_2024::config::{Client, Config, Drone, Server};
+use wg_2024::network::NodeId;
+
+#[derive(Debug, Clone, PartialEq, Error)]
+pub enum TopologyGeneratorError {
+    #[error("The topology needs {0} node ids, at most 255 are available.")]
+    TooManyNodes(usize),
+    #[error("The topology has no drones.")]
+    NoDrones,
+    #[error("Servers must be connected to two drones, the topology has only one.")]
+    NotEnoughDronesForServers,
+    #[error("The PDR distribution is not valid: {0}")]
+    InvalidPdr(String),
+    #[error("The generated config is not valid: {0:?}")]
+    Validation(Vec<ValidationError>),
+}
+
+/// Shape of the drone network of a generated topology, with its size.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum TopologyShape {
+    Ring { drones: usize },
+    Star { drones: usize },                  //The first drone is linked to all the others
+    Grid { rows: usize, columns: usize },
+    Tree { depth: usize, branching: usize }, //Full tree, depth 0 is the root alone
+    DoubleChain { length: usize },           //Two chains of `length` drones linked rung by rung
+    Butterfly { dimension: u32 },            //`dimension + 1` levels of `2^dimension` drones
+    Random { drones: usize, average_degree: f64 },
+}
+
+impl TopologyShape {
+    pub fn drone_count(&self) -> usize {
+        match *self {
+            TopologyShape::Ring { drones }
+            | TopologyShape::Star { drones }
+            | TopologyShape::Random { drones, .. } => drones,
+            TopologyShape::Grid { rows, columns } => rows.saturating_mul(columns),
+            TopologyShape::Tree { depth, branching } => (0..=depth)
+                .map(|level| branching.saturating_pow(level as u32))
+                .fold(0, usize::saturating_add),
+            TopologyShape::DoubleChain { length } => length.saturating_mul(2),
+            TopologyShape::Butterfly { dimension } => {
+                1usize.checked_shl(dimension).map_or(usize::MAX, |rows| {
+                    rows.saturating_mul(dimension as usize + 1)
+                })
+            }
+        }
+    }
+
+    // Links between drones, as indexes from 0
+    fn drone_links(&self, rng: &mut StdRng) -> BTreeSet<(usize, usize)> {
+        let count = self.drone_count();
+        let mut links = BTreeSet::new();
+        let mut link = |a: usize, b: usize| {
+            if a != b {
+                links.insert((a.min(b), a.max(b)));
+            }
+        };
+        match *self {
+            TopologyShape::Ring { drones } => {
+                for index in 0..drones {
+                    link(index, (index + 1) % drones);
+                }
+            }
+            TopologyShape::Star { drones } => {
+                for index in 1..drones {
+                    link(0, index);
+                }
+            }
+            TopologyShape::Grid { rows, columns } => {
+                for row in 0..rows {
+                    for column in 0..columns {
+                        let index = row * columns + column;
+                        if column + 1 < columns {
+                            link(index, index + 1);
+                        }
+                        if row + 1 < rows {
+                            link(index, index + columns);
+                        }
+                    }
+                }
+            }
+            TopologyShape::Tree { branching, .. } => {
+                for index in 1..count {
+                    link(index, (index - 1) / branching);
+                }
+            }
+            TopologyShape::DoubleChain { length } => {
+                for index in 0..length {
+                    link(index, length + index);
+                    if index + 1 < length {
+                        link(index, index + 1);
+                        link(length + index, length + index + 1);
+                    }
+                }
+            }
+            TopologyShape::Butterfly { dimension } => {
+                let rows = 1 << dimension;
+                for level in 0..dimension as usize {
+                    for row in 0..rows {
+                        let index = level * rows + row;
+                        link(index, index + rows);
+                        link(index, (level + 1) * rows + (row ^ (1 << level)));
+                    }
+                }
+            }
+            TopologyShape::Random {
+                drones,
+                average_degree,
+            } => {
+                //A random spanning tree keeps the network connected, random links are added on top
+                for index in 1..drones {
+                    link(index, rng.random_range(0..index));
+                }
+                let max_links = drones * drones.saturating_sub(1) / 2;
+                let target = ((average_degree.max(0.0) * drones as f64 / 2.0).round() as usize)
+                    .clamp(drones.saturating_sub(1), max_links);
+                let mut missing: Vec<(usize, usize)> = (0..drones)
+                    .flat_map(|a| ((a + 1)..drones).map(move |b| (a, b)))
+                    .collect();
+                missing.shuffle(rng);
+                let mut added = drones.saturating_sub(1);
+                for (a, b) in missing {
+                    if added >= target {
+                        break;
+                    }
+                    if !links.contains(&(a, b)) {
+                        links.insert((a, b));
+                        added += 1;
+                    }
+                }
+            }
+        }
+        links
+    }
+}
+
+/// Distribution the PDR of every generated drone is drawn from.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum PdrDistribution {
+    Constant(f32),
+    Uniform { min: f32, max: f32 },
+    Normal { mean: f32, std_dev: f32 }, //Clamped to [0, 1]
+}
+
+impl PdrDistribution {
+    fn check(&self) -> Result<(), TopologyGeneratorError> {
+        let in_bounds = |pdr: f32| (0.0..=1.0).contains(&pdr);
+        let valid = match *self {
+            PdrDistribution::Constant(pdr) => in_bounds(pdr),
+            PdrDistribution::Uniform { min, max } => in_bounds(min) && in_bounds(max) && min <= max,
+            PdrDistribution::Normal { mean, std_dev } => in_bounds(mean) && std_dev >= 0.0,
+        };
+        if valid {
+            Ok(())
+        } else {
+            Err(TopologyGeneratorError::InvalidPdr(format!("{self:?}")))
+        }
+    }
+
+    fn sample(&self, rng: &mut StdRng) -> f32 {
+        match *self {
+            PdrDistribution::Constant(pdr) => pdr,
+            PdrDistribution::Uniform { min, max } => rng.random_range(min..=max),
+            PdrDistribution::Normal { mean, std_dev } => {
+                //Box-Muller transform
+                let u1: f32 = 1.0 - rng.random::<f32>();
+                let u2: f32 = rng.random();
+                let z = (-2.0 * u1.ln()).sqrt() * (std::f32::consts::TAU * u2).cos();
+                (mean + std_dev * z).clamp(0.0, 1.0)
+            }
+        }
+    }
+}
+
+/// Builds a `Config` of the given shape, with clients and servers attached at random drones.
+///
+/// Drones get the ids from 1, then clients and servers follow. Every client is linked to one
+/// or two drones and every server to two or three, as the AP protocol requires. The same seed
+/// always gives the same config, and every config passes `validate_config`.
+pub struct TopologyGenerator {
+    shape: TopologyShape,
+    clients: usize,
+    servers: usize,
+    pdr: PdrDistribution,
+    seed: u64,
+}
+
+impl TopologyGenerator {
+    pub fn new(shape: TopologyShape) -> Self {
+        Self {
+            shape,
+            clients: 1,
+            servers: 1,
+            pdr: PdrDistribution::Constant(0.0),
+            seed: 0,
+        }
+    }
+
+    #[must_use]
+    pub fn clients(mut self, clients: usize) -> Self {
+        self.clients = clients;
+        self
+    }
+
+    #[must_use]
+    pub fn servers(mut self, servers: usize) -> Self {
+        self.servers = servers;
+        self
+    }
+
+    #[must_use]
+    pub fn pdr(mut self, pdr: PdrDistribution) -> Self {
+        self.pdr = pdr;
+        self
+    }
+
+    #[must_use]
+    pub fn seed(mut self, seed: u64) -> Self {
+        self.seed = seed;
+        self
+    }
+
+    pub fn generate(&self) -> Result<Config, TopologyGeneratorError> {
+        let drone_count = self.shape.drone_count();
+        let total = drone_count
+            .saturating_add(self.clients)
+            .saturating_add(self.servers);
+        if total > usize::from(NodeId::MAX) {
+            return Err(TopologyGeneratorError::TooManyNodes(total));
+        }
+        if drone_count == 0 {
+            return Err(TopologyGeneratorError::NoDrones);
+        }
+        if self.servers > 0 && drone_count < 2 {
+            return Err(TopologyGeneratorError::NotEnoughDronesForServers);
+        }
+        self.pdr.check()?;
+
+        let mut rng = StdRng::seed_from_u64(self.seed);
+        let mut drones: Vec<Drone> = (0..drone_count)
+            .map(|index| Drone {
+                id: drone_id(index),
+                connected_node_ids: Vec::new(),
+                pdr: self.pdr.sample(&mut rng),
+            })
+            .collect();
+        for (a, b) in self.shape.drone_links(&mut rng) {
+            drones[a].connected_node_ids.push(drone_id(b));
+            drones[b].connected_node_ids.push(drone_id(a));
+        }
+
+        let mut next_id = drone_id(drone_count);
+        let mut clients = Vec::new();
+        for _ in 0..self.clients {
+            let degree = if drone_count > 1 && rng.random_bool(0.5) {
+                2
+            } else {
+                1
+            };
+            let connected_drone_ids = attach(&mut drones, next_id, degree, &mut rng);
+            clients.push(Client {
+                id: next_id,
+                connected_drone_ids,
+            });
+            next_id = next_id.wrapping_add(1);
+        }
+        let mut servers = Vec::new();
+        for _ in 0..self.servers {
+            let degree = if drone_count > 2 && rng.random_bool(0.5) {
+                3
+            } else {
+                2
+            };
+            let connected_drone_ids = attach(&mut drones, next_id, degree, &mut rng);
+            servers.push(Server {
+                id: next_id,
+                connected_drone_ids,
+            });
+            next_id = next_id.wrapping_add(1);
+        }
+        for drone in &mut drones {
+            drone.connected_node_ids.sort_unstable();
+        }
+
+        let config = Config {
+            drone: drones,
+            client: clients,
+            server: servers,
+        };
+        validate_config(&config).map_err(TopologyGeneratorError::Validation)?;
+        Ok(config)
+    }
+}
+
+fn drone_id(index: usize) -> NodeId {
+    index as NodeId + 1
+}
+
+// Links a client or server to `degree` distinct random drones, returning their sorted ids
+fn attach(drones: &mut [Drone], node_id: NodeId, degree: usize, rng: &mut StdRng) -> Vec<NodeId> {
+    let indexes: Vec<usize> = (0..drones.len()).collect();
+    let mut connected = Vec::new();
+    for index in indexes.choose_multiple(rng, degree) {
+        drones[*index].connected_node_ids.push(node_id);
+        connected.push(drones[*index].id);
+    }
+    connected.sort_unstable();
+    connected
+}
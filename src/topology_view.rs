@@ -0,0 +1,291 @@
+use crate::simulation_controller::sent_link;
+use crate::{DroneSnapshot, DroneState, NetworkGraph, SimulationController};
+use std::collections::BTreeMap;
+use std::fmt::Write;
+use wg_2024::config::Config;
+use wg_2024::controller::DroneEvent;
+use wg_2024::network::NodeId;
+use wg_2024::packet::NodeType;
+
+mod layout;
+pub use layout::*;
+
+const SVG_WIDTH: f64 = 800.0;
+const SVG_HEIGHT: f64 = 600.0;
+const SVG_MARGIN: f64 = 40.0;
+const NODE_RADIUS: f64 = 18.0;
+
+/// Topology of a network with the state of its drones and the packets sent on every link,
+/// rendered as Graphviz DOT or as SVG.
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct TopologyView {
+    graph: NetworkGraph,
+    pdr: BTreeMap<NodeId, f32>,
+    states: BTreeMap<NodeId, DroneState>,
+    sent_per_link: BTreeMap<(NodeId, NodeId), u64>, //PacketSent per (sender, receiver)
+}
+
+impl TopologyView {
+    /// View of a config before boot: every drone is active and no packet was sent.
+    pub fn from_config(config: &Config) -> Self {
+        Self {
+            graph: NetworkGraph::from_config(config),
+            pdr: config
+                .drone
+                .iter()
+                .map(|drone| (drone.id, drone.pdr))
+                .collect(),
+            states: config
+                .drone
+                .iter()
+                .map(|drone| (drone.id, DroneState::Active))
+                .collect(),
+            sent_per_link: BTreeMap::new(),
+        }
+    }
+
+    /// View of the live topology of a controller, with the traffic it has handled so far.
+    ///
+    /// Crashed drones are shown as `DroneState::Crashed`, without links.
+    pub fn from_controller(controller: &SimulationController) -> Self {
+        let graph = controller.topology().clone();
+        let drones = graph.nodes_of_type(NodeType::Drone);
+        Self {
+            pdr: drones
+                .iter()
+                .filter_map(|id| controller.pdr(*id).map(|pdr| (*id, pdr)))
+                .collect(),
+            states: drones
+                .iter()
+                .map(|id| {
+                    let state = if controller.is_crashed(*id) {
+                        DroneState::Crashed
+                    } else {
+                        DroneState::Active
+                    };
+                    (*id, state)
+                })
+                .collect(),
+            sent_per_link: controller
+                .traffic()
+                .sent_per_link
+                .iter()
+                .map(|(link, count)| (*link, *count))
+                .collect(),
+            graph,
+        }
+    }
+
+    pub fn graph(&self) -> &NetworkGraph {
+        &self.graph
+    }
+
+    pub fn set_state(&mut self, drone_id: NodeId, state: DroneState) {
+        self.states.insert(drone_id, state);
+    }
+
+    pub fn set_pdr(&mut self, drone_id: NodeId, pdr: f32) {
+        self.pdr.insert(drone_id, pdr);
+    }
+
+    /// Takes the state and PDR of a drone from its snapshot.
+    pub fn update_from_snapshot(&mut self, snapshot: &DroneSnapshot) {
+        self.set_state(snapshot.id, snapshot.state.clone());
+        self.set_pdr(snapshot.id, snapshot.pdr);
+    }
+
+    /// Counts the packet of a `PacketSent` on its link, other events are ignored.
+    pub fn record_event(&mut self, event: &DroneEvent) {
+        if let DroneEvent::PacketSent(packet) = event {
+            if let Some(link) = sent_link(packet) {
+                *self.sent_per_link.entry(link).or_default() += 1;
+            }
+        }
+    }
+
+    /// Packets sent on a link, in both directions.
+    pub fn packets_on_link(&self, a: NodeId, b: NodeId) -> u64 {
+        self.sent_per_link.get(&(a, b)).copied().unwrap_or_default()
+            + self.sent_per_link.get(&(b, a)).copied().unwrap_or_default()
+    }
+
+    /// Undirected Graphviz graph: drones are ellipses, clients boxes and servers cylinders.
+    ///
+    /// Links with traffic are labelled with the packets sent in both directions, the tooltip
+    /// has the count of each direction.
+    pub fn to_dot(&self) -> String {
+        let mut dot =
+            String::from("graph network {\n    node [style=filled, fontname=\"Helvetica\"];\n");
+        for node_id in self.graph.nodes() {
+            let (shape, label) = match self.graph.node_type(node_id) {
+                Some(NodeType::Drone) => (
+                    "ellipse",
+                    format!("{node_id}\\npdr {:.2}", self.pdr(node_id)),
+                ),
+                Some(NodeType::Client) => ("box", format!("{node_id}")),
+                _ => ("cylinder", format!("{node_id}")),
+            };
+            let style = if self.state(node_id) == Some(&DroneState::Crashed) {
+                ", style=\"filled,dashed\""
+            } else {
+                ""
+            };
+            let _ = writeln!(
+                dot,
+                "    {node_id} [label=\"{label}\", shape={shape}, fillcolor=\"{}\"{style}];",
+                self.fill_colour(node_id)
+            );
+        }
+        for (a, b) in self.graph.edges() {
+            let packets = self.packets_on_link(a, b);
+            if packets == 0 {
+                let _ = writeln!(dot, "    {a} -- {b};");
+            } else {
+                let _ = writeln!(
+                    dot,
+                    "    {a} -- {b} [label=\"{packets}\", tooltip=\"{a} -> {b}: {}, {b} -> {a}: {}\", penwidth={:.1}];",
+                    self.packets_sent(a, b),
+                    self.packets_sent(b, a),
+                    pen_width(packets)
+                );
+            }
+        }
+        dot.push_str("}\n");
+        dot
+    }
+
+    /// Standalone SVG picture of the topology, placed with `force_directed_layout`.
+    ///
+    /// Drones are circles, clients squares and servers diamonds, with the colours of `to_dot`.
+    pub fn to_svg(&self) -> String {
+        let positions: BTreeMap<NodeId, (f64, f64)> = force_directed_layout(
+            &self.graph,
+            SVG_WIDTH - 2.0 * SVG_MARGIN,
+            SVG_HEIGHT - 2.0 * SVG_MARGIN,
+            DEFAULT_LAYOUT_ITERATIONS,
+        )
+        .into_iter()
+        .map(|(node_id, (x, y))| (node_id, (x + SVG_MARGIN, y + SVG_MARGIN)))
+        .collect();
+
+        let mut svg = String::new();
+        let _ = writeln!(
+            svg,
+            "<svg xmlns=\"http://www.w3.org/2000/svg\" width=\"{SVG_WIDTH}\" height=\"{SVG_HEIGHT}\" viewBox=\"0 0 {SVG_WIDTH} {SVG_HEIGHT}\" font-family=\"Helvetica\" font-size=\"12\">"
+        );
+        let _ = writeln!(
+            svg,
+            "<rect width=\"{SVG_WIDTH}\" height=\"{SVG_HEIGHT}\" fill=\"white\"/>"
+        );
+        for (a, b) in self.graph.edges() {
+            let ((x1, y1), (x2, y2)) = (positions[&a], positions[&b]);
+            let packets = self.packets_on_link(a, b);
+            let _ = writeln!(
+                svg,
+                "<line x1=\"{x1:.1}\" y1=\"{y1:.1}\" x2=\"{x2:.1}\" y2=\"{y2:.1}\" stroke=\"#555555\" stroke-width=\"{:.1}\"/>",
+                pen_width(packets)
+            );
+            if packets > 0 {
+                let _ = writeln!(
+                    svg,
+                    "<text x=\"{:.1}\" y=\"{:.1}\" text-anchor=\"middle\" fill=\"#333333\">{packets}<title>{a} -&gt; {b}: {}, {b} -&gt; {a}: {}</title></text>",
+                    (x1 + x2) / 2.0,
+                    (y1 + y2) / 2.0 - 4.0,
+                    self.packets_sent(a, b),
+                    self.packets_sent(b, a)
+                );
+            }
+        }
+        for (node_id, (x, y)) in &positions {
+            let fill = self.fill_colour(*node_id);
+            let dash = if self.state(*node_id) == Some(&DroneState::Crashed) {
+                " stroke-dasharray=\"4 2\""
+            } else {
+                ""
+            };
+            let shape = match self.graph.node_type(*node_id) {
+                Some(NodeType::Drone) => format!(
+                    "<circle cx=\"{x:.1}\" cy=\"{y:.1}\" r=\"{NODE_RADIUS}\" fill=\"{fill}\" stroke=\"black\"{dash}/>"
+                ),
+                Some(NodeType::Client) => format!(
+                    "<rect x=\"{:.1}\" y=\"{:.1}\" width=\"{}\" height=\"{}\" fill=\"{fill}\" stroke=\"black\"/>",
+                    x - NODE_RADIUS,
+                    y - NODE_RADIUS,
+                    2.0 * NODE_RADIUS,
+                    2.0 * NODE_RADIUS
+                ),
+                _ => format!(
+                    "<polygon points=\"{x:.1},{:.1} {:.1},{y:.1} {x:.1},{:.1} {:.1},{y:.1}\" fill=\"{fill}\" stroke=\"black\"/>",
+                    y - NODE_RADIUS * 1.3,
+                    x + NODE_RADIUS * 1.3,
+                    y + NODE_RADIUS * 1.3,
+                    x - NODE_RADIUS * 1.3
+                ),
+            };
+            let _ = writeln!(svg, "{shape}");
+            let _ = writeln!(
+                svg,
+                "<text x=\"{x:.1}\" y=\"{:.1}\" text-anchor=\"middle\">{node_id}</text>",
+                y + 4.0
+            );
+        }
+        svg.push_str("</svg>\n");
+        svg
+    }
+
+    fn pdr(&self, drone_id: NodeId) -> f32 {
+        self.pdr.get(&drone_id).copied().unwrap_or_default()
+    }
+
+    fn state(&self, drone_id: NodeId) -> Option<&DroneState> {
+        self.states.get(&drone_id)
+    }
+
+    fn packets_sent(&self, sender: NodeId, receiver: NodeId) -> u64 {
+        self.sent_per_link
+            .get(&(sender, receiver))
+            .copied()
+            .unwrap_or_default()
+    }
+
+    fn fill_colour(&self, node_id: NodeId) -> String {
+        match self.graph.node_type(node_id) {
+            Some(NodeType::Drone) => drone_colour(
+                self.state(node_id).unwrap_or(&DroneState::Active),
+                self.pdr(node_id),
+            ),
+            Some(NodeType::Client) => String::from("#85c1e9"),
+            _ => String::from("#bb8fce"),
+        }
+    }
+}
+
+impl From<&Config> for TopologyView {
+    fn from(config: &Config) -> Self {
+        Self::from_config(config)
+    }
+}
+
+/// Fill colour of a drone: active drones go from green (PDR 0) to red (PDR 1), crashing drones
+/// are orange and crashed ones grey.
+pub fn drone_colour(state: &DroneState, pdr: f32) -> String {
+    match state {
+        DroneState::Active => {
+            let pdr = f64::from(pdr.clamp(0.0, 1.0));
+            let channel = |from: f64, to: f64| (from + (to - from) * pdr).round() as u8;
+            format!(
+                "#{:02x}{:02x}{:02x}",
+                channel(46.0, 231.0),
+                channel(204.0, 76.0),
+                channel(113.0, 60.0)
+            )
+        }
+        DroneState::Crashing => String::from("#f39c12"),
+        DroneState::Crashed => String::from("#7f8c8d"),
+    }
+}
+
+// Thicker lines for busier links, capped
+fn pen_width(packets: u64) -> f64 {
+    (1.0 + (packets as f64).ln_1p()).min(6.0)
+}
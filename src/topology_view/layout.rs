@@ -0,0 +1,95 @@
+use crate::NetworkGraph;
+use std::collections::BTreeMap;
+use wg_2024::network::NodeId;
+
+/// Iterations of `force_directed_layout` used by the SVG writer.
+pub const DEFAULT_LAYOUT_ITERATIONS: usize = 300;
+
+/// Position of every node of the graph in a `width` x `height` area (Fruchterman-Reingold).
+///
+/// Nodes start on a circle in id order and every step has no randomness, so the same graph
+/// always gets the same layout.
+pub fn force_directed_layout(
+    graph: &NetworkGraph,
+    width: f64,
+    height: f64,
+    iterations: usize,
+) -> BTreeMap<NodeId, (f64, f64)> {
+    let nodes = graph.nodes();
+    let count = nodes.len();
+    let (center_x, center_y) = (width / 2.0, height / 2.0);
+    let mut positions: Vec<(f64, f64)> = nodes
+        .iter()
+        .enumerate()
+        .map(|(index, _)| {
+            let angle = std::f64::consts::TAU * index as f64 / count as f64;
+            (
+                center_x + center_x / 2.0 * angle.cos(),
+                center_y + center_y / 2.0 * angle.sin(),
+            )
+        })
+        .collect();
+    if count < 2 {
+        return nodes
+            .into_iter()
+            .map(|node_id| (node_id, (center_x, center_y)))
+            .collect();
+    }
+
+    let index_of: BTreeMap<NodeId, usize> = nodes
+        .iter()
+        .enumerate()
+        .map(|(index, node_id)| (*node_id, index))
+        .collect();
+    let edges: Vec<(usize, usize)> = graph
+        .edges()
+        .into_iter()
+        .map(|(a, b)| (index_of[&a], index_of[&b]))
+        .collect();
+
+    let k = (width * height / count as f64).sqrt(); //Ideal distance between two nodes
+    let initial_temperature = width.min(height) / 10.0;
+    for iteration in 0..iterations {
+        let mut displacement = vec![(0.0, 0.0); count];
+        for a in 0..count {
+            for b in (a + 1)..count {
+                let (dx, dy, distance) = delta(positions[a], positions[b]);
+                let force = k * k / distance;
+                displacement[a].0 += dx / distance * force;
+                displacement[a].1 += dy / distance * force;
+                displacement[b].0 -= dx / distance * force;
+                displacement[b].1 -= dy / distance * force;
+            }
+        }
+        for (a, b) in &edges {
+            let (dx, dy, distance) = delta(positions[*a], positions[*b]);
+            let force = distance * distance / k;
+            displacement[*a].0 -= dx / distance * force;
+            displacement[*a].1 -= dy / distance * force;
+            displacement[*b].0 += dx / distance * force;
+            displacement[*b].1 += dy / distance * force;
+        }
+
+        let temperature = initial_temperature * (1.0 - iteration as f64 / iterations as f64);
+        for (position, (dx, dy)) in positions.iter_mut().zip(displacement) {
+            let length = (dx * dx + dy * dy).sqrt().max(f64::EPSILON);
+            let step = length.min(temperature);
+            position.0 = (position.0 + dx / length * step).clamp(0.0, width);
+            position.1 = (position.1 + dy / length * step).clamp(0.0, height);
+        }
+    }
+
+    nodes.into_iter().zip(positions).collect()
+}
+
+// Vector from b to a and its length, never zero
+fn delta(a: (f64, f64), b: (f64, f64)) -> (f64, f64, f64) {
+    let (dx, dy) = (a.0 - b.0, a.1 - b.1);
+    let distance = (dx * dx + dy * dy).sqrt();
+    if distance < 0.01 {
+        //Overlapping nodes are pushed apart along a fixed direction
+        (0.01, 0.0, 0.01)
+    } else {
+        (dx, dy, distance)
+    }
+}
@@ -0,0 +1,266 @@
+use dronegowski::{
+    parse_config, ApplicationClient, ApplicationError, ChatServer, Client, ContentServer,
+    DecodeError, ErrorResponse, NetworkHandle, NetworkInitializer, Request, RequestHandler,
+    Response, Server, ServerKind, PROTOCOL_VERSION,
+};
+use std::thread::{self, JoinHandle};
+use std::time::Duration;
+
+fn start_network(lossless: bool) -> NetworkHandle {
+    let mut config = parse_config("tests/common/config.toml").expect("Error loading the config");
+    if lossless {
+        for drone in &mut config.drone {
+            drone.pdr = 0.0;
+        }
+    }
+    NetworkInitializer::new(config)
+        .with_seed(5)
+        .start()
+        .expect("Error starting the network")
+}
+
+fn spawn<H: RequestHandler + Send + 'static>(mut server: Server<H>) -> JoinHandle<Server<H>> {
+    thread::spawn(move || {
+        server.run();
+        server
+    })
+}
+
+fn client(network: &NetworkHandle, client_id: u8) -> ApplicationClient {
+    ApplicationClient::new(
+        Client::from_network(network, client_id).expect("Error creating the client"),
+    )
+}
+
+#[test]
+fn messages_survive_encoding() {
+    let requests = vec![
+        Request::ServerType,
+        Request::ListFiles,
+        Request::GetFile("notes.txt".to_string()),
+        Request::Register("Alice".to_string()),
+        Request::ListClients,
+        Request::SendMessage {
+            to: 5,
+            message: "ciao 👋".to_string(),
+        },
+    ];
+    for request in requests {
+        assert_eq!(Request::decode(&request.encode()), Ok(request));
+    }
+
+    let responses = vec![
+        Response::ServerType(ServerKind::Media),
+        Response::FileList(vec!["a.txt".to_string(), String::new()]),
+        Response::File {
+            name: "image.png".to_string(),
+            content: (0..=255).collect(),
+        },
+        Response::Registered,
+        Response::ClientList(vec![(4, "Alice".to_string()), (5, "Bob".to_string())]),
+        Response::MessageSent,
+        Response::MessageFrom {
+            from: 4,
+            message: "hi".to_string(),
+        },
+        Response::Error(ErrorResponse::FileNotFound("b.txt".to_string())),
+        Response::Error(ErrorResponse::UnknownClient(9)),
+    ];
+    for response in responses {
+        let bytes = response.encode();
+        assert_eq!(bytes[0], PROTOCOL_VERSION);
+        assert_eq!(Response::decode(&bytes), Ok(response));
+    }
+}
+
+#[test]
+fn malformed_messages_are_refused() {
+    let bytes = Request::GetFile("notes.txt".to_string()).encode();
+    assert_eq!(Request::decode(&[]), Err(DecodeError::Truncated));
+    assert_eq!(
+        Request::decode(&bytes[..bytes.len() - 1]),
+        Err(DecodeError::Truncated)
+    );
+    assert_eq!(
+        Request::decode(&[bytes.as_slice(), &[0]].concat()),
+        Err(DecodeError::TrailingBytes(1))
+    );
+    assert_eq!(
+        Request::decode(&[PROTOCOL_VERSION + 1, 0, 1]),
+        Err(DecodeError::UnsupportedVersion(PROTOCOL_VERSION + 1))
+    );
+    assert_eq!(
+        Request::decode(&[PROTOCOL_VERSION, 0, 42]),
+        Err(DecodeError::UnknownTag(42))
+    );
+    assert_eq!(
+        Request::decode(&[PROTOCOL_VERSION, 0, 4, 2, 0, 0, 0, 0xff, 0xfe]),
+        Err(DecodeError::InvalidUtf8)
+    );
+    assert!(matches!(
+        Response::decode(&bytes),
+        Err(DecodeError::WrongDirection(_))
+    ));
+    //A list cannot claim more items than the bytes left
+    assert_eq!(
+        Response::decode(&[PROTOCOL_VERSION, 1, 2, 0xff, 0xff, 0xff, 0xff]),
+        Err(DecodeError::Truncated)
+    );
+
+    //The handlers answer with an explicit error instead of nothing
+    let mut text = ContentServer::text();
+    let mut chat = ChatServer::new();
+    for (request, error) in [
+        (
+            vec![PROTOCOL_VERSION, 0, 42],
+            ErrorResponse::UnknownMessage(42),
+        ),
+        (vec![7, 0, 1], ErrorResponse::UnsupportedVersion(7)),
+        (vec![PROTOCOL_VERSION, 0, 3, 9], ErrorResponse::Malformed),
+    ] {
+        let expected = Some(Response::Error(error).encode());
+        assert_eq!(text.handle_request(4, &request), expected);
+        assert_eq!(chat.handle_request(4, &request), expected);
+    }
+    assert_eq!(
+        text.handle_request(4, &Request::ListClients.encode()),
+        Some(Response::Error(ErrorResponse::UnsupportedRequest).encode())
+    );
+    assert_eq!(
+        chat.handle_request(4, &Request::ListFiles.encode()),
+        Some(Response::Error(ErrorResponse::UnsupportedRequest).encode())
+    );
+}
+
+#[test]
+fn responses_are_matched_to_requests() {
+    let get_file = Request::GetFile("a.txt".to_string());
+    assert!(get_file.accepts(&Response::File {
+        name: "a.txt".to_string(),
+        content: Vec::new(),
+    }));
+    assert!(get_file.accepts(&Response::Error(ErrorResponse::FileNotFound(
+        "a.txt".to_string()
+    ))));
+    assert!(get_file.accepts(&Response::Error(ErrorResponse::UnsupportedRequest)));
+    assert!(!get_file.accepts(&Response::FileList(vec!["a.txt".to_string()])));
+    assert!(!get_file.accepts(&Response::File {
+        name: "b.txt".to_string(),
+        content: Vec::new(),
+    }));
+
+    let send = Request::SendMessage {
+        to: 5,
+        message: "hi".to_string(),
+    };
+    assert!(send.accepts(&Response::MessageSent));
+    assert!(send.accepts(&Response::Error(ErrorResponse::UnknownClient(5))));
+    assert!(!send.accepts(&Response::Error(ErrorResponse::UnknownClient(9))));
+    assert!(!Request::ServerType.accepts(&Response::Error(ErrorResponse::NotRegistered)));
+}
+
+#[test]
+fn late_responses_are_not_taken_for_the_next_request() {
+    let network = start_network(true);
+    let mut files = ContentServer::text().with_file("a.txt", b"hello");
+    //The file list is sent after the client stopped waiting for it
+    let handler = move |client_id, request: &[u8]| {
+        if Request::decode(request) == Ok(Request::ListFiles) {
+            thread::sleep(Duration::from_millis(800));
+        }
+        files.handle_request(client_id, request)
+    };
+    let server = Server::from_network(&network, 6, handler).expect("Error creating the server");
+    let stop = server.stop_token();
+    let server = spawn(server);
+
+    let mut client = client(&network, 4).with_timeout(Duration::from_millis(200));
+    assert!(matches!(
+        client.list_files(6),
+        Err(ApplicationError::Client(_))
+    ));
+    let mut client = client.with_timeout(Duration::from_secs(5));
+    assert_eq!(client.get_file(6, "a.txt"), Ok(b"hello".to_vec()));
+
+    stop.stop();
+    server.join().expect("The server panicked");
+    network.stop().expect("Error stopping the network");
+}
+
+#[test]
+fn files_are_served_over_lossy_network() {
+    let network = start_network(false);
+    let story: Vec<u8> = b"C'era una volta un drone. ".repeat(100);
+    let handler = ContentServer::text()
+        .with_file("story.txt", &story)
+        .with_file("empty.txt", b"");
+    let server = Server::from_network(&network, 6, handler).expect("Error creating the server");
+    let stop = server.stop_token();
+    let server = spawn(server);
+
+    let mut client = client(&network, 4);
+    assert_eq!(client.server_type(6), Ok(ServerKind::Text));
+    assert_eq!(
+        client.list_files(6),
+        Ok(vec!["empty.txt".to_string(), "story.txt".to_string()])
+    );
+    assert_eq!(client.get_file(6, "story.txt"), Ok(story));
+    assert_eq!(client.get_file(6, "empty.txt"), Ok(Vec::new()));
+    assert_eq!(
+        client.get_file(6, "missing.txt"),
+        Err(ApplicationError::Server(ErrorResponse::FileNotFound(
+            "missing.txt".to_string()
+        )))
+    );
+    assert_eq!(
+        client.register(6, "Alice"),
+        Err(ApplicationError::Server(ErrorResponse::UnsupportedRequest))
+    );
+
+    stop.stop();
+    let server = server.join().expect("The server panicked");
+    assert_eq!(server.stats().requests_handled, 6);
+    network.stop().expect("Error stopping the network");
+}
+
+#[test]
+fn chat_messages_reach_other_clients() {
+    let network = start_network(true);
+    let server =
+        Server::from_network(&network, 6, ChatServer::new()).expect("Error creating the server");
+    let stop = server.stop_token();
+    let server = spawn(server);
+
+    let mut alice = client(&network, 4);
+    let mut bob = client(&network, 5);
+    assert_eq!(
+        alice.list_clients(6),
+        Err(ApplicationError::Server(ErrorResponse::NotRegistered))
+    );
+    assert_eq!(alice.register(6, "Alice"), Ok(()));
+    assert_eq!(bob.register(6, "Bob"), Ok(()));
+    assert_eq!(
+        alice.list_clients(6),
+        Ok(vec![(4, "Alice".to_string()), (5, "Bob".to_string())])
+    );
+
+    assert_eq!(alice.send_chat_message(6, 5, "Ciao Bob!"), Ok(()));
+    assert_eq!(
+        bob.recv_chat_message(Duration::from_secs(5)),
+        Some((6, 4, "Ciao Bob!".to_string()))
+    );
+    assert_eq!(
+        bob.send_chat_message(6, 9, "Anyone?"),
+        Err(ApplicationError::Server(ErrorResponse::UnknownClient(9)))
+    );
+    assert_eq!(bob.recv_chat_message(Duration::from_millis(100)), None);
+
+    //Lets the last Acks reach the server
+    thread::sleep(Duration::from_millis(200));
+    stop.stop();
+    let server = server.join().expect("The server panicked");
+    assert_eq!(server.handler().clients().len(), 2);
+    assert_eq!(server.stats().messages_sent, 1);
+    assert_eq!(server.delivery().stats().delivered, 7);
+    network.stop().expect("Error stopping the network");
+}
@@ -0,0 +1,124 @@
+use dronegowski::{
+    DroneError, DroneStatsHandle, DronegowskiBuilder, GilbertElliottDrop, PerHopDrop,
+    ScheduledDrop,
+};
+use std::collections::HashMap;
+use std::time::Duration;
+use wg_2024::controller::{DroneCommand, DroneEvent};
+use wg_2024::drone::Drone;
+use wg_2024::network::SourceRoutingHeader;
+use wg_2024::packet::{Fragment, Packet, PacketType};
+const TIMER: Duration = Duration::from_secs(5);
+
+#[test]
+fn builder_rejects_invalid_settings() {
+    let (sim_controller_send, _) = crossbeam_channel::unbounded::<DroneEvent>();
+    let (_controller_send, controller_receive) = crossbeam_channel::unbounded::<DroneCommand>();
+    let (neighbor_send, packet_receive) = crossbeam_channel::unbounded::<Packet>();
+
+    let builder = |senders: HashMap<u8, _>| {
+        DronegowskiBuilder::new(
+            1,
+            sim_controller_send.clone(),
+            controller_receive.clone(),
+            packet_receive.clone(),
+            senders,
+        )
+    };
+
+    assert_eq!(
+        builder(HashMap::new()).pdr(1.7).build().unwrap_err(),
+        DroneError::PdrOutOfBounds(1.7)
+    );
+    assert_eq!(
+        builder(HashMap::from([(1, neighbor_send)]))
+            .build()
+            .unwrap_err(),
+        DroneError::SelfNeighbour(1)
+    );
+    assert_eq!(
+        builder(HashMap::new())
+            .flood_cache_capacity(0)
+            .build()
+            .unwrap_err(),
+        DroneError::InvalidFloodCacheCapacity(0)
+    );
+    assert_eq!(
+        builder(HashMap::new())
+            .drop_model(GilbertElliottDrop::new(1.5, -0.2, 2.0, 0.0))
+            .build()
+            .unwrap_err(),
+        DroneError::InvalidDropProbability(1.5)
+    );
+    assert_eq!(
+        builder(HashMap::new())
+            .drop_model(PerHopDrop::new(0.1).with_hop(2, -0.5))
+            .build()
+            .unwrap_err(),
+        DroneError::InvalidDropProbability(-0.5)
+    );
+
+    let drone = builder(HashMap::new())
+        .pdr(0.3)
+        .seed(5)
+        .build()
+        .expect("Valid drone");
+    assert_eq!(drone.get_seed(), 5);
+    assert_eq!(drone.get_pdr(), 0.3);
+}
+
+#[test]
+fn builder_with_drop_model_and_stats() {
+    //Create channel
+    let (sim_controller_send, _) = crossbeam_channel::unbounded::<DroneEvent>();
+    let (_controller_send, controller_receive) = crossbeam_channel::unbounded::<DroneCommand>();
+    let (packet_send, packet_receive) = crossbeam_channel::unbounded::<Packet>();
+
+    //Create channel for the neighbor
+    let (client_send, client_receive) = crossbeam_channel::unbounded::<Packet>();
+    let mut senders = HashMap::new();
+    senders.insert(0, client_send);
+
+    let stats = DroneStatsHandle::new();
+    let mut my_drone = DronegowskiBuilder::new(
+        1,
+        sim_controller_send,
+        controller_receive,
+        packet_receive,
+        senders,
+    )
+    .drop_model(ScheduledDrop::new(vec![true]))
+    .stats_handle(stats.clone())
+    .log_target("drone-1")
+    .build()
+    .expect("Valid drone");
+
+    std::thread::spawn(move || {
+        my_drone.run();
+    });
+
+    packet_send
+        .send(Packet {
+            pack_type: PacketType::MsgFragment(Fragment {
+                fragment_index: 10,
+                total_n_fragments: 15,
+                length: 5,
+                data: [5; 128],
+            }),
+            routing_header: SourceRoutingHeader {
+                hop_index: 1,
+                hops: vec![0, 1, 2],
+            },
+            session_id: 1,
+        })
+        .expect("Error sending the fragment...");
+
+    match client_receive.recv_timeout(TIMER) {
+        Ok(Packet {
+            pack_type: PacketType::Nack(_),
+            ..
+        }) => {}
+        other => panic!("Expected a Nack, got {other:?}"),
+    }
+    assert_eq!(stats.snapshot().fragments_dropped, 1);
+}
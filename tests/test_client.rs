@@ -0,0 +1,168 @@
+use dronegowski::{
+    flood_response, parse_config, Client, ClientError, NetworkGraph, NetworkHandle,
+    NetworkInitializer,
+};
+use std::collections::HashMap;
+use std::thread::{self, JoinHandle};
+use std::time::Duration;
+use wg_2024::network::{NodeId, SourceRoutingHeader};
+use wg_2024::packet::{Ack, Fragment, Nack, NackType, NodeType, Packet, PacketType};
+
+const QUIET: Duration = Duration::from_millis(300);
+
+fn start_network() -> NetworkHandle {
+    let mut config = parse_config("tests/common/config.toml").expect("Error loading the config");
+    for drone in &mut config.drone {
+        drone.pdr = 0.0;
+    }
+    NetworkInitializer::new(config)
+        .start()
+        .expect("Error starting the network")
+}
+
+// Stands in for server `server_id`: answers the floods and collects the fragments it receives
+fn run_server(network: &NetworkHandle, server_id: NodeId) -> JoinHandle<Vec<Packet>> {
+    let packet_recv = network.packet_recv()[&server_id].clone();
+    let packet_send = network.packet_send().clone();
+    thread::spawn(move || {
+        let mut fragments = Vec::new();
+        while let Ok(packet) = packet_recv.recv_timeout(Duration::from_secs(1)) {
+            match packet.pack_type {
+                PacketType::FloodRequest(ref flood_request) => {
+                    let response = flood_response(
+                        flood_request,
+                        packet.session_id,
+                        server_id,
+                        NodeType::Server,
+                    );
+                    let next_hop = response.routing_header.hops[1];
+                    packet_send[&next_hop]
+                        .send(response)
+                        .expect("Error answering the flood");
+                }
+                PacketType::MsgFragment(_) => fragments.push(packet),
+                _ => {}
+            }
+        }
+        fragments
+    })
+}
+
+// Lets client `client_id` answer the floods of the others while the test runs
+fn run_client(network: &NetworkHandle, client_id: NodeId) -> JoinHandle<Client> {
+    let mut client = Client::from_network(network, client_id).expect("Error creating the client");
+    thread::spawn(move || {
+        while client.recv_timeout(Duration::from_secs(1)).is_some() {}
+        client
+    })
+}
+
+#[test]
+fn client_discovers_topology() {
+    let network = start_network();
+    let server = run_server(&network, 6);
+    let other_client = run_client(&network, 5);
+    let mut client = Client::from_network(&network, 4).expect("Error creating the client");
+    assert_eq!(client.neighbours(), vec![2, 3]);
+    assert!(client.servers().is_empty());
+
+    let responses = client.discover(QUIET).expect("Error flooding the network");
+    assert!(responses > 0);
+    assert_eq!(
+        client.topology(),
+        &NetworkGraph::from_config(network.config())
+    );
+    assert_eq!(client.servers(), vec![6]);
+    assert_eq!(client.topology().node_type(5), Some(NodeType::Client));
+
+    server.join().expect("The server panicked");
+    other_client.join().expect("The other client panicked");
+    network.stop().expect("Error stopping the network");
+}
+
+#[test]
+fn client_sends_on_learned_route() {
+    let network = start_network();
+    let server = run_server(&network, 6);
+    let mut client = Client::from_network(&network, 4).expect("Error creating the client");
+    client.discover(QUIET).expect("Error flooding the network");
+
+    let route = client.route_to(6).expect("The server was not discovered");
+    assert_eq!(route.hop_index, 1);
+    assert_eq!(route.hops, vec![4, 2, 6]);
+    let fragment = PacketType::MsgFragment(Fragment {
+        fragment_index: 0,
+        total_n_fragments: 1,
+        length: 5,
+        data: [7; 128],
+    });
+    client
+        .send_to(6, 11, fragment.clone())
+        .expect("Error sending the fragment");
+
+    let fragments = server.join().expect("The server panicked");
+    assert_eq!(fragments.len(), 1);
+    assert_eq!(fragments[0].session_id, 11);
+    assert_eq!(fragments[0].routing_header.hops, vec![4, 2, 6]);
+    assert_eq!(fragments[0].routing_header.hop_index, 2);
+    assert_eq!(
+        client.send_to(42, 12, fragment),
+        Err(ClientError::NoRoute(42))
+    );
+    network.stop().expect("Error stopping the network");
+}
+
+#[test]
+fn floods_have_unique_ids() {
+    let network = start_network();
+    assert!(matches!(
+        Client::from_network(&network, 1),
+        Err(ClientError::NotAClient(1))
+    ));
+
+    let mut first = Client::from_network(&network, 4).expect("Error creating the client");
+    let mut second = Client::from_network(&network, 5).expect("Error creating the client");
+    let ids = [
+        first.flood().expect("Error flooding the network"),
+        first.flood().expect("Error flooding the network"),
+        second.flood().expect("Error flooding the network"),
+    ];
+    assert_ne!(ids[0], ids[1]);
+    assert_ne!(ids[0], ids[2]);
+    assert_ne!(ids[1], ids[2]);
+
+    //Client 5 answers the floods of client 4, which learns the link 1-5 from it
+    while first.recv_timeout(QUIET).is_some() {}
+    while second.recv_timeout(QUIET).is_some() {}
+    while first.recv_timeout(QUIET).is_some() {}
+    assert!(first.topology().has_edge(1, 5));
+    network.stop().expect("Error stopping the network");
+}
+
+#[test]
+fn late_acks_are_not_returned() {
+    let (packet_send, packet_recv) = crossbeam_channel::unbounded::<Packet>();
+    let mut client = Client::new(4, packet_recv, HashMap::new());
+
+    //Session 9 was never sent by the client, or already finished
+    for pack_type in [
+        PacketType::Ack(Ack { fragment_index: 0 }),
+        PacketType::Nack(Nack {
+            fragment_index: 1,
+            nack_type: NackType::Dropped,
+        }),
+    ] {
+        packet_send
+            .send(Packet {
+                pack_type,
+                routing_header: SourceRoutingHeader {
+                    hop_index: 2,
+                    hops: vec![6, 2, 4],
+                },
+                session_id: 9,
+            })
+            .expect("Error sending the packet...");
+    }
+    assert!(client.recv_timeout(QUIET).is_none());
+    assert!(packet_send.is_empty());
+}
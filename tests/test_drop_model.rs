@@ -0,0 +1,140 @@
+use dronegowski::{
+    DropModel, DroneError, Dronegowski, GilbertElliottDrop, PerHopDrop, ScheduledDrop,
+    UniformDrop,
+};
+use std::collections::HashMap;
+use std::time::Duration;
+use wg_2024::controller::{DroneCommand, DroneEvent};
+use wg_2024::drone::Drone;
+use wg_2024::network::SourceRoutingHeader;
+use wg_2024::packet::{Fragment, Packet, PacketType};
+const TIMER: Duration = Duration::from_secs(5);
+
+fn fragment_packet(hops: Vec<u8>) -> Packet {
+    Packet {
+        pack_type: PacketType::MsgFragment(Fragment {
+            fragment_index: 10,
+            total_n_fragments: 15,
+            length: 5,
+            data: [5; 128],
+        }),
+        routing_header: SourceRoutingHeader { hop_index: 1, hops },
+        session_id: 1,
+    }
+}
+
+fn is_nack(packet: &Packet) -> bool {
+    matches!(packet.pack_type, PacketType::Nack(_))
+}
+
+#[test]
+fn scheduled_drop_is_deterministic() {
+    let mut model = ScheduledDrop::new(vec![false, true, true]);
+    let packet = fragment_packet(vec![0, 1, 2]);
+    let mut rng = rand::rng();
+
+    let decisions: Vec<bool> = (0..6)
+        .map(|_| model.should_drop(&packet, Some(2), &mut rng))
+        .collect();
+    assert_eq!(decisions, vec![false, true, true, false, true, true]);
+    assert!((model.pdr() - 2.0 / 3.0).abs() < f32::EPSILON);
+
+    //The new PDR is ignored and reported to the drone, which logs it under its own target
+    assert!(!model.set_pdr(1.0));
+    assert!((model.pdr() - 2.0 / 3.0).abs() < f32::EPSILON);
+}
+
+#[test]
+fn gilbert_elliott_enters_bad_state() {
+    //Always moves to the bad state, never comes back, loses everything there
+    let mut model = GilbertElliottDrop::new(1.0, 0.0, 0.0, 1.0);
+    let packet = fragment_packet(vec![0, 1, 2]);
+    let mut rng = rand::rng();
+
+    assert!(model.should_drop(&packet, Some(2), &mut rng));
+    assert!(model.is_bad_state());
+    assert!(model.should_drop(&packet, Some(2), &mut rng));
+    assert_eq!(model.pdr(), 1.0);
+}
+
+#[test]
+fn per_hop_drop_depends_on_next_hop() {
+    let mut model = PerHopDrop::new(0.0).with_hop(2, 1.0);
+    let mut rng = rand::rng();
+
+    assert!(model.should_drop(&fragment_packet(vec![0, 1, 2]), Some(2), &mut rng));
+    assert!(!model.should_drop(&fragment_packet(vec![0, 1, 3]), Some(3), &mut rng));
+
+    model.set_pdr(1.0);
+    assert!(model.should_drop(&fragment_packet(vec![0, 1, 3]), Some(3), &mut rng));
+}
+
+#[test]
+fn uniform_drop_follows_set_pdr() {
+    let mut model = UniformDrop::new(0.0);
+    let packet = fragment_packet(vec![0, 1, 2]);
+    let mut rng = rand::rng();
+
+    assert!(!model.should_drop(&packet, Some(2), &mut rng));
+    assert!(model.set_pdr(1.0));
+    assert!(model.should_drop(&packet, Some(2), &mut rng));
+    assert_eq!(model.pdr(), 1.0);
+}
+
+#[test]
+fn switch_drop_model_while_running() {
+    //Create channel
+    let (sim_controller_send, _) = crossbeam_channel::unbounded::<DroneEvent>();
+    let (_send_controller, controller_receive) = crossbeam_channel::unbounded::<DroneCommand>();
+    let (packet_send, packet_receive) = crossbeam_channel::unbounded::<Packet>();
+
+    //Create channel for the neighbors
+    let (client_send, client_receive) = crossbeam_channel::unbounded::<Packet>();
+    let (neighbor_send, neighbor_receive) = crossbeam_channel::unbounded::<Packet>();
+    let mut senders = HashMap::new();
+    senders.insert(0, client_send);
+    senders.insert(2, neighbor_send);
+
+    let mut my_drone = Dronegowski::new(
+        1,
+        sim_controller_send,
+        controller_receive,
+        packet_receive,
+        senders,
+        0.0,
+    );
+    let drop_model = my_drone.drop_model_handle();
+
+    std::thread::spawn(move || {
+        my_drone.run();
+    });
+
+    packet_send
+        .send(fragment_packet(vec![0, 1, 2]))
+        .expect("Error sending the fragment...");
+    assert!(!is_nack(
+        &neighbor_receive
+            .recv_timeout(TIMER)
+            .expect("Timeout: No packet received.")
+    ));
+
+    //A model that makes no sense is refused and the current one is kept
+    assert!(matches!(
+        drop_model.set_model(GilbertElliottDrop::new(0.1, 0.1, 0.0, f32::NAN)),
+        Err(DroneError::InvalidDropProbability(p)) if p.is_nan()
+    ));
+    assert_eq!(drop_model.pdr(), 0.0);
+    //From now on the fragments are dropped
+    drop_model
+        .set_model(ScheduledDrop::new(vec![true]))
+        .expect("Error switching the drop model");
+    packet_send
+        .send(fragment_packet(vec![0, 1, 2]))
+        .expect("Error sending the fragment...");
+    assert!(is_nack(
+        &client_receive
+            .recv_timeout(TIMER)
+            .expect("Timeout: No packet received.")
+    ));
+    assert_eq!(drop_model.pdr(), 1.0);
+}
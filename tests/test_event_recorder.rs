@@ -0,0 +1,181 @@
+use dronegowski::{
+    read_event_log, rotated_path, EventKind, EventRecorder, NetworkInitializer,
+    SimulationController,
+};
+use std::path::PathBuf;
+use std::time::Duration;
+use wg_2024::controller::DroneEvent;
+use wg_2024::network::SourceRoutingHeader;
+use wg_2024::packet::{Ack, FloodRequest, Nack, NackType, NodeType, Packet, PacketType};
+const TIMER: Duration = Duration::from_secs(5);
+
+fn log_path(name: &str) -> PathBuf {
+    let dir = std::env::temp_dir().join(format!("dronegowski-{}", std::process::id()));
+    std::fs::create_dir_all(&dir).expect("Error creating the log directory");
+    dir.join(name)
+}
+
+fn ack_packet(hop_index: usize) -> Packet {
+    Packet {
+        pack_type: PacketType::Ack(Ack { fragment_index: 0 }),
+        routing_header: SourceRoutingHeader {
+            hop_index,
+            hops: vec![1, 2, 3],
+        },
+        session_id: 1,
+    }
+}
+
+#[test]
+fn recorder_writes_one_line_per_event() {
+    let path = log_path("events.jsonl");
+    let flood_request = Packet {
+        pack_type: PacketType::FloodRequest(FloodRequest {
+            flood_id: 1,
+            initiator_id: 0,
+            path_trace: vec![(0, NodeType::Client), (7, NodeType::Drone)],
+        }),
+        routing_header: SourceRoutingHeader {
+            hop_index: 0,
+            hops: vec![],
+        },
+        session_id: 1,
+    };
+    let nack = Packet {
+        pack_type: PacketType::Nack(Nack {
+            fragment_index: 0,
+            nack_type: NackType::Dropped,
+        }),
+        ..ack_packet(1)
+    };
+    let events = vec![
+        DroneEvent::PacketSent(ack_packet(2)),
+        DroneEvent::PacketSent(flood_request),
+        DroneEvent::PacketDropped(nack.clone()),
+        DroneEvent::ControllerShortcut(nack),
+    ];
+
+    {
+        let mut recorder = EventRecorder::create(&path).expect("Error creating the log");
+        for event in &events {
+            recorder.record(event).expect("Error recording the event");
+        }
+        assert_eq!(recorder.records(), 4);
+        //Dropping the recorder flushes the buffered lines
+    }
+
+    let records = read_event_log(&path).expect("Error reading the log");
+    assert_eq!(records.len(), 4);
+    assert_eq!(
+        records.iter().map(|record| record.kind).collect::<Vec<_>>(),
+        vec![
+            EventKind::PacketSent,
+            EventKind::PacketSent,
+            EventKind::PacketDropped,
+            EventKind::ControllerShortcut,
+        ]
+    );
+    assert_eq!(
+        records
+            .iter()
+            .map(|record| record.drone)
+            .collect::<Vec<_>>(),
+        vec![Some(2), Some(7), Some(2), Some(2)]
+    );
+    assert!(records
+        .windows(2)
+        .all(|pair| pair[0].timestamp_us <= pair[1].timestamp_us));
+    for (record, event) in records.iter().zip(&events) {
+        assert_eq!(
+            format!("{:?}", record.to_event()),
+            format!("{:?}", Some(event))
+        );
+    }
+}
+
+#[test]
+fn recorder_rotates_files() {
+    let path = log_path("rotated.jsonl");
+    {
+        //Every line is bigger than the limit, so every event gets its own file
+        let mut recorder = EventRecorder::create(&path)
+            .expect("Error creating the log")
+            .with_rotation(1, 2);
+        for hop_index in 0..4 {
+            recorder
+                .record(&DroneEvent::PacketSent(ack_packet(hop_index)))
+                .expect("Error recording the event");
+        }
+    }
+
+    let hop_index = |path: PathBuf| {
+        let records = read_event_log(path).expect("Error reading the log");
+        assert_eq!(records.len(), 1);
+        records[0]
+            .packet
+            .as_ref()
+            .expect("Drone events have a packet")
+            .routing_header
+            .hop_index
+    };
+    assert_eq!(hop_index(path.clone()), 3);
+    assert_eq!(hop_index(rotated_path(&path, 1)), 2);
+    assert_eq!(hop_index(rotated_path(&path, 2)), 1);
+    assert!(!rotated_path(&path, 3).exists());
+}
+
+#[test]
+fn recorder_taps_event_stream() {
+    let path = log_path("tap.jsonl");
+    let (event_send, event_recv) = crossbeam_channel::unbounded::<DroneEvent>();
+    let recorder = EventRecorder::create(&path).expect("Error creating the log");
+    let (tapped_recv, handle) = recorder.tap(event_recv);
+
+    event_send
+        .send(DroneEvent::PacketSent(ack_packet(1)))
+        .expect("Error sending the event");
+    event_send
+        .send(DroneEvent::PacketSent(ack_packet(2)))
+        .expect("Error sending the event");
+    drop(event_send);
+
+    assert_eq!(tapped_recv.iter().count(), 2);
+    let recorder = handle.join().expect("The recorder panicked");
+    assert_eq!(recorder.records(), 2);
+    assert_eq!(
+        read_event_log(&path).expect("Error reading the log").len(),
+        2
+    );
+}
+
+#[test]
+fn controller_records_handled_events() {
+    let path = log_path("controller.jsonl");
+    let network = NetworkInitializer::from_file("tests/common/config.toml")
+        .expect("Error loading the config")
+        .start()
+        .expect("Error starting the network");
+    let mut controller = SimulationController::new(network)
+        .with_recorder(EventRecorder::create(&path).expect("Error creating the log"));
+
+    controller.network().packet_send()[&3]
+        .send(Packet {
+            pack_type: PacketType::Ack(Ack { fragment_index: 0 }),
+            routing_header: SourceRoutingHeader {
+                hop_index: 1,
+                hops: vec![4, 3, 6],
+            },
+            session_id: 1,
+        })
+        .expect("Error sending the packet...");
+    controller
+        .recv_event_timeout(TIMER)
+        .expect("Timeout: No event received.");
+    assert_eq!(controller.recorder().map(EventRecorder::records), Some(1));
+    controller.stop().expect("Error stopping the network");
+
+    let records = read_event_log(&path).expect("Error reading the log");
+    assert_eq!(records.len(), 1);
+    assert_eq!(records[0].kind, EventKind::PacketSent);
+    assert_eq!(records[0].drone, Some(3));
+}
@@ -0,0 +1,148 @@
+use dronegowski::{DroneError, DronegowskiBuilder, FloodCache};
+use std::collections::HashMap;
+use std::time::Duration;
+use wg_2024::controller::{DroneCommand, DroneEvent};
+use wg_2024::drone::Drone;
+use wg_2024::network::SourceRoutingHeader;
+use wg_2024::packet::{FloodRequest, NodeType, Packet, PacketType};
+const TIMER: Duration = Duration::from_secs(5);
+
+fn flood_request(flood_id: u64) -> Packet {
+    Packet {
+        pack_type: PacketType::FloodRequest(FloodRequest {
+            flood_id,
+            initiator_id: 0,
+            path_trace: vec![(0, NodeType::Client)],
+        }),
+        routing_header: SourceRoutingHeader {
+            hop_index: 0,
+            hops: vec![],
+        },
+        session_id: 1,
+    }
+}
+
+#[test]
+fn cache_deduplicates_recent_floods() {
+    let mut cache = FloodCache::new(2).expect("Error creating the cache");
+    assert!(cache.insert(1, 0));
+    assert!(!cache.insert(1, 0));
+    assert!(cache.insert(1, 5)); //Same flood_id, other initiator
+    assert_eq!(cache.len(), 2);
+}
+
+#[test]
+fn cache_evicts_least_recently_seen() {
+    let mut cache = FloodCache::new(2).expect("Error creating the cache");
+    cache.insert(1, 0);
+    cache.insert(2, 0);
+    cache.insert(1, 0); //Flood 1 is now the most recent
+    cache.insert(3, 0);
+
+    assert!(cache.contains(1, 0));
+    assert!(!cache.contains(2, 0));
+    assert!(cache.contains(3, 0));
+    assert_eq!(cache.len(), cache.capacity());
+}
+
+#[test]
+fn cache_stays_bounded() {
+    let mut cache = FloodCache::new(16).expect("Error creating the cache");
+    for flood_id in 0..10_000 {
+        cache.insert(flood_id, (flood_id % 7) as u8);
+        cache.insert(flood_id, (flood_id % 7) as u8);
+    }
+    assert_eq!(cache.len(), 16);
+    assert!(cache.contains(9_999, (9_999 % 7) as u8));
+}
+
+#[test]
+fn zero_capacity_is_refused() {
+    assert_eq!(
+        FloodCache::new(0).unwrap_err(),
+        DroneError::InvalidFloodCacheCapacity(0)
+    );
+
+    let (sim_controller_send, _) = crossbeam_channel::unbounded::<DroneEvent>();
+    let (_send_controller, controller_receive) = crossbeam_channel::unbounded::<DroneCommand>();
+    let (_packet_send, packet_receive) = crossbeam_channel::unbounded::<Packet>();
+    let result = DronegowskiBuilder::new(
+        1,
+        sim_controller_send,
+        controller_receive,
+        packet_receive,
+        HashMap::new(),
+    )
+    .flood_cache_capacity(0)
+    .build();
+    assert_eq!(result.err(), Some(DroneError::InvalidFloodCacheCapacity(0)));
+}
+
+#[test]
+fn drone_forwards_again_an_evicted_flood() {
+    //Create channel
+    let (sim_controller_send, _) = crossbeam_channel::unbounded::<DroneEvent>();
+    let (_send_controller, controller_receive) = crossbeam_channel::unbounded::<DroneCommand>();
+    let (packet_send, packet_receive) = crossbeam_channel::unbounded::<Packet>();
+
+    //Create channel for the neighbors
+    let (client_send, client_receive) = crossbeam_channel::unbounded::<Packet>();
+    let (neighbor_send, neighbor_receive) = crossbeam_channel::unbounded::<Packet>();
+    let mut senders = HashMap::new();
+    senders.insert(0, client_send);
+    senders.insert(2, neighbor_send);
+
+    let mut my_drone = DronegowskiBuilder::new(
+        1,
+        sim_controller_send,
+        controller_receive,
+        packet_receive,
+        senders,
+    )
+    .pdr(0.0)
+    .flood_cache_capacity(1)
+    .build()
+    .expect("Error building the drone");
+
+    std::thread::spawn(move || {
+        my_drone.run();
+    });
+
+    //Flood 1 is forwarded, then answered while it is still in the cache
+    packet_send
+        .send(flood_request(1))
+        .expect("Error sending the flood request...");
+    neighbor_receive
+        .recv_timeout(TIMER)
+        .expect("Timeout: No Flood Request received.");
+    packet_send
+        .send(flood_request(1))
+        .expect("Error sending the flood request...");
+    match client_receive.recv_timeout(TIMER) {
+        Ok(Packet {
+            pack_type: PacketType::FloodResponse(_),
+            ..
+        }) => {}
+        other => panic!("Expected a Flood Response, got {other:?}"),
+    }
+
+    //Flood 2 evicts flood 1, which is then forwarded again
+    packet_send
+        .send(flood_request(2))
+        .expect("Error sending the flood request...");
+    neighbor_receive
+        .recv_timeout(TIMER)
+        .expect("Timeout: No Flood Request received.");
+    packet_send
+        .send(flood_request(1))
+        .expect("Error sending the flood request...");
+    match neighbor_receive.recv_timeout(TIMER) {
+        Ok(Packet {
+            pack_type: PacketType::FloodRequest(request),
+            ..
+        }) => {
+            assert_eq!(request.flood_id, 1)
+        }
+        other => panic!("Expected a Flood Request, got {other:?}"),
+    }
+}
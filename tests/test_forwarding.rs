@@ -387,4 +387,73 @@ fn forward_msg_fragment_dropped() {
         }
         Err(_) => println!("Timeout: No packet received."),
     }
-}
\ No newline at end of file
+}
+#[test]
+fn forward_msg_fragment_neighbour_disconnected() {
+    //Create channel
+    let (sim_controller_send, _) = crossbeam_channel::unbounded::<DroneEvent>();
+    let (_send_controller, controller_receive) = crossbeam_channel::unbounded::<DroneCommand>();
+    let (packet_send_my_drone, packet_receive_my_drone) = crossbeam_channel::unbounded::<Packet>();
+
+    //Create channel for the neighbors, the receiver of Drone 2 is dropped
+    let (packet_send_test_drone, packet_receive_test_drone) = crossbeam_channel::unbounded::<Packet>();
+    let (neighbor_send, _) = crossbeam_channel::unbounded::<Packet>();
+
+    //Create map for neighbors
+    let mut senders_my_drone = HashMap::new();
+    senders_my_drone.insert(0, packet_send_test_drone.clone());
+    senders_my_drone.insert(2, neighbor_send);
+
+    let mut my_drone = Dronegowski::new(
+        1,
+        sim_controller_send,
+        controller_receive,
+        packet_receive_my_drone.clone(),
+        senders_my_drone,
+        0.0
+    );
+
+    let packet = Packet {
+        pack_type: PacketType::MsgFragment(Fragment{
+            fragment_index: 10,
+            total_n_fragments: 15,
+            length: 5,
+            data:[5; 128],
+        }),
+        routing_header: SourceRoutingHeader {
+            hop_index: 1,
+            hops: vec![0, 1, 2], //Path: Drone 1 -> Drone 2 (Drone 2 disconnected)
+        },
+        session_id: 1,
+    };
+
+    std::thread::spawn(move || {
+        my_drone.run();
+    });
+
+    packet_send_my_drone.send(packet.clone()).expect("Error sending the fragment...");
+
+    let packet_test = Packet {
+        pack_type: PacketType::Nack(Nack{
+            fragment_index: 10,
+            nack_type: NackType::ErrorInRouting(2),
+        }),
+        routing_header: SourceRoutingHeader {
+            hop_index: 1,
+            hops: vec![1, 0],
+        },
+        session_id: 1,
+    };
+
+    let received_packet = packet_receive_test_drone
+        .recv_timeout(TIMER)
+        .expect("Timeout: No packet received.");
+    assert_eq!(packet_test, received_packet);
+
+    //The drone is still running after the disconnection and keeps answering
+    packet_send_my_drone.send(packet).expect("Error sending the fragment...");
+    let received_packet = packet_receive_test_drone
+        .recv_timeout(TIMER)
+        .expect("Timeout: No packet received.");
+    assert_eq!(packet_test, received_packet);
+}
@@ -0,0 +1,176 @@
+use dronegowski::{
+    fragment, message_packets, ExpiredSession, FragmentStatus, Reassembler, ReassemblyError,
+};
+use rand::rngs::StdRng;
+use rand::seq::SliceRandom;
+use rand::SeedableRng;
+use std::thread;
+use std::time::Duration;
+use wg_2024::network::SourceRoutingHeader;
+use wg_2024::packet::{PacketType, FRAGMENT_DSIZE};
+
+fn payload(len: usize) -> Vec<u8> {
+    (0..len).map(|byte| (byte % 251) as u8).collect()
+}
+
+#[test]
+fn payloads_are_split_in_numbered_fragments() {
+    let fragments = fragment(&payload(300));
+    assert_eq!(fragments.len(), 3);
+    for (index, fragment) in fragments.iter().enumerate() {
+        assert_eq!(fragment.fragment_index, index as u64);
+        assert_eq!(fragment.total_n_fragments, 3);
+    }
+    assert_eq!(fragments[0].length as usize, FRAGMENT_DSIZE);
+    assert_eq!(fragments[2].length, 44);
+    assert_eq!(fragments[2].data[43], payload(300)[299]);
+    assert_eq!(fragments[2].data[44], 0);
+
+    assert_eq!(fragment(&payload(256)).len(), 2);
+    let empty = fragment(&[]);
+    assert_eq!(empty.len(), 1);
+    assert_eq!((empty[0].length, empty[0].total_n_fragments), (0, 1));
+
+    let header = SourceRoutingHeader {
+        hop_index: 1,
+        hops: vec![4, 2, 6],
+    };
+    let packets = message_packets(9, &header, &payload(200));
+    assert_eq!(packets.len(), 2);
+    assert!(packets
+        .iter()
+        .all(|packet| packet.session_id == 9 && packet.routing_header == header));
+    assert!(matches!(packets[1].pack_type, PacketType::MsgFragment(_)));
+}
+
+#[test]
+fn messages_are_reassembled_in_any_order() {
+    let mut rng = StdRng::seed_from_u64(7);
+    let mut reassembler = Reassembler::new();
+    for (session_id, len) in [0, 1, 127, 128, 129, 1000, 5000].into_iter().enumerate() {
+        let expected = payload(len);
+        let mut fragments = fragment(&expected);
+        fragments.shuffle(&mut rng);
+        let last = fragments.len() - 1;
+        for (position, fragment) in fragments.iter().enumerate() {
+            let status = reassembler
+                .push(4, session_id as u64, fragment)
+                .expect("Error pushing the fragment");
+            if position == last {
+                assert_eq!(status, FragmentStatus::Complete(expected.clone()));
+            } else {
+                assert_eq!(
+                    status,
+                    FragmentStatus::Incomplete {
+                        received: position as u64 + 1,
+                        total: fragments.len() as u64
+                    }
+                );
+            }
+        }
+    }
+    assert_eq!(reassembler.sessions_in_progress(), 0);
+}
+
+#[test]
+fn duplicates_and_sources_are_told_apart() {
+    let mut reassembler = Reassembler::new();
+    let first = fragment(&payload(400));
+    let second = fragment(&[1, 2, 3]);
+
+    assert!(reassembler.push(4, 1, &first[0]).is_ok());
+    assert_eq!(
+        reassembler.push(4, 1, &first[0]),
+        Ok(FragmentStatus::Duplicate)
+    );
+    //Same session id from another source is another message
+    assert_eq!(
+        reassembler.push(5, 1, &second[0]),
+        Ok(FragmentStatus::Complete(vec![1, 2, 3]))
+    );
+    let mut conflicting = first[1].clone();
+    conflicting.data[0] ^= 1;
+    assert!(reassembler.push(4, 1, &first[1]).is_ok());
+    assert_eq!(
+        reassembler.push(4, 1, &conflicting),
+        Err(ReassemblyError::ConflictingDuplicate(1, 1))
+    );
+    assert_eq!(reassembler.missing(4, 1), Some(vec![2, 3]));
+
+    for fragment in &first[2..] {
+        assert!(reassembler.push(4, 1, fragment).is_ok());
+    }
+    //Retransmissions after the message is complete are still duplicates
+    assert_eq!(
+        reassembler.push(4, 1, &first[3]),
+        Ok(FragmentStatus::Duplicate)
+    );
+    assert_eq!(reassembler.missing(4, 1), None);
+}
+
+#[test]
+fn invalid_fragments_are_refused() {
+    let mut reassembler = Reassembler::new().with_max_session_bytes(4 * FRAGMENT_DSIZE);
+    let fragments = fragment(&payload(300));
+
+    let mut past_end = fragments[0].clone();
+    past_end.fragment_index = 3;
+    assert!(matches!(
+        reassembler.push(4, 1, &past_end),
+        Err(ReassemblyError::InvalidFragment(1, 3, _))
+    ));
+    let mut short = fragments[0].clone();
+    short.length = 10;
+    assert!(matches!(
+        reassembler.push(4, 1, &short),
+        Err(ReassemblyError::InvalidFragment(1, 0, _))
+    ));
+    let mut too_long = fragments[2].clone();
+    too_long.length = 200;
+    assert!(matches!(
+        reassembler.push(4, 1, &too_long),
+        Err(ReassemblyError::InvalidFragment(1, 2, _))
+    ));
+
+    assert!(reassembler.push(4, 1, &fragments[0]).is_ok());
+    let mut other_total = fragments[1].clone();
+    other_total.total_n_fragments = 4;
+    assert_eq!(
+        reassembler.push(4, 1, &other_total),
+        Err(ReassemblyError::InconsistentTotal(1, 1, 3, 4))
+    );
+
+    let large = fragment(&payload(5 * FRAGMENT_DSIZE));
+    assert_eq!(
+        reassembler.push(4, 2, &large[0]),
+        Err(ReassemblyError::SessionTooLarge(
+            2,
+            5 * FRAGMENT_DSIZE,
+            4 * FRAGMENT_DSIZE
+        ))
+    );
+    assert_eq!(reassembler.sessions_in_progress(), 1);
+}
+
+#[test]
+fn stale_sessions_expire() {
+    let mut reassembler = Reassembler::new().with_timeout(Duration::from_millis(100));
+    let fragments = fragment(&payload(500));
+    assert!(reassembler.push(4, 1, &fragments[1]).is_ok());
+    assert!(reassembler.push(5, 2, &fragments[0]).is_ok());
+    assert!(reassembler.expire().is_empty());
+
+    thread::sleep(Duration::from_millis(60));
+    assert!(reassembler.push(5, 2, &fragments[2]).is_ok());
+    thread::sleep(Duration::from_millis(60));
+    assert_eq!(
+        reassembler.expire(),
+        vec![ExpiredSession {
+            source: 4,
+            session_id: 1,
+            missing: vec![0, 2, 3]
+        }]
+    );
+    assert_eq!(reassembler.sessions_in_progress(), 1);
+    assert_eq!(reassembler.missing(5, 2), Some(vec![1, 3]));
+}
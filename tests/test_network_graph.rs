@@ -0,0 +1,161 @@
+use dronegowski::{parse_config, GraphError, NetworkGraph};
+use wg_2024::packet::NodeType;
+
+fn config_graph() -> NetworkGraph {
+    let config = parse_config("tests/common/config.toml").expect("Error loading the config");
+    NetworkGraph::from_config(&config)
+}
+
+#[test]
+fn graph_from_config() {
+    let graph = config_graph();
+    assert_eq!(graph.len(), 6);
+    assert_eq!(graph.nodes_of_type(NodeType::Drone), vec![1, 2, 3]);
+    assert_eq!(graph.nodes_of_type(NodeType::Client), vec![4, 5]);
+    assert_eq!(graph.nodes_of_type(NodeType::Server), vec![6]);
+    assert_eq!(graph.neighbours(3), Some(vec![1, 2, 4, 6]));
+    assert_eq!(
+        graph.edges(),
+        vec![
+            (1, 2),
+            (1, 3),
+            (1, 5),
+            (2, 3),
+            (2, 4),
+            (2, 6),
+            (3, 4),
+            (3, 6)
+        ]
+    );
+    assert!(graph.cut_off_nodes().is_empty());
+}
+
+#[test]
+fn graph_edges_can_change() {
+    let mut graph = NetworkGraph::new();
+    assert!(graph.add_node(1, NodeType::Drone));
+    assert!(graph.add_node(2, NodeType::Client));
+    assert!(!graph.add_node(1, NodeType::Server));
+
+    assert_eq!(graph.add_edge(1, 1), Err(GraphError::SelfLoop(1)));
+    assert_eq!(graph.add_edge(1, 9), Err(GraphError::UnknownNode(9)));
+    assert_eq!(graph.add_edge(1, 2), Ok(true));
+    assert_eq!(graph.add_edge(2, 1), Ok(false));
+    assert!(graph.has_edge(2, 1));
+
+    assert!(graph.remove_edge(2, 1));
+    assert!(!graph.remove_edge(1, 2));
+    assert_eq!(graph.degree(1), 0);
+
+    let _ = graph.add_edge(1, 2);
+    assert_eq!(graph.remove_node(1), Some(NodeType::Drone));
+    assert_eq!(graph.neighbours(2), Some(vec![]));
+    assert_eq!(graph.cut_off_nodes(), vec![2]);
+}
+
+#[test]
+fn graph_paths_go_through_drones() {
+    let graph = config_graph();
+    assert_eq!(graph.shortest_path(5, 6), Some(vec![5, 1, 2, 6]));
+    assert_eq!(graph.shortest_path(4, 4), Some(vec![4]));
+    assert_eq!(graph.shortest_path(4, 9), None);
+
+    assert_eq!(
+        graph.simple_paths(5, 6, 3),
+        vec![vec![5, 1, 2, 6], vec![5, 1, 3, 6]]
+    );
+    //Client 4 never forwards, so it is never in the middle of a path
+    assert_eq!(
+        graph.simple_paths(5, 6, 4),
+        vec![
+            vec![5, 1, 2, 6],
+            vec![5, 1, 3, 6],
+            vec![5, 1, 2, 3, 6],
+            vec![5, 1, 3, 2, 6],
+        ]
+    );
+
+    //Drones 1 and 2 are only linked through client 3
+    let mut graph = NetworkGraph::new();
+    graph.add_node(1, NodeType::Drone);
+    graph.add_node(2, NodeType::Drone);
+    graph.add_node(3, NodeType::Client);
+    let _ = graph.add_edge(1, 3);
+    let _ = graph.add_edge(3, 2);
+    assert_eq!(graph.shortest_path(1, 2), None);
+    assert_eq!(
+        graph.reachable_from(1).into_iter().collect::<Vec<_>>(),
+        vec![1, 3]
+    );
+    assert_eq!(graph.diameter().unreachable_pairs, vec![(1, 2)]);
+}
+
+#[test]
+fn graph_articulation_points_and_bridges() {
+    let graph = config_graph();
+    assert_eq!(graph.articulation_points(), vec![1]);
+    assert_eq!(graph.bridges(), vec![(1, 5)]);
+
+    //Chain 1 - 2 - 3 - 4 closed by a cycle 3 - 4 - 5
+    let mut graph = NetworkGraph::new();
+    for id in 1..=5 {
+        graph.add_node(id, NodeType::Drone);
+    }
+    for (a, b) in [(1, 2), (2, 3), (3, 4), (4, 5), (5, 3)] {
+        let _ = graph.add_edge(a, b);
+    }
+    assert_eq!(graph.articulation_points(), vec![2, 3]);
+    assert_eq!(graph.bridges(), vec![(1, 2), (2, 3)]);
+}
+
+#[test]
+fn graph_vertex_connectivity_and_diameter() {
+    let mut graph = config_graph();
+    assert_eq!(graph.vertex_connectivity(4, 6), 2);
+    assert_eq!(graph.vertex_connectivity(5, 6), 1);
+    assert_eq!(graph.vertex_connectivity(5, 9), 0);
+
+    let report = graph.diameter();
+    assert_eq!(report.diameter, 3);
+    assert_eq!(report.endpoints, Some((4, 5)));
+    assert_eq!(report.eccentricity[&1], 2);
+    assert!(report.unreachable_pairs.is_empty());
+
+    graph.remove_edge(2, 6);
+    assert_eq!(graph.vertex_connectivity(4, 6), 1);
+    graph.remove_edge(1, 5);
+    assert_eq!(graph.vertex_connectivity(5, 6), 0);
+    assert_eq!(graph.cut_off_nodes(), vec![5]);
+}
+
+#[test]
+fn servers_without_clients_are_not_cut_off() {
+    let mut graph = NetworkGraph::new();
+    graph.add_node(1, NodeType::Drone);
+    graph.add_node(2, NodeType::Drone);
+    graph.add_node(6, NodeType::Server);
+    graph.add_node(7, NodeType::Server);
+    for (a, b) in [(1, 2), (1, 6), (2, 6), (1, 7)] {
+        graph.add_edge(a, b).expect("Error adding the edge");
+    }
+    assert!(graph.cut_off_nodes().is_empty());
+
+    //Losing the last drone link still cuts off a server
+    graph.remove_edge(1, 7);
+    assert_eq!(graph.cut_off_nodes(), vec![7]);
+}
+
+#[test]
+fn only_new_cut_offs_are_reported() {
+    let mut graph = config_graph();
+    graph.add_node(7, NodeType::Client);
+    assert_eq!(graph.cut_off_nodes(), vec![7]);
+
+    //Client 7 was isolated before the change, so it is not reported again
+    let mut changed = graph.clone();
+    changed.remove_edge(2, 6);
+    assert!(graph.newly_cut_off(&changed).is_empty());
+    changed.remove_edge(1, 5);
+    assert_eq!(graph.newly_cut_off(&changed), vec![5]);
+    assert_eq!(changed.cut_off_nodes(), vec![5, 7]);
+}
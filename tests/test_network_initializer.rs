@@ -0,0 +1,71 @@
+use dronegowski::{parse_config, NetworkInitializer};
+use std::time::Duration;
+use wg_2024::controller::DroneEvent;
+use wg_2024::network::SourceRoutingHeader;
+use wg_2024::packet::{Ack, Packet, PacketType};
+const TIMER: Duration = Duration::from_secs(5);
+
+#[test]
+fn initializer_creates_all_channels() {
+    let network = NetworkInitializer::from_file("tests/common/config.toml")
+        .expect("Error loading the config")
+        .start()
+        .expect("Error starting the network");
+
+    let mut drones = network.drone_ids();
+    drones.sort();
+    assert_eq!(drones, vec![1, 2, 3]);
+    assert_eq!(network.packet_send().len(), 6);
+
+    //Only clients and servers keep their receiver
+    let mut edge_nodes: Vec<_> = network.packet_recv().keys().copied().collect();
+    edge_nodes.sort();
+    assert_eq!(edge_nodes, vec![4, 5, 6]);
+
+    network.shutdown().expect("Error shutting down the network");
+}
+
+#[test]
+fn initializer_routes_packet_from_client_to_server() {
+    let config = parse_config("tests/common/config.toml").expect("Error loading the config");
+    let network = NetworkInitializer::new(config).start().expect("Error starting the network");
+
+    //Ack are never dropped, so the path Client 4 -> Drone 3 -> Server 6 is deterministic
+    let packet = Packet {
+        pack_type: PacketType::Ack(Ack { fragment_index: 0 }),
+        routing_header: SourceRoutingHeader {
+            hop_index: 1,
+            hops: vec![4, 3, 6],
+        },
+        session_id: 1,
+    };
+    network.packet_send()[&3]
+        .send(packet)
+        .expect("Error sending the packet...");
+
+    let packet_test = Packet {
+        pack_type: PacketType::Ack(Ack { fragment_index: 0 }),
+        routing_header: SourceRoutingHeader {
+            hop_index: 2,
+            hops: vec![4, 3, 6],
+        },
+        session_id: 1,
+    };
+
+    let received_packet = network.packet_recv()[&6]
+        .recv_timeout(TIMER)
+        .expect("Timeout: No packet received.");
+    assert_eq!(packet_test, received_packet);
+
+    match network.event_recv().recv_timeout(TIMER) {
+        Ok(DroneEvent::PacketSent(sent_packet)) => assert_eq!(packet_test, sent_packet),
+        other => panic!("Unexpected event {other:?}"),
+    }
+
+    network.shutdown().expect("Error shutting down the network");
+}
+
+#[test]
+fn initializer_rejects_missing_file() {
+    assert!(NetworkInitializer::from_file("tests/common/missing.toml").is_err());
+}
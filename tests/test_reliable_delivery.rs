@@ -0,0 +1,305 @@
+use dronegowski::{
+    flood_response, parse_config, Client, ClientError, DeliveryError, DeliveryEvent,
+    FragmentStatus, NetworkGraph, NetworkHandle, NetworkInitializer, Reassembler, ReliableSender,
+    SimulationController,
+};
+use std::thread::{self, JoinHandle};
+use std::time::Duration;
+use wg_2024::network::{NodeId, SourceRoutingHeader};
+use wg_2024::packet::{Ack, Nack, NackType, NodeType, Packet, PacketType};
+
+fn topology() -> NetworkGraph {
+    NetworkGraph::from_config(
+        &parse_config("tests/common/config.toml").expect("Error loading the config"),
+    )
+}
+
+fn fragment_index(packet: &Packet) -> u64 {
+    match packet.pack_type {
+        PacketType::MsgFragment(ref fragment) => fragment.fragment_index,
+        _ => panic!("Not a fragment: {packet:?}"),
+    }
+}
+
+fn ack(session_id: u64, fragment_index: u64) -> Packet {
+    Packet {
+        pack_type: PacketType::Ack(Ack { fragment_index }),
+        routing_header: SourceRoutingHeader {
+            hop_index: 2,
+            hops: vec![6, 2, 4],
+        },
+        session_id,
+    }
+}
+
+// Nack sent back by `hops[0]`
+fn nack(session_id: u64, fragment_index: u64, nack_type: NackType, hops: Vec<NodeId>) -> Packet {
+    Packet {
+        pack_type: PacketType::Nack(Nack {
+            fragment_index,
+            nack_type,
+        }),
+        routing_header: SourceRoutingHeader {
+            hop_index: hops.len() - 1,
+            hops,
+        },
+        session_id,
+    }
+}
+
+#[test]
+fn dropped_fragments_are_sent_again() {
+    let mut topology = topology();
+    let mut sender = ReliableSender::new(4);
+    let session_id = sender.send(6, &[1; 300], &topology);
+    let sent = sender.take_outgoing();
+    assert_eq!(sent.len(), 3);
+    assert!(sent
+        .iter()
+        .all(|packet| packet.routing_header.hops == vec![4, 2, 6]));
+    assert_eq!(sender.in_progress(), vec![session_id]);
+
+    assert!(sender.handle_packet(&ack(session_id, 0), &mut topology));
+    assert!(sender.handle_packet(
+        &nack(session_id, 1, NackType::Dropped, vec![2, 4]),
+        &mut topology
+    ));
+    let resent = sender.take_outgoing();
+    assert_eq!(resent, vec![sent[1].clone()]);
+    assert!(!sender.handle_packet(&ack(session_id + 1, 0), &mut topology));
+
+    assert!(sender.handle_packet(&ack(session_id, 1), &mut topology));
+    assert!(sender.handle_packet(&ack(session_id, 2), &mut topology));
+    assert_eq!(
+        sender.take_events(),
+        vec![DeliveryEvent::Delivered(session_id)]
+    );
+    assert!(sender.is_idle());
+    let stats = sender.stats();
+    assert_eq!((stats.fragments_sent, stats.retransmissions), (3, 1));
+    assert_eq!(stats.delivered, 1);
+}
+
+#[test]
+fn broken_links_are_routed_around() {
+    let mut topology = topology();
+    let mut sender = ReliableSender::new(4);
+    let session_id = sender.send(6, &[2; 200], &topology);
+    sender.take_outgoing();
+
+    //Drone 2 lost its link to the server
+    sender.handle_packet(
+        &nack(session_id, 0, NackType::ErrorInRouting(6), vec![2, 4]),
+        &mut topology,
+    );
+    assert!(!topology.has_edge(2, 6));
+    let resent = sender.take_outgoing();
+    assert_eq!(resent.len(), 1);
+    assert_eq!(fragment_index(&resent[0]), 0);
+    assert_eq!(resent[0].routing_header.hops, vec![4, 3, 6]);
+    assert_eq!(sender.stats().reroutes, 1);
+
+    //Then drone 3 as well: there is no route left until the network is flooded again
+    sender.handle_packet(
+        &nack(session_id, 1, NackType::ErrorInRouting(6), vec![3, 4]),
+        &mut topology,
+    );
+    assert!(sender.take_outgoing().is_empty());
+    assert!(sender.needs_flood());
+    topology.add_edge(3, 6).expect("The nodes are in the graph");
+    sender.reroute(&topology);
+    assert!(!sender.needs_flood());
+    let resent = sender.take_outgoing();
+    assert_eq!(resent.len(), 1);
+    assert_eq!(fragment_index(&resent[0]), 1);
+    assert_eq!(resent[0].routing_header.hops, vec![4, 3, 6]);
+
+    //A stale route is flooded again, and fails if the destination is gone
+    sender.handle_packet(
+        &nack(session_id, 0, NackType::UnexpectedRecipient(3), vec![3, 4]),
+        &mut topology,
+    );
+    assert!(sender.needs_flood());
+    topology.remove_node(6);
+    sender.reroute(&topology);
+    assert_eq!(
+        sender.take_events(),
+        vec![DeliveryEvent::Failed(session_id, DeliveryError::NoRoute(6))]
+    );
+    assert!(sender.is_idle());
+}
+
+#[test]
+fn budgets_are_enforced() {
+    let mut topology = topology();
+    let mut sender = ReliableSender::new(4).with_max_retries(2);
+    let session_id = sender.send(6, &[3; 10], &topology);
+    assert_eq!(sender.take_outgoing().len(), 1);
+    for retry in 0..3 {
+        sender.handle_packet(
+            &nack(session_id, 0, NackType::Dropped, vec![3, 4]),
+            &mut topology,
+        );
+        assert_eq!(sender.take_outgoing().len(), usize::from(retry < 2));
+    }
+    assert_eq!(
+        sender.take_events(),
+        vec![DeliveryEvent::Failed(
+            session_id,
+            DeliveryError::RetriesExhausted(session_id, 0, 2)
+        )]
+    );
+
+    let session_id = sender.send(1, &[4; 10], &topology);
+    sender.handle_packet(
+        &nack(session_id, 0, NackType::DestinationIsDrone, vec![1, 2, 4]),
+        &mut topology,
+    );
+    assert_eq!(
+        sender.take_events(),
+        vec![DeliveryEvent::Failed(
+            session_id,
+            DeliveryError::DestinationIsDrone(1)
+        )]
+    );
+
+    let mut sender = ReliableSender::new(4)
+        .with_ack_timeout(Duration::from_millis(30))
+        .with_session_timeout(Duration::from_millis(100));
+    let session_id = sender.send(6, &[5; 10], &topology);
+    assert_eq!(sender.take_outgoing().len(), 1);
+    thread::sleep(Duration::from_millis(40));
+    sender.check_timeouts();
+    assert_eq!(sender.take_outgoing().len(), 1);
+    thread::sleep(Duration::from_millis(80));
+    sender.check_timeouts();
+    assert_eq!(
+        sender.take_events(),
+        vec![DeliveryEvent::Failed(
+            session_id,
+            DeliveryError::TimedOut(session_id, Duration::from_millis(100))
+        )]
+    );
+}
+
+// Stands in for server `server_id`: answers the floods, acknowledges every fragment on the
+// reversed route and returns the messages it reassembled
+fn run_server(network: &NetworkHandle, server_id: NodeId) -> JoinHandle<Vec<Vec<u8>>> {
+    let packet_recv = network.packet_recv()[&server_id].clone();
+    let packet_send = network.packet_send().clone();
+    thread::spawn(move || {
+        let mut reassembler = Reassembler::new();
+        let mut messages = Vec::new();
+        while let Ok(packet) = packet_recv.recv_timeout(Duration::from_secs(1)) {
+            let response = match packet.pack_type {
+                PacketType::FloodRequest(ref flood_request) => flood_response(
+                    flood_request,
+                    packet.session_id,
+                    server_id,
+                    NodeType::Server,
+                ),
+                PacketType::MsgFragment(ref fragment) => {
+                    let source = packet.routing_header.hops[0];
+                    if let Ok(FragmentStatus::Complete(message)) =
+                        reassembler.push(source, packet.session_id, fragment)
+                    {
+                        messages.push(message);
+                    }
+                    Packet {
+                        pack_type: PacketType::Ack(Ack {
+                            fragment_index: fragment.fragment_index,
+                        }),
+                        routing_header: SourceRoutingHeader {
+                            hop_index: 1,
+                            hops: packet.routing_header.hops.iter().rev().copied().collect(),
+                        },
+                        session_id: packet.session_id,
+                    }
+                }
+                _ => continue,
+            };
+            let next_hop = response.routing_header.hops[1];
+            let _ = packet_send[&next_hop].send(response);
+        }
+        messages
+    })
+}
+
+#[test]
+fn client_delivers_messages_over_lossy_network() {
+    let network = NetworkInitializer::from_file("tests/common/config.toml")
+        .expect("Error loading the config")
+        .with_seed(7)
+        .start()
+        .expect("Error starting the network");
+    let server = run_server(&network, 6);
+    let mut client = Client::from_network(&network, 4).expect("Error creating the client");
+
+    //Nothing was discovered yet, the first message floods the network
+    let messages: Vec<Vec<u8>> = (0..3)
+        .map(|message| {
+            (0..3000)
+                .map(|byte| ((byte + message) % 256) as u8)
+                .collect()
+        })
+        .collect();
+    for message in &messages {
+        client
+            .send_message(6, message)
+            .expect("Error delivering the message");
+    }
+    let stats = client.delivery().stats();
+    assert_eq!(stats.delivered, 3);
+    assert_eq!(stats.fragments_sent, 3 * 24);
+    assert!(stats.floods >= 1);
+    assert!(stats.retransmissions > 0, "The drones drop some fragments");
+
+    assert_eq!(server.join().expect("The server panicked"), messages);
+    network.stop().expect("Error stopping the network");
+}
+
+#[test]
+fn client_routes_around_removed_link() {
+    let mut config = parse_config("tests/common/config.toml").expect("Error loading the config");
+    for drone in &mut config.drone {
+        drone.pdr = 0.0;
+    }
+    let mut controller = SimulationController::new(
+        NetworkInitializer::new(config)
+            .start()
+            .expect("Error starting the network"),
+    );
+    let server = run_server(controller.network(), 6);
+    let mut client =
+        Client::from_network(controller.network(), 4).expect("Error creating the client");
+    client
+        .discover(Duration::from_millis(300))
+        .expect("Error flooding the network");
+    assert_eq!(
+        client.route_to(6).map(|route| route.hops),
+        Some(vec![4, 2, 6])
+    );
+
+    controller
+        .remove_link(2, 6)
+        .expect("Error removing the link");
+    client
+        .send_message(6, &[9; 500])
+        .expect("Error delivering the message");
+    assert!(client.delivery().stats().reroutes >= 1);
+    assert!(!client.topology().has_edge(2, 6));
+    assert_eq!(
+        client.route_to(6).map(|route| route.hops),
+        Some(vec![4, 3, 6])
+    );
+    assert_eq!(
+        client.send_message(1, &[1]),
+        Err(ClientError::Delivery(DeliveryError::DestinationIsDrone(1)))
+    );
+
+    assert_eq!(
+        server.join().expect("The server panicked"),
+        vec![vec![9; 500]]
+    );
+    controller.stop().expect("Error stopping the network");
+}
@@ -0,0 +1,233 @@
+use dronegowski::{
+    first_divergence, parse_config, read_event_log, ControllerCommand, EventKind, EventRecord,
+    EventRecorder, NetworkInitializer, ReplayEngine, SimulationController,
+};
+use std::path::PathBuf;
+use std::time::Duration;
+use wg_2024::config::Config;
+use wg_2024::controller::DroneEvent;
+use wg_2024::network::SourceRoutingHeader;
+use wg_2024::packet::{Ack, FloodRequest, NodeType, Packet, PacketType};
+const SETTLE: Duration = Duration::from_millis(200);
+
+fn log_path(name: &str) -> PathBuf {
+    let dir = std::env::temp_dir().join(format!("dronegowski-replay-{}", std::process::id()));
+    std::fs::create_dir_all(&dir).expect("Error creating the log directory");
+    dir.join(name)
+}
+
+fn ack_packet(hops: Vec<u8>) -> Packet {
+    Packet {
+        pack_type: PacketType::Ack(Ack { fragment_index: 0 }),
+        routing_header: SourceRoutingHeader { hop_index: 1, hops },
+        session_id: 1,
+    }
+}
+
+// Acks are never dropped, so the recording does not depend on the PDR
+fn record_run(name: &str) -> Vec<EventRecord> {
+    let path = log_path(name);
+    let network = NetworkInitializer::from_file("tests/common/config.toml")
+        .expect("Error loading the config")
+        .start()
+        .expect("Error starting the network");
+    let mut controller = SimulationController::new(network)
+        .with_recorder(EventRecorder::create(&path).expect("Error creating the log"));
+
+    controller
+        .inject(3, ack_packet(vec![4, 3, 6]))
+        .expect("Error injecting the packet");
+    while controller.recv_event_timeout(SETTLE).is_some() {}
+    controller
+        .apply(ControllerCommand::SetPdr(1, 0.5))
+        .expect("Error setting the PDR");
+    controller
+        .inject(1, ack_packet(vec![5, 1, 2, 6]))
+        .expect("Error injecting the packet");
+    while controller.recv_event_timeout(SETTLE).is_some() {}
+    controller.stop().expect("Error stopping the network");
+
+    read_event_log(&path).expect("Error reading the log")
+}
+
+#[test]
+fn replay_reproduces_recording() {
+    let records = record_run("identical.jsonl");
+    assert_eq!(
+        records.iter().map(|record| record.kind).collect::<Vec<_>>(),
+        vec![
+            EventKind::PacketInjected,
+            EventKind::PacketSent,
+            EventKind::Command,
+            EventKind::PacketInjected,
+            EventKind::PacketSent,
+            EventKind::PacketSent,
+        ]
+    );
+
+    let config = parse_config("tests/common/config.toml").expect("Error loading the config");
+    let report = ReplayEngine::new(config, records)
+        .with_settle_time(SETTLE)
+        .run()
+        .expect("Error replaying the log");
+    assert_eq!(report.inputs_replayed, 3);
+    assert_eq!(report.expected_events, 3);
+    assert_eq!(report.actual_events, 3);
+    assert!(report.is_identical(), "{:?}", report.divergence);
+}
+
+// The drones form a tree, so each of them receives the flood once. Drones 4 and 5 have no other
+// neighbour and answer at the same time, drone 1 forwards their responses in either order
+fn flooding_config() -> Config {
+    toml::from_str(
+        r#"
+        [[drone]]
+        id = 1
+        connected_node_ids = [5, 3, 2, 4, 10]
+        pdr = 0.0
+
+        [[drone]]
+        id = 2
+        connected_node_ids = [1, 20]
+        pdr = 0.0
+
+        [[drone]]
+        id = 3
+        connected_node_ids = [1, 20]
+        pdr = 0.0
+
+        [[drone]]
+        id = 4
+        connected_node_ids = [1]
+        pdr = 0.0
+
+        [[drone]]
+        id = 5
+        connected_node_ids = [1]
+        pdr = 0.0
+
+        [[client]]
+        id = 10
+        connected_drone_ids = [1]
+
+        [[server]]
+        id = 20
+        connected_drone_ids = [2, 3]
+        "#,
+    )
+    .expect("Error occurred while parsing the config")
+}
+
+#[test]
+fn flooding_run_is_replayed_without_divergence() {
+    let path = log_path("flooding.jsonl");
+    let mut controller = SimulationController::new(
+        NetworkInitializer::new(flooding_config())
+            .start()
+            .expect("Error starting the network"),
+    )
+    .with_recorder(EventRecorder::create(&path).expect("Error creating the log"));
+    let flood_request = Packet {
+        pack_type: PacketType::FloodRequest(FloodRequest {
+            flood_id: 1,
+            initiator_id: 10,
+            path_trace: vec![(10, NodeType::Client)],
+        }),
+        routing_header: SourceRoutingHeader {
+            hop_index: 0,
+            hops: Vec::new(),
+        },
+        session_id: 1,
+    };
+    controller
+        .inject(1, flood_request)
+        .expect("Error injecting the packet");
+    while controller.recv_event_timeout(SETTLE).is_some() {}
+    controller.stop().expect("Error stopping the network");
+    let records = read_event_log(&path).expect("Error reading the log");
+
+    //Drone 1 sends four requests and forwards two responses, the other drones send one packet
+    let sent_by = |drone| {
+        records
+            .iter()
+            .filter(|record| record.kind == EventKind::PacketSent && record.drone == Some(drone))
+            .count()
+    };
+    assert_eq!(
+        (1..=5).map(sent_by).collect::<Vec<_>>(),
+        vec![6, 1, 1, 1, 1]
+    );
+
+    for _ in 0..3 {
+        let report = ReplayEngine::new(flooding_config(), records.clone())
+            .with_settle_time(SETTLE)
+            .run()
+            .expect("Error replaying the log");
+        assert_eq!(report.inputs_replayed, 1);
+        assert_eq!(report.actual_events, 10);
+        assert!(report.is_identical(), "{:?}", report.divergence);
+    }
+}
+
+#[test]
+fn replay_reports_first_divergence() {
+    let mut records = record_run("tampered.jsonl");
+    //The second drone 2 forwards to 6, pretend it went elsewhere
+    let tampered = records
+        .iter_mut()
+        .rposition(|record| record.kind == EventKind::PacketSent)
+        .expect("The run sent packets");
+    records[tampered]
+        .packet
+        .as_mut()
+        .expect("Drone events have a packet")
+        .routing_header
+        .hops = vec![5, 1, 2, 4];
+
+    let config = parse_config("tests/common/config.toml").expect("Error loading the config");
+    let report = ReplayEngine::new(config, records.clone())
+        .with_settle_time(SETTLE)
+        .run()
+        .expect("Error replaying the log");
+    let divergence = report.divergence.expect("The replay should diverge");
+    assert_eq!(divergence.drone, Some(2));
+    assert_eq!(divergence.index, 0);
+    assert_eq!(divergence.expected, Some(records[tampered].clone()));
+    assert_eq!(
+        divergence
+            .actual
+            .and_then(|record| record.packet)
+            .map(|packet| packet.routing_header.hops),
+        Some(vec![5, 1, 2, 6])
+    );
+}
+
+#[test]
+fn divergence_reports_missing_events() {
+    let sent = |timestamp_us, hops| {
+        EventRecord::new(timestamp_us, &DroneEvent::PacketSent(ack_packet(hops)))
+    };
+    let expected = vec![sent(10, vec![4, 3, 6]), sent(20, vec![5, 1, 2])];
+    let actual = vec![sent(12, vec![4, 3, 6])];
+
+    assert_eq!(first_divergence(&expected, &expected), None);
+    let divergence = first_divergence(&expected, &actual).expect("An event is missing");
+    assert_eq!(divergence.drone, Some(5));
+    assert_eq!(divergence.expected, Some(expected[1].clone()));
+    assert_eq!(divergence.actual, None);
+
+    //Between two inputs the events of a drone can come in any order, but not across inputs
+    let expected = vec![sent(10, vec![4, 3, 6]), sent(20, vec![4, 3, 1])];
+    let swapped = vec![sent(11, vec![4, 3, 1]), sent(21, vec![4, 3, 6])];
+    assert_eq!(first_divergence(&expected, &swapped), None);
+    let input = EventRecord::command(15, ControllerCommand::SetPdr(1, 0.5));
+    let divergence = first_divergence(
+        &[expected[0].clone(), input.clone(), expected[1].clone()],
+        &[swapped[0].clone(), input, swapped[1].clone()],
+    )
+    .expect("The events moved across an input");
+    assert_eq!(divergence.drone, Some(4));
+    assert_eq!(divergence.index, 0);
+    assert_eq!(divergence.expected, Some(expected[0].clone()));
+    assert_eq!(divergence.actual, Some(swapped[0].clone()));
+}
@@ -13,24 +13,44 @@ use wg_2024::network::SourceRoutingHeader;
 use wg_2024::packet::{Ack, Fragment, Nack, NackType, Packet, PacketType};
 const TIMER: Duration = Duration::from_secs(5);
 
+fn fragment_packet() -> Packet {
+    Packet {
+        pack_type: PacketType::MsgFragment(Fragment {
+            fragment_index: 10,
+            total_n_fragments: 15,
+            length: 5,
+            data: [5; 128],
+        }),
+        routing_header: SourceRoutingHeader {
+            hop_index: 1,
+            hops: vec![0, 1, 2], //Path: Drone 1 -> Drone 2
+        },
+        session_id: 1,
+    }
+}
+
 #[test]
-#[should_panic(expected = "pdr out of bounds because is negative")]
 fn set_pdr_negative() {
     //Create channel
     let (sim_controller_send, _) = crossbeam_channel::unbounded::<DroneEvent>();
     let (controller_send, controller_receive) = crossbeam_channel::unbounded::<DroneCommand>();
-    let (_send_packet, packet_receive) = crossbeam_channel::unbounded::<Packet>();
+    let (packet_send, packet_receive) = crossbeam_channel::unbounded::<Packet>();
+
+    //Create channel for the neighbor
+    let (neighbor_send, neighbor_receive) = crossbeam_channel::unbounded::<Packet>();
+    let mut senders = HashMap::new();
+    senders.insert(2, neighbor_send);
 
     let mut my_drone = Dronegowski::new(
         1,
         sim_controller_send.clone(),
         controller_receive,
         packet_receive,
-        HashMap::new(),
-        0.1, //Valid PDR
+        senders,
+        0.0, //Valid PDR
     );
 
-    let handle = std::thread::spawn(move || {
+    std::thread::spawn(move || {
         my_drone.run();
     });
 
@@ -38,30 +58,39 @@ fn set_pdr_negative() {
         .send(DroneCommand::SetPacketDropRate(-0.7))
         .expect("Error sending the command...");
 
-    //Wait until thread terminate and verify if the panic success
-    handle
-        .join()
-        .expect("pdr out of bounds because is negative");
+    //The invalid command is ignored: the drone is still running with PDR 0
+    packet_send
+        .send(fragment_packet())
+        .expect("Error sending the fragment...");
+
+    let received_packet = neighbor_receive
+        .recv_timeout(TIMER)
+        .expect("Timeout: No packet received.");
+    assert_eq!(received_packet.routing_header.hop_index, 2);
 }
 
 #[test]
-#[should_panic(expected = "pdr out of bounds because is too big")]
 fn set_pdr_too_big() {
     //Create channel
     let (sim_controller_send, _) = crossbeam_channel::unbounded::<DroneEvent>();
     let (controller_send, controller_receive) = crossbeam_channel::unbounded::<DroneCommand>();
-    let (_send_packet, packet_receive) = crossbeam_channel::unbounded::<Packet>();
+    let (packet_send, packet_receive) = crossbeam_channel::unbounded::<Packet>();
+
+    //Create channel for the neighbor
+    let (neighbor_send, neighbor_receive) = crossbeam_channel::unbounded::<Packet>();
+    let mut senders = HashMap::new();
+    senders.insert(2, neighbor_send);
 
     let mut my_drone = Dronegowski::new(
         1,
         sim_controller_send.clone(),
         controller_receive,
         packet_receive,
-        HashMap::new(),
-        0.1, //Valid PDR
+        senders,
+        0.0, //Valid PDR
     );
 
-    let handle = std::thread::spawn(move || {
+    std::thread::spawn(move || {
         my_drone.run();
     });
 
@@ -69,8 +98,15 @@ fn set_pdr_too_big() {
         .send(DroneCommand::SetPacketDropRate(1.7))
         .expect("Error sending the command...");
 
-    //Wait until thread terminate and verify if the panic success
-    handle.join().expect("pdr out of bounds because is too big");
+    //A PDR of 1.7 would drop every fragment, the fragment must still be forwarded
+    packet_send
+        .send(fragment_packet())
+        .expect("Error sending the fragment...");
+
+    let received_packet = neighbor_receive
+        .recv_timeout(TIMER)
+        .expect("Timeout: No packet received.");
+    assert_eq!(received_packet.routing_header.hop_index, 2);
 }
 
 #[test]
@@ -99,23 +135,27 @@ fn set_pdr() {
 }
 
 #[test]
-#[should_panic(expected = "The node it's not a neighbour")]
 fn remove_sender_no_neighbour() {
     //Create channel
     let (sim_controller_send, _) = crossbeam_channel::unbounded::<DroneEvent>();
     let (controller_send, controller_receive) = crossbeam_channel::unbounded::<DroneCommand>();
-    let (_send_packet, packet_receive) = crossbeam_channel::unbounded::<Packet>();
+    let (packet_send, packet_receive) = crossbeam_channel::unbounded::<Packet>();
+
+    //Create channel for the neighbor
+    let (neighbor_send, neighbor_receive) = crossbeam_channel::unbounded::<Packet>();
+    let mut senders = HashMap::new();
+    senders.insert(2, neighbor_send);
 
     let mut my_drone = Dronegowski::new(
         1,
         sim_controller_send.clone(),
         controller_receive,
         packet_receive,
-        HashMap::new(),
-        0.1, //Valid PDR
+        senders,
+        0.0, //Valid PDR
     );
 
-    let handle = std::thread::spawn(move || {
+    std::thread::spawn(move || {
         my_drone.run();
     });
 
@@ -123,8 +163,15 @@ fn remove_sender_no_neighbour() {
         .send(DroneCommand::RemoveSender(0))
         .expect("Error sending the command...");
 
-    //Wait until thread terminate and verify if the panic success
-    handle.join().expect("The node it's not a neighbour");
+    //Removing an unknown neighbour doesn't stop the drone
+    packet_send
+        .send(fragment_packet())
+        .expect("Error sending the fragment...");
+
+    let received_packet = neighbor_receive
+        .recv_timeout(TIMER)
+        .expect("Timeout: No packet received.");
+    assert_eq!(received_packet.routing_header.hop_index, 2);
 }
 
 #[test]
@@ -214,19 +261,19 @@ fn remove_sender() {
 }
 
 #[test]
-#[should_panic(expected = "The neighbour is already present")]
 fn add_sender_already_present() {
     //Create channel
     let (sim_controller_send, _) = crossbeam_channel::unbounded::<DroneEvent>();
     let (controller_send, controller_receive) = crossbeam_channel::unbounded::<DroneCommand>();
-    let (_send_packet, packet_receive) = crossbeam_channel::unbounded::<Packet>();
+    let (packet_send, packet_receive) = crossbeam_channel::unbounded::<Packet>();
 
     //Create channel for the neighbor
-    let (neighbor_send, _) = crossbeam_channel::unbounded::<Packet>();
+    let (neighbor_send, neighbor_receive) = crossbeam_channel::unbounded::<Packet>();
+    let (other_send, other_receive) = crossbeam_channel::unbounded::<Packet>();
 
     //Create map for neighbors
     let mut senders = HashMap::new();
-    senders.insert(0, neighbor_send.clone());
+    senders.insert(2, neighbor_send);
 
     let mut my_drone = Dronegowski::new(
         1,
@@ -234,18 +281,27 @@ fn add_sender_already_present() {
         controller_receive,
         packet_receive,
         senders,
-        0.1, //Valid PDR
+        0.0, //Valid PDR
     );
 
-    let handle = std::thread::spawn(move || {
+    std::thread::spawn(move || {
         my_drone.run();
     });
 
     controller_send
-        .send(DroneCommand::AddSender(0, neighbor_send))
+        .send(DroneCommand::AddSender(2, other_send))
         .expect("Error sending the command...");
 
-    handle.join().expect("The neighbour is already present");
+    //The duplicated sender is rejected and the original one is kept
+    packet_send
+        .send(fragment_packet())
+        .expect("Error sending the fragment...");
+
+    let received_packet = neighbor_receive
+        .recv_timeout(TIMER)
+        .expect("Timeout: No packet received.");
+    assert_eq!(received_packet.routing_header.hop_index, 2);
+    assert!(other_receive.try_recv().is_err());
 }
 
 #[test]
@@ -0,0 +1,196 @@
+use dronegowski::{
+    parse_config, ControllerCommand, Scenario, ScenarioAction, ScenarioError, ScheduledAction,
+    SimulationControllerError, SimulationRunner,
+};
+use std::process::Command;
+use std::time::Duration;
+use wg_2024::packet::NodeType;
+
+#[test]
+fn scenario_file_is_parsed_and_scheduled() {
+    let scenario =
+        Scenario::from_file("tests/common/scenario.toml").expect("Error loading the scenario");
+    assert_eq!(scenario.steps.len(), 4);
+    assert_eq!(scenario.steps[1].at, 2.0);
+    assert_eq!(
+        scenario.steps[3].action,
+        ScenarioAction::AddLink { link: (2, 5) }
+    );
+    assert_eq!(scenario.end(), Duration::from_secs(10));
+
+    let schedule = scenario.schedule();
+    assert_eq!(schedule.len(), 103);
+    assert!(schedule.windows(2).all(|pair| pair[0].at <= pair[1].at));
+    assert_eq!(schedule[0].at, Duration::ZERO);
+    assert_eq!(
+        schedule[1].action,
+        ScheduledAction::SendMessage {
+            client: 4,
+            server: 6,
+            index: 2,
+            count: 100
+        }
+    );
+    assert_eq!(schedule[1].at, Duration::from_millis(100));
+    let commands: Vec<(Duration, ControllerCommand)> = schedule
+        .iter()
+        .filter_map(|scheduled| match scheduled.action {
+            ScheduledAction::Command(command) => Some((scheduled.at, command)),
+            ScheduledAction::SendMessage { .. } => None,
+        })
+        .collect();
+    assert_eq!(
+        commands,
+        vec![
+            (Duration::from_secs(2), ControllerCommand::SetPdr(3, 0.5)),
+            (Duration::from_secs(4), ControllerCommand::Crash(1)),
+            (
+                Duration::from_millis(5500),
+                ControllerCommand::AddLink(2, 5)
+            ),
+        ]
+    );
+    assert_eq!(schedule[21].action.to_string(), "set drone 3 pdr to 0.5");
+}
+
+#[test]
+fn invalid_steps_are_refused() {
+    let step = |fields: &str| Scenario::parse(&format!("[[step]]\n{fields}"));
+    assert!(matches!(
+        step("at = -1\naction = \"crash\"\ndrone = 1"),
+        Err(ScenarioError::InvalidTime(0))
+    ));
+    assert!(matches!(
+        step("at = 3\naction = \"send_messages\"\nclient = 4\nserver = 6\ncount = 5\nuntil = 1"),
+        Err(ScenarioError::EmptyWindow(0))
+    ));
+    assert!(matches!(
+        step("at = 0\naction = \"send_messages\"\nclient = 4\nserver = 6\ncount = 0\nuntil = 1"),
+        Err(ScenarioError::NoMessages(0))
+    ));
+    assert!(matches!(
+        step("at = 0\naction = \"explode\"\ndrone = 1"),
+        Err(ScenarioError::Parse(_))
+    ));
+}
+
+#[test]
+fn scenario_nodes_are_checked_against_config() {
+    let config = parse_config("tests/common/config.toml").expect("Error loading the config");
+    let scenario = Scenario::parse(
+        "[[step]]\nat = 0\naction = \"crash\"\ndrone = 4\n\n\
+         [[step]]\nat = 0\naction = \"add_link\"\nlink = [1, 42]\n\n\
+         [[step]]\nat = 0\naction = \"send_messages\"\nclient = 4\nserver = 5\ncount = 1\nuntil = 0",
+    )
+    .expect("Error parsing the scenario");
+    let errors = scenario.validate(&config).expect_err("The nodes are wrong");
+    assert_eq!(errors.len(), 3);
+    assert!(matches!(
+        errors[0],
+        ScenarioError::WrongNodeType(0, 4, NodeType::Drone)
+    ));
+    assert!(matches!(errors[1], ScenarioError::UnknownNode(1, 42)));
+    assert!(matches!(
+        errors[2],
+        ScenarioError::WrongNodeType(2, 5, NodeType::Server)
+    ));
+
+    let scenario =
+        Scenario::from_file("tests/common/scenario.toml").expect("Error loading the scenario");
+    assert!(scenario.validate(&config).is_ok());
+}
+
+#[test]
+fn runner_performs_scenario_steps() {
+    let config = parse_config("tests/common/config.toml").expect("Error loading the config");
+    let scenario = Scenario::parse(
+        r#"
+        [[step]]
+        at = 0
+        action = "set_pdr"
+        drone = 1
+        pdr = 0
+
+        [[step]]
+        at = 0
+        action = "set_pdr"
+        drone = 2
+        pdr = 0
+
+        [[step]]
+        at = 0
+        action = "set_pdr"
+        drone = 3
+        pdr = 0
+
+        [[step]]
+        at = 0
+        action = "send_messages"
+        client = 4
+        server = 6
+        count = 20
+        until = 0.2
+
+        [[step]]
+        at = 0.1
+        action = "remove_link"
+        link = [2, 3]
+
+        [[step]]
+        at = 0.15
+        action = "crash"
+        drone = 1
+        "#,
+    )
+    .expect("Error parsing the scenario");
+
+    let summary = SimulationRunner::new(config)
+        .with_scenario(&scenario)
+        .with_duration(scenario.end() + Duration::from_millis(50))
+        .run()
+        .expect("Error running the scenario");
+    assert_eq!(summary.fragments_sent, 20);
+    assert_eq!(summary.fragments_delivered, 20);
+    assert_eq!(
+        summary.faults_applied,
+        vec![
+            ControllerCommand::SetPdr(1, 0.0),
+            ControllerCommand::SetPdr(2, 0.0),
+            ControllerCommand::SetPdr(3, 0.0),
+            ControllerCommand::RemoveLink(2, 3),
+        ]
+    );
+    assert_eq!(
+        summary.faults_refused,
+        vec![(
+            ControllerCommand::Crash(1),
+            SimulationControllerError::WouldCutOff(vec![5])
+        )]
+    );
+}
+
+#[test]
+fn binary_runs_scenario() {
+    let output = Command::new(env!("CARGO_BIN_EXE_dronegowski-sim"))
+        .args([
+            "tests/common/config.toml",
+            "--scenario",
+            "tests/common/scenario.toml",
+            "--duration",
+            "0.5",
+        ])
+        .output()
+        .expect("Error running the simulator");
+    assert_eq!(output.status.code(), Some(0));
+    assert!(String::from_utf8_lossy(&output.stdout).contains("of 5 fragments"));
+
+    let output = Command::new(env!("CARGO_BIN_EXE_dronegowski-sim"))
+        .args([
+            "tests/common/config.toml",
+            "--scenario",
+            "tests/common/missing.toml",
+        ])
+        .output()
+        .expect("Error running the simulator");
+    assert_eq!(output.status.code(), Some(2));
+}
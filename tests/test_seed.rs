@@ -0,0 +1,121 @@
+use crossbeam_channel::select;
+use dronegowski::{drone_seed, Dronegowski, NetworkInitializer};
+use std::collections::{BTreeMap, BTreeSet, HashMap};
+use std::time::Duration;
+use wg_2024::controller::{DroneCommand, DroneEvent};
+use wg_2024::drone::Drone;
+use wg_2024::network::{NodeId, SourceRoutingHeader};
+use wg_2024::packet::{Fragment, Packet, PacketType};
+const TIMER: Duration = Duration::from_secs(5);
+const FRAGMENTS: u64 = 64;
+
+//Runs a drone with PDR 0.5 and returns which fragments were dropped
+fn drop_decisions(seed: u64) -> Vec<bool> {
+    let (sim_controller_send, _) = crossbeam_channel::unbounded::<DroneEvent>();
+    let (_send_controller, controller_receive) = crossbeam_channel::unbounded::<DroneCommand>();
+    let (packet_send, packet_receive) = crossbeam_channel::unbounded::<Packet>();
+
+    //Create channel for the neighbors
+    let (client_send, client_receive) = crossbeam_channel::unbounded::<Packet>();
+    let (neighbor_send, neighbor_receive) = crossbeam_channel::unbounded::<Packet>();
+    let mut senders = HashMap::new();
+    senders.insert(0, client_send);
+    senders.insert(2, neighbor_send);
+
+    let mut my_drone = Dronegowski::new_with_seed(
+        1,
+        sim_controller_send,
+        controller_receive,
+        packet_receive,
+        senders,
+        0.5,
+        seed,
+    );
+    assert_eq!(my_drone.get_seed(), seed);
+
+    std::thread::spawn(move || {
+        my_drone.run();
+    });
+
+    for fragment_index in 0..FRAGMENTS {
+        packet_send
+            .send(Packet {
+                pack_type: PacketType::MsgFragment(Fragment {
+                    fragment_index,
+                    total_n_fragments: FRAGMENTS,
+                    length: 5,
+                    data: [5; 128],
+                }),
+                routing_header: SourceRoutingHeader {
+                    hop_index: 1,
+                    hops: vec![0, 1, 2],
+                },
+                session_id: 1,
+            })
+            .expect("Error sending the fragment...");
+    }
+
+    let mut dropped = vec![false; FRAGMENTS as usize];
+    for _ in 0..FRAGMENTS {
+        select! {
+            recv(client_receive) -> nack => {
+                if let Ok(Packet { pack_type: PacketType::Nack(nack), .. }) = nack {
+                    dropped[nack.fragment_index as usize] = true;
+                }
+            },
+            recv(neighbor_receive) -> _ => {},
+            default(TIMER) => panic!("Timeout: No packet received."),
+        }
+    }
+    dropped
+}
+
+#[test]
+fn same_seed_same_drops() {
+    let first = drop_decisions(42);
+    let second = drop_decisions(42);
+    assert_eq!(first, second);
+
+    //With PDR 0.5 on 64 fragments both outcomes show up
+    assert!(first.iter().any(|dropped| *dropped));
+    assert!(first.iter().any(|dropped| !*dropped));
+}
+
+#[test]
+fn different_seeds_different_drops() {
+    assert_ne!(drop_decisions(1), drop_decisions(2));
+}
+
+#[test]
+fn drone_seeds_derived_from_master_seed() {
+    assert_eq!(drone_seed(7, 1), drone_seed(7, 1));
+    assert_ne!(drone_seed(7, 1), drone_seed(7, 2));
+    assert_ne!(drone_seed(7, 1), drone_seed(8, 1));
+
+    //Seed every drone of a network ran with
+    let boot = |master_seed: u64| -> BTreeMap<NodeId, u64> {
+        NetworkInitializer::from_file("tests/common/config.toml")
+            .expect("Error loading the config")
+            .with_seed(master_seed)
+            .start()
+            .expect("Error starting the network")
+            .shutdown()
+            .expect("Error shutting down the network")
+            .into_iter()
+            .map(|(id, report)| (id, report.seed))
+            .collect()
+    };
+    let seeds = boot(7);
+    assert_eq!(
+        seeds,
+        [1, 2, 3]
+            .into_iter()
+            .map(|id| (id, drone_seed(7, id)))
+            .collect::<BTreeMap<_, _>>()
+    );
+    assert_eq!(boot(7), seeds);
+
+    let distinct: BTreeSet<u64> = seeds.values().copied().collect();
+    assert_eq!(distinct.len(), seeds.len());
+    assert!(boot(8).iter().all(|(id, seed)| seeds[id] != *seed));
+}
@@ -0,0 +1,139 @@
+use dronegowski::{
+    parse_config, Client, ClientError, NetworkHandle, NetworkInitializer, RequestHandler, Server,
+    ServerError,
+};
+use std::thread::{self, JoinHandle};
+use std::time::Duration;
+use wg_2024::network::NodeId;
+use wg_2024::packet::{Fragment, NodeType, PacketType};
+
+const TIMEOUT: Duration = Duration::from_secs(5);
+
+fn start_network(lossless: bool) -> NetworkHandle {
+    let mut config = parse_config("tests/common/config.toml").expect("Error loading the config");
+    if lossless {
+        for drone in &mut config.drone {
+            drone.pdr = 0.0;
+        }
+    }
+    NetworkInitializer::new(config)
+        .with_seed(11)
+        .start()
+        .expect("Error starting the network")
+}
+
+fn spawn<H: RequestHandler + Send + 'static>(mut server: Server<H>) -> JoinHandle<Server<H>> {
+    thread::spawn(move || {
+        server.run();
+        server
+    })
+}
+
+// Keeps the requests without answering them
+#[derive(Default)]
+struct Recorder(Vec<(NodeId, Vec<u8>)>);
+
+impl RequestHandler for Recorder {
+    fn handle_request(&mut self, client_id: NodeId, request: &[u8]) -> Option<Vec<u8>> {
+        self.0.push((client_id, request.to_vec()));
+        None
+    }
+}
+
+fn uppercase(_client_id: NodeId, request: &[u8]) -> Option<Vec<u8>> {
+    Some(request.to_ascii_uppercase())
+}
+
+#[test]
+fn server_answers_requests_over_lossy_network() {
+    let network = start_network(false);
+    let server = Server::from_network(&network, 6, uppercase).expect("Error creating the server");
+    let stop = server.stop_token();
+    let server = spawn(server);
+
+    let request: Vec<u8> = b"hello dronegowski ".repeat(60);
+    for client_id in [4, 5] {
+        let mut client =
+            Client::from_network(&network, client_id).expect("Error creating the client");
+        let response = client
+            .request(6, &request, TIMEOUT)
+            .expect("Error sending the request");
+        assert_eq!(response, request.to_ascii_uppercase());
+    }
+
+    //Lets the last Acks of the responses reach the server
+    thread::sleep(Duration::from_millis(200));
+    stop.stop();
+    let server = server.join().expect("The server panicked");
+    let stats = server.stats();
+    assert_eq!(stats.requests_handled, 2);
+    assert_eq!(stats.responses_sent, 2);
+    assert_eq!(stats.acks_sent, stats.fragments_received);
+    assert!(stats.fragments_received >= 2 * 9);
+    assert_eq!(server.delivery().stats().delivered, 2);
+    network.stop().expect("Error stopping the network");
+}
+
+#[test]
+fn server_learns_routes_from_requests() {
+    let network = start_network(true);
+    let server =
+        Server::from_network(&network, 6, Recorder::default()).expect("Error creating the server");
+    let stop = server.stop_token();
+    let server = spawn(server);
+
+    let mut client = Client::from_network(&network, 4).expect("Error creating the client");
+    assert_eq!(
+        client.request(6, b"ping", Duration::from_millis(300)),
+        Err(ClientError::NoResponse(6))
+    );
+
+    stop.stop();
+    let server = server.join().expect("The server panicked");
+    assert_eq!(server.handler().0, vec![(4, b"ping".to_vec())]);
+    assert_eq!(server.stats().responses_sent, 0);
+    assert_eq!(server.topology().node_type(4), Some(NodeType::Client));
+    assert_eq!(server.topology().shortest_path(6, 4), Some(vec![6, 2, 4]));
+    assert_eq!(server.delivery().stats().floods, 0);
+    network.stop().expect("Error stopping the network");
+}
+
+#[test]
+fn fragments_are_acknowledged_on_reversed_route() {
+    let network = start_network(true);
+    assert!(matches!(
+        Server::from_network(&network, 4, uppercase),
+        Err(ServerError::NotAServer(4))
+    ));
+    let server = Server::from_network(&network, 6, uppercase).expect("Error creating the server");
+    let stop = server.stop_token();
+    let server = spawn(server);
+
+    let mut client = Client::from_network(&network, 4).expect("Error creating the client");
+    client
+        .discover(Duration::from_millis(300))
+        .expect("Error flooding the network");
+    let fragment = Fragment {
+        fragment_index: 0,
+        total_n_fragments: 2,
+        length: 128,
+        data: [1; 128],
+    };
+    client
+        .send_to(6, 77, PacketType::MsgFragment(fragment))
+        .expect("Error sending the fragment");
+
+    //The session is not one of the client, which would drop the Ack, so it is read from the channel
+    let ack = network.packet_recv()[&4]
+        .recv_timeout(TIMEOUT)
+        .expect("No Ack received");
+    assert!(matches!(ack.pack_type, PacketType::Ack(ref ack) if ack.fragment_index == 0));
+    assert_eq!(ack.session_id, 77);
+    assert_eq!(ack.routing_header.hops, vec![6, 2, 4]);
+    assert_eq!(ack.routing_header.hop_index, 2);
+
+    stop.stop();
+    let server = server.join().expect("The server panicked");
+    assert_eq!(server.stats().requests_handled, 0);
+    network.stop().expect("Error stopping the network");
+}
@@ -0,0 +1,109 @@
+use dronegowski::{NetworkInitializer, ShortcutDispatcher, ShortcutError, SimulationController};
+use std::time::Duration;
+use wg_2024::controller::DroneEvent;
+use wg_2024::network::SourceRoutingHeader;
+use wg_2024::packet::{Ack, Fragment, Nack, NackType, Packet, PacketType};
+const TIMER: Duration = Duration::from_secs(5);
+
+#[test]
+fn dispatcher_delivers_to_final_hop() {
+    let (client_send, client_receive) = crossbeam_channel::unbounded::<Packet>();
+    let mut dispatcher = ShortcutDispatcher::new();
+    dispatcher.add_destination(4, client_send);
+
+    let packet = Packet {
+        pack_type: PacketType::Nack(Nack {
+            fragment_index: 3,
+            nack_type: NackType::Dropped,
+        }),
+        routing_header: SourceRoutingHeader {
+            hop_index: 1,
+            hops: vec![2, 1, 4],
+        },
+        session_id: 7,
+    };
+    assert_eq!(dispatcher.dispatch(packet.clone()), Ok(4));
+
+    let received_packet = client_receive
+        .recv_timeout(TIMER)
+        .expect("Timeout: No packet received.");
+    assert_eq!(received_packet.routing_header.hop_index, 2);
+    assert_eq!(received_packet.pack_type, packet.pack_type);
+
+    let mut unknown = packet.clone();
+    unknown.routing_header.hops = vec![2, 1, 9];
+    assert_eq!(
+        dispatcher.dispatch(unknown),
+        Err(ShortcutError::UnknownDestination(9))
+    );
+
+    let mut empty = packet.clone();
+    empty.routing_header.hops = vec![];
+    assert_eq!(dispatcher.dispatch(empty), Err(ShortcutError::EmptyRoute));
+
+    let fragment = Packet {
+        pack_type: PacketType::MsgFragment(Fragment {
+            fragment_index: 0,
+            total_n_fragments: 1,
+            length: 128,
+            data: [1; 128],
+        }),
+        ..packet
+    };
+    assert_eq!(
+        dispatcher.dispatch(fragment),
+        Err(ShortcutError::NotAllowed)
+    );
+
+    drop(client_receive);
+    let ack = Packet {
+        pack_type: PacketType::Ack(Ack { fragment_index: 0 }),
+        routing_header: SourceRoutingHeader {
+            hop_index: 0,
+            hops: vec![1, 4],
+        },
+        session_id: 7,
+    };
+    assert_eq!(
+        dispatcher.dispatch(ack),
+        Err(ShortcutError::DestinationDisconnected(4))
+    );
+}
+
+#[test]
+fn ack_reaches_client_after_crash() {
+    let network = NetworkInitializer::from_file("tests/common/config.toml")
+        .expect("Error loading the config")
+        .start()
+        .expect("Error starting the network");
+    let mut controller = SimulationController::new(network);
+
+    //The path Server 6 -> Drone 3 -> Drone 2 -> Client 4 is split by the crash of drone 2
+    controller.crash(2).expect("Error crashing the drone");
+    let ack = Packet {
+        pack_type: PacketType::Ack(Ack { fragment_index: 0 }),
+        routing_header: SourceRoutingHeader {
+            hop_index: 1,
+            hops: vec![6, 3, 2, 4],
+        },
+        session_id: 1,
+    };
+    controller.network().packet_send()[&3]
+        .send(ack.clone())
+        .expect("Error sending the packet...");
+
+    match controller.recv_event_timeout(TIMER) {
+        Some(DroneEvent::ControllerShortcut(packet)) => assert_eq!(packet, ack),
+        other => panic!("Unexpected event {other:?}"),
+    }
+    assert_eq!(controller.traffic().shortcuts_delivered, 1);
+    assert!(controller.take_undelivered().is_empty());
+
+    let received_packet = controller.network().packet_recv()[&4]
+        .recv_timeout(TIMER)
+        .expect("Timeout: No packet received.");
+    assert_eq!(received_packet.pack_type, ack.pack_type);
+    assert_eq!(received_packet.routing_header.hop_index, 3);
+
+    controller.stop().expect("Error stopping the network");
+}
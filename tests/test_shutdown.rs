@@ -0,0 +1,132 @@
+use dronegowski::{DronegowskiBuilder, NetworkInitializer, ShutdownReason, StopToken};
+use std::collections::HashMap;
+use std::time::Duration;
+use wg_2024::controller::{DroneCommand, DroneEvent};
+use wg_2024::network::SourceRoutingHeader;
+use wg_2024::packet::{Fragment, Nack, NackType, Packet, PacketType};
+const TIMER: Duration = Duration::from_secs(5);
+
+fn fragment_packet(fragment_index: u64) -> Packet {
+    Packet {
+        pack_type: PacketType::MsgFragment(Fragment {
+            fragment_index,
+            total_n_fragments: 2,
+            length: 128,
+            data: [1; 128],
+        }),
+        routing_header: SourceRoutingHeader {
+            hop_index: 1,
+            hops: vec![0, 1, 2],
+        },
+        session_id: 1,
+    }
+}
+
+#[test]
+fn stop_token_drains_in_flight_fragments() {
+    //Create channel
+    let (sim_controller_send, _sim_controller_receive) =
+        crossbeam_channel::unbounded::<DroneEvent>();
+    let (_controller_send, controller_receive) = crossbeam_channel::unbounded::<DroneCommand>();
+    let (packet_send, packet_receive) = crossbeam_channel::unbounded::<Packet>();
+
+    //Create channel for the neighbors
+    let (client_send, client_receive) = crossbeam_channel::unbounded::<Packet>();
+    let (neighbor_send, neighbor_receive) = crossbeam_channel::unbounded::<Packet>();
+
+    let stop = StopToken::new();
+    let mut my_drone = DronegowskiBuilder::new(
+        1,
+        sim_controller_send,
+        controller_receive,
+        packet_receive,
+        HashMap::from([(0, client_send), (2, neighbor_send)]),
+    )
+    .stop_token(stop.clone())
+    .build()
+    .expect("Valid drone");
+
+    //The fragments are still in the channel when the drone is stopped
+    packet_send
+        .send(fragment_packet(0))
+        .expect("Error sending the packet...");
+    packet_send
+        .send(fragment_packet(1))
+        .expect("Error sending the packet...");
+    stop.stop();
+    assert!(stop.is_stopped());
+
+    //packet_send is still alive, the drone must terminate anyway
+    let report = std::thread::spawn(move || my_drone.run_with_report())
+        .join()
+        .expect("The drone panicked");
+    assert_eq!(report.id, 1);
+    assert_eq!(report.reason, ShutdownReason::Stopped);
+    assert_eq!(report.drained, 2);
+    assert_eq!(report.stats.received.msg_fragment, 2);
+    assert_eq!(report.stats.nacks_generated.error_in_routing, 2);
+
+    for fragment_index in 0..2 {
+        let nack = client_receive
+            .recv_timeout(TIMER)
+            .expect("Timeout: No packet received.");
+        assert_eq!(
+            nack.pack_type,
+            PacketType::Nack(Nack {
+                fragment_index,
+                nack_type: NackType::ErrorInRouting(2),
+            })
+        );
+    }
+    assert!(neighbor_receive.try_recv().is_err());
+    drop(packet_send);
+}
+
+#[test]
+fn crash_timeout_ends_crashing() {
+    //Create channel
+    let (sim_controller_send, _sim_controller_receive) =
+        crossbeam_channel::unbounded::<DroneEvent>();
+    let (controller_send, controller_receive) = crossbeam_channel::unbounded::<DroneCommand>();
+    let (packet_send, packet_receive) = crossbeam_channel::unbounded::<Packet>();
+
+    let mut my_drone = DronegowskiBuilder::new(
+        1,
+        sim_controller_send,
+        controller_receive,
+        packet_receive,
+        HashMap::new(),
+    )
+    .crash_timeout(Duration::from_millis(50))
+    .build()
+    .expect("Valid drone");
+
+    let handle = std::thread::spawn(move || my_drone.run_with_report());
+    controller_send
+        .send(DroneCommand::Crash)
+        .expect("Error sending the command...");
+
+    //Without the timeout the drone would wait forever for packet_send to be dropped
+    let report = handle.join().expect("The drone panicked");
+    assert_eq!(report.reason, ShutdownReason::CrashTimeout);
+    assert_eq!(report.drained, 0);
+    drop(packet_send);
+}
+
+#[test]
+fn network_stop_returns_every_report() {
+    let network = NetworkInitializer::from_file("tests/common/config.toml")
+        .expect("Error loading the config")
+        .start()
+        .expect("Error starting the network");
+    assert!(network.stop_token(1).is_some());
+    assert!(network.stop_token(4).is_none());
+
+    let reports = network.stop().expect("Error stopping the network");
+    let mut drones: Vec<_> = reports.keys().copied().collect();
+    drones.sort();
+    assert_eq!(drones, vec![1, 2, 3]);
+    assert!(reports
+        .values()
+        .all(|report| report.reason == ShutdownReason::Stopped));
+}
@@ -0,0 +1,287 @@
+use dronegowski::{
+    NetworkInitializer, ShortcutError, SimulationController, SimulationControllerError,
+    ValidationError,
+};
+use std::time::{Duration, Instant};
+use wg_2024::controller::{DroneCommand, DroneEvent};
+use wg_2024::network::SourceRoutingHeader;
+use wg_2024::packet::{Ack, Packet, PacketType};
+const TIMER: Duration = Duration::from_secs(5);
+
+fn start_controller() -> SimulationController {
+    let network = NetworkInitializer::from_file("tests/common/config.toml")
+        .expect("Error loading the config")
+        .start()
+        .expect("Error starting the network");
+    SimulationController::new(network)
+}
+
+fn ack_packet(hops: Vec<u8>) -> Packet {
+    Packet {
+        pack_type: PacketType::Ack(Ack { fragment_index: 0 }),
+        routing_header: SourceRoutingHeader { hop_index: 1, hops },
+        session_id: 1,
+    }
+}
+
+// Stops a drone behind the back of the controller and waits until its command channel is closed
+fn disconnect_drone(controller: &SimulationController, drone_id: u8) {
+    let network = controller.network();
+    network
+        .stop_token(drone_id)
+        .expect("Unknown drone")
+        .stop();
+    let deadline = Instant::now() + TIMER;
+    while network.send_command(drone_id, DroneCommand::SetPacketDropRate(0.0)) {
+        assert!(Instant::now() < deadline, "Drone {drone_id} did not stop");
+        std::thread::sleep(Duration::from_millis(10));
+    }
+}
+
+#[test]
+fn controller_sets_pdr() {
+    let mut controller = start_controller();
+    assert_eq!(controller.pdr(1), Some(0.05));
+
+    controller.set_pdr(1, 0.5).expect("Error setting the PDR");
+    assert_eq!(controller.pdr(1), Some(0.5));
+
+    assert_eq!(
+        controller.set_pdr(1, 2.0),
+        Err(SimulationControllerError::PdrOutOfBounds(1, 2.0))
+    );
+    assert_eq!(
+        controller.set_pdr(4, 0.5),
+        Err(SimulationControllerError::NotADrone(4))
+    );
+    assert_eq!(
+        controller.set_pdr(9, 0.5),
+        Err(SimulationControllerError::UnknownNode(9))
+    );
+
+    controller.stop().expect("Error stopping the network");
+}
+
+#[test]
+fn controller_adds_link() {
+    let mut controller = start_controller();
+    assert_eq!(
+        controller.add_link(1, 2),
+        Err(SimulationControllerError::LinkAlreadyPresent(1, 2))
+    );
+    assert_eq!(
+        controller.add_link(4, 6),
+        Err(SimulationControllerError::NoDroneInLink(4, 6))
+    );
+
+    //Client 4 is already linked to two drones
+    assert_eq!(
+        controller.add_link(4, 1),
+        Err(SimulationControllerError::WouldBreakRule(
+            ValidationError::InvalidClientDegree(4, 3)
+        ))
+    );
+    assert_eq!(controller.neighbours(1), Some(vec![2, 3, 5]));
+
+    controller.add_link(1, 6).expect("Error adding the link");
+    assert_eq!(controller.neighbours(1), Some(vec![2, 3, 5, 6]));
+    assert_eq!(controller.neighbours(6), Some(vec![1, 2, 3]));
+
+    //Drone 1 now has the sender of server 6
+    controller.network().packet_send()[&1]
+        .send(ack_packet(vec![5, 1, 6]))
+        .expect("Error sending the packet...");
+    controller.network().packet_recv()[&6]
+        .recv_timeout(TIMER)
+        .expect("Timeout: No packet received.");
+
+    match controller.recv_event_timeout(TIMER) {
+        Some(DroneEvent::PacketSent(_)) => {}
+        other => panic!("Unexpected event {other:?}"),
+    }
+    assert_eq!(controller.traffic().packets_sent, 1);
+    assert_eq!(controller.traffic().sent_per_link.get(&(1, 6)), Some(&1));
+
+    controller.stop().expect("Error stopping the network");
+}
+
+#[test]
+fn controller_rolls_back_link_to_disconnected_drone() {
+    let mut controller = start_controller();
+    controller
+        .remove_link(2, 3)
+        .expect("Error removing the link");
+    disconnect_drone(&controller, 3);
+
+    assert_eq!(
+        controller.add_link(2, 3),
+        Err(SimulationControllerError::DroneDisconnected(3))
+    );
+    assert_eq!(controller.neighbours(2), Some(vec![1, 4, 6]));
+
+    //The sender of drone 3 was taken back from drone 2, the Ack goes to the Simulation Controller
+    controller.network().packet_send()[&2]
+        .send(ack_packet(vec![1, 2, 3]))
+        .expect("Error sending the packet...");
+    match controller.recv_event_timeout(TIMER) {
+        Some(DroneEvent::ControllerShortcut(_)) => {}
+        other => panic!("Unexpected event {other:?}"),
+    }
+
+    controller.stop().expect("Error stopping the network");
+}
+
+#[test]
+fn controller_removes_link() {
+    let mut controller = start_controller();
+    controller
+        .remove_link(2, 3)
+        .expect("Error removing the link");
+    assert_eq!(controller.neighbours(2), Some(vec![1, 4, 6]));
+    assert_eq!(
+        controller.remove_link(3, 2),
+        Err(SimulationControllerError::LinkNotPresent(3, 2))
+    );
+
+    //Drone 2 can no longer reach drone 3, the Ack goes to the Simulation Controller
+    controller.network().packet_send()[&2]
+        .send(ack_packet(vec![1, 2, 3]))
+        .expect("Error sending the packet...");
+    match controller.recv_event_timeout(TIMER) {
+        Some(DroneEvent::ControllerShortcut(_)) => {}
+        other => panic!("Unexpected event {other:?}"),
+    }
+    assert_eq!(controller.traffic().controller_shortcuts, 1);
+
+    //Drone 3 is not a client or a server, the shortcut cannot be delivered
+    let undelivered = controller.take_undelivered();
+    assert_eq!(undelivered.len(), 1);
+    assert_eq!(undelivered[0].1, ShortcutError::UnknownDestination(3));
+
+    controller.stop().expect("Error stopping the network");
+}
+
+#[test]
+fn controller_crashes_drone() {
+    let mut controller = start_controller();
+    controller.crash(3).expect("Error crashing the drone");
+
+    assert!(controller.is_crashed(3));
+    assert_eq!(controller.neighbours(3), Some(vec![]));
+    assert_eq!(controller.neighbours(2), Some(vec![1, 4, 6]));
+    assert_eq!(controller.neighbours(4), Some(vec![2]));
+    assert!(!controller.network().packet_send().contains_key(&3));
+
+    assert_eq!(
+        controller.crash(3),
+        Err(SimulationControllerError::AlreadyCrashed(3))
+    );
+    assert_eq!(
+        controller.add_link(3, 1),
+        Err(SimulationControllerError::AlreadyCrashed(3))
+    );
+
+    let reports = controller
+        .shutdown()
+        .expect("Error shutting down the network");
+    assert_eq!(reports.len(), 3);
+}
+
+#[test]
+fn controller_completes_crash_with_disconnected_neighbour() {
+    let mut controller = start_controller();
+    disconnect_drone(&controller, 2);
+
+    //Drone 1 is still told, and drone 3 is crashed even if drone 2 cannot be told
+    assert_eq!(
+        controller.crash(3),
+        Err(SimulationControllerError::DroneDisconnected(2))
+    );
+    assert!(controller.is_crashed(3));
+    assert_eq!(controller.neighbours(1), Some(vec![2, 5]));
+    assert_eq!(controller.neighbours(2), Some(vec![1, 4, 6]));
+    assert!(!controller.network().packet_send().contains_key(&3));
+    assert_eq!(
+        controller.crash(3),
+        Err(SimulationControllerError::AlreadyCrashed(3))
+    );
+
+    controller.stop().expect("Error stopping the network");
+}
+
+#[test]
+fn controller_refuses_to_cut_off_nodes() {
+    let mut controller = start_controller();
+
+    //Client 5 is only linked to drone 1
+    assert_eq!(
+        controller.crash(1),
+        Err(SimulationControllerError::WouldCutOff(vec![5]))
+    );
+    assert_eq!(
+        controller.remove_link(5, 1),
+        Err(SimulationControllerError::WouldCutOff(vec![5]))
+    );
+    assert!(!controller.is_crashed(1));
+    assert_eq!(controller.neighbours(5), Some(vec![1]));
+
+    //Server 6 would lose its last drone link, so no client could reach it
+    controller.remove_link(2, 6).expect("Error removing the link");
+    assert_eq!(
+        controller.remove_link(3, 6),
+        Err(SimulationControllerError::WouldCutOff(vec![4, 5, 6]))
+    );
+    assert_eq!(
+        controller.crash(3),
+        Err(SimulationControllerError::WouldCutOff(vec![4, 5, 6]))
+    );
+    assert_eq!(controller.neighbours(6), Some(vec![3]));
+
+    controller.stop().expect("Error stopping the network");
+}
+
+#[test]
+fn controller_changes_network_without_clients() {
+    let config = toml::from_str(
+        r#"
+        client = []
+
+        [[drone]]
+        id = 1
+        connected_node_ids = [2, 3, 6]
+        pdr = 0.0
+
+        [[drone]]
+        id = 2
+        connected_node_ids = [1, 3, 6]
+        pdr = 0.0
+
+        [[drone]]
+        id = 3
+        connected_node_ids = [1, 2]
+        pdr = 0.0
+
+        [[server]]
+        id = 6
+        connected_drone_ids = [1, 2]
+        "#,
+    )
+    .expect("Error occurred while parsing the config");
+    let mut controller = SimulationController::new(
+        NetworkInitializer::new(config)
+            .start()
+            .expect("Error starting the network"),
+    );
+
+    //Without clients server 6 is only cut off once it loses its last drone link
+    assert!(controller.topology().cut_off_nodes().is_empty());
+    controller.remove_link(1, 6).expect("Error removing the link");
+    controller.crash(3).expect("Error crashing the drone");
+    assert_eq!(
+        controller.crash(2),
+        Err(SimulationControllerError::WouldCutOff(vec![6]))
+    );
+    assert_eq!(controller.neighbours(6), Some(vec![2]));
+
+    controller.stop().expect("Error stopping the network");
+}
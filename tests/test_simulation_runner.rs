@@ -0,0 +1,103 @@
+use dronegowski::{parse_config, ControllerCommand, SimulationControllerError, SimulationRunner};
+use std::path::PathBuf;
+use std::process::Command;
+use std::time::Duration;
+const SIMULATOR: &str = env!("CARGO_BIN_EXE_dronegowski-sim");
+
+fn runner() -> SimulationRunner {
+    let config = parse_config("tests/common/config.toml").expect("Error loading the config");
+    SimulationRunner::new(config)
+        .with_seed(7)
+        .with_duration(Duration::from_millis(300))
+        .with_message_interval(Duration::from_millis(5))
+}
+
+fn write_config(name: &str, contents: &str) -> PathBuf {
+    let dir = std::env::temp_dir().join(format!("dronegowski-sim-{}", std::process::id()));
+    std::fs::create_dir_all(&dir).expect("Error creating the config directory");
+    let path = dir.join(name);
+    std::fs::write(&path, contents).expect("Error writing the config");
+    path
+}
+
+#[test]
+fn every_fragment_is_delivered_or_nacked() {
+    let summary = runner().run().expect("Error running the simulation");
+    assert!(summary.fragments_sent > 0);
+    assert_eq!(summary.unroutable, 0);
+    assert_eq!(
+        summary.fragments_delivered + summary.nacks.dropped,
+        summary.fragments_sent
+    );
+    assert_eq!(
+        summary.dropped_per_drone.values().sum::<u64>(),
+        summary.nacks.dropped
+    );
+    assert_eq!(summary.nacks.total(), summary.nacks.dropped);
+    assert_eq!(summary.controller_shortcuts, 0);
+    assert!((0.0..=1.0).contains(&summary.delivery_ratio()));
+}
+
+#[test]
+fn faults_are_applied_or_refused() {
+    let summary = runner()
+        .with_fault(Duration::from_millis(100), ControllerCommand::Crash(3))
+        .with_fault(Duration::from_millis(150), ControllerCommand::Crash(1))
+        .run()
+        .expect("Error running the simulation");
+    assert_eq!(summary.faults_applied, vec![ControllerCommand::Crash(3)]);
+    assert_eq!(
+        summary.faults_refused,
+        vec![(
+            ControllerCommand::Crash(1),
+            SimulationControllerError::WouldCutOff(vec![5])
+        )]
+    );
+    assert_eq!(summary.unroutable, 0);
+    assert!(summary.fragments_delivered > 0);
+}
+
+#[test]
+fn binary_prints_summary() {
+    let output = Command::new(SIMULATOR)
+        .args([
+            "tests/common/config.toml",
+            "--duration",
+            "0.3",
+            "--seed",
+            "7",
+        ])
+        .output()
+        .expect("Error running the simulator");
+    assert_eq!(output.status.code(), Some(0));
+    let stdout = String::from_utf8_lossy(&output.stdout);
+    assert!(stdout.contains("Delivery ratio: "));
+    assert!(stdout.contains("Drops per drone:"));
+    assert!(stdout.contains("Nacks: "));
+    assert!(stdout.contains("Controller shortcuts: "));
+    assert!(stdout.contains("Faults applied: [Crash(2)]"));
+}
+
+#[test]
+fn binary_exit_codes() {
+    let status = |args: &[&str]| {
+        Command::new(SIMULATOR)
+            .args(args)
+            .output()
+            .expect("Error running the simulator")
+            .status
+            .code()
+    };
+    let invalid = write_config(
+        "invalid.toml",
+        "[[drone]]\nid = 1\nconnected_node_ids = [1]\npdr = 0.5\n",
+    );
+    let invalid = invalid.to_str().expect("The temp dir is not UTF-8");
+    let missing = "tests/common/missing.toml";
+
+    assert_eq!(status(&[invalid, "--duration", "0.1"]), Some(2));
+    assert_eq!(status(&[missing]), Some(2));
+    assert_eq!(status(&["--duration"]), Some(3));
+    assert_eq!(status(&["--crash", "1"]), Some(3));
+    assert_eq!(status(&["--help"]), Some(0));
+}
@@ -0,0 +1,111 @@
+use dronegowski::{DroneSnapshot, DroneSnapshotHandle, DroneState, Dronegowski};
+use std::collections::HashMap;
+use std::time::{Duration, Instant};
+use wg_2024::controller::{DroneCommand, DroneEvent};
+use wg_2024::drone::Drone;
+use wg_2024::network::SourceRoutingHeader;
+use wg_2024::packet::{FloodRequest, NodeType, Packet, PacketType};
+const TIMER: Duration = Duration::from_secs(5);
+
+//Polls the snapshot until the condition holds or the timer expires
+fn wait_for<F: Fn(&DroneSnapshot) -> bool>(
+    handle: &DroneSnapshotHandle,
+    condition: F,
+) -> DroneSnapshot {
+    let start = Instant::now();
+    loop {
+        let snapshot = handle.snapshot();
+        if condition(&snapshot) {
+            return snapshot;
+        }
+        assert!(
+            start.elapsed() < TIMER,
+            "Timeout: last snapshot {snapshot:?}"
+        );
+        std::thread::sleep(Duration::from_millis(5));
+    }
+}
+
+#[test]
+fn accessors_do_not_consume_the_drone() {
+    let (sim_controller_send, _) = crossbeam_channel::unbounded::<DroneEvent>();
+    let (_send_controller, controller_receive) = crossbeam_channel::unbounded::<DroneCommand>();
+    let (_packet_send, packet_receive) = crossbeam_channel::unbounded::<Packet>();
+    let (neighbor_send, _neighbor_receive) = crossbeam_channel::unbounded::<Packet>();
+
+    let drone = Dronegowski::new(
+        1,
+        sim_controller_send,
+        controller_receive,
+        packet_receive,
+        HashMap::from([(2, neighbor_send)]),
+        0.25,
+    );
+
+    assert_eq!(drone.get_id(), 1);
+    assert_eq!(drone.get_pdr(), 0.25);
+    assert_eq!(drone.get_state(), DroneState::Active);
+    assert_eq!(drone.get_packet_send().len(), 1);
+    assert_eq!(
+        drone.snapshot(),
+        DroneSnapshot {
+            id: 1,
+            pdr: 0.25,
+            state: DroneState::Active,
+            neighbours: vec![2],
+            flood_cache_len: 0,
+        }
+    );
+}
+
+#[test]
+fn snapshot_follows_running_drone() {
+    let (sim_controller_send, _) = crossbeam_channel::unbounded::<DroneEvent>();
+    let (controller_send, controller_receive) = crossbeam_channel::unbounded::<DroneCommand>();
+    let (packet_send, packet_receive) = crossbeam_channel::unbounded::<Packet>();
+    let (neighbor_send, _neighbor_receive) = crossbeam_channel::unbounded::<Packet>();
+
+    let mut my_drone = Dronegowski::new(
+        1,
+        sim_controller_send,
+        controller_receive,
+        packet_receive,
+        HashMap::new(),
+        0.1,
+    );
+    let handle = my_drone.snapshot_handle();
+
+    std::thread::spawn(move || {
+        my_drone.run();
+    });
+
+    controller_send
+        .send(DroneCommand::AddSender(2, neighbor_send))
+        .expect("Error sending the command...");
+    controller_send
+        .send(DroneCommand::SetPacketDropRate(0.5))
+        .expect("Error sending the command...");
+    let snapshot = wait_for(&handle, |snapshot| snapshot.pdr == 0.5);
+    assert_eq!(snapshot.neighbours, vec![2]);
+
+    packet_send
+        .send(Packet {
+            pack_type: PacketType::FloodRequest(FloodRequest {
+                flood_id: 1,
+                initiator_id: 0,
+                path_trace: vec![(0, NodeType::Client)],
+            }),
+            routing_header: SourceRoutingHeader {
+                hop_index: 0,
+                hops: vec![],
+            },
+            session_id: 1,
+        })
+        .expect("Error sending the flood request...");
+    wait_for(&handle, |snapshot| snapshot.flood_cache_len == 1);
+
+    controller_send
+        .send(DroneCommand::Crash)
+        .expect("Error sending the command...");
+    wait_for(&handle, |snapshot| snapshot.state == DroneState::Crashing);
+}
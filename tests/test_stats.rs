@@ -0,0 +1,187 @@
+use dronegowski::{DroneStats, Dronegowski};
+use std::collections::HashMap;
+use std::time::Duration;
+use wg_2024::controller::{DroneCommand, DroneEvent};
+use wg_2024::drone::Drone;
+use wg_2024::network::SourceRoutingHeader;
+use wg_2024::packet::{Ack, FloodRequest, Fragment, NodeType, Packet, PacketType};
+const TIMER: Duration = Duration::from_secs(5);
+
+fn fragment_packet() -> Packet {
+    Packet {
+        pack_type: PacketType::MsgFragment(Fragment {
+            fragment_index: 10,
+            total_n_fragments: 15,
+            length: 5,
+            data: [5; 128],
+        }),
+        routing_header: SourceRoutingHeader {
+            hop_index: 1,
+            hops: vec![0, 1, 2], //Path: Drone 1 -> Drone 2
+        },
+        session_id: 1,
+    }
+}
+
+#[test]
+fn stats_count_forwarded_fragments() {
+    //Create channel
+    let (sim_controller_send, sim_controller_receive) = crossbeam_channel::unbounded::<DroneEvent>();
+    let (_send_controller, controller_receive) = crossbeam_channel::unbounded::<DroneCommand>();
+    let (packet_send, packet_receive) = crossbeam_channel::unbounded::<Packet>();
+
+    //Create channel for the neighbor
+    let (neighbor_send, _neighbor_receive) = crossbeam_channel::unbounded::<Packet>();
+    let mut senders = HashMap::new();
+    senders.insert(2, neighbor_send);
+
+    let mut my_drone = Dronegowski::new(
+        1,
+        sim_controller_send,
+        controller_receive,
+        packet_receive,
+        senders,
+        0.0,
+    );
+    let stats = my_drone.stats_handle();
+
+    std::thread::spawn(move || {
+        my_drone.run();
+    });
+
+    packet_send
+        .send(fragment_packet())
+        .expect("Error sending the fragment...");
+    match sim_controller_receive.recv_timeout(TIMER) {
+        Ok(DroneEvent::PacketSent(_)) => {}
+        other => panic!("Unexpected event {other:?}"),
+    }
+
+    let snapshot = stats.snapshot();
+    assert_eq!(snapshot.received.msg_fragment, 1);
+    assert_eq!(snapshot.fragments_forwarded, 1);
+    assert_eq!(snapshot.fragments_dropped, 0);
+    assert_eq!(snapshot.nacks_generated.total(), 0);
+}
+
+#[test]
+fn stats_count_dropped_fragments_and_shortcuts() {
+    //Create channel
+    let (sim_controller_send, sim_controller_receive) = crossbeam_channel::unbounded::<DroneEvent>();
+    let (_send_controller, controller_receive) = crossbeam_channel::unbounded::<DroneCommand>();
+    let (packet_send, packet_receive) = crossbeam_channel::unbounded::<Packet>();
+
+    //Create channel for the neighbor
+    let (neighbor_send, neighbor_receive) = crossbeam_channel::unbounded::<Packet>();
+    let mut senders = HashMap::new();
+    senders.insert(0, neighbor_send);
+
+    let mut my_drone = Dronegowski::new(
+        1,
+        sim_controller_send,
+        controller_receive,
+        packet_receive,
+        senders,
+        1.0, //Every fragment is dropped
+    );
+    let stats = my_drone.stats_handle();
+
+    std::thread::spawn(move || {
+        my_drone.run();
+    });
+
+    packet_send
+        .send(fragment_packet())
+        .expect("Error sending the fragment...");
+    neighbor_receive
+        .recv_timeout(TIMER)
+        .expect("Timeout: No Nack received.");
+
+    //An Ack to a node which is not a neighbour goes to the Simulation Controller
+    packet_send
+        .send(Packet {
+            pack_type: PacketType::Ack(Ack { fragment_index: 0 }),
+            routing_header: SourceRoutingHeader {
+                hop_index: 1,
+                hops: vec![0, 1, 3],
+            },
+            session_id: 1,
+        })
+        .expect("Error sending the ack...");
+    loop {
+        match sim_controller_receive.recv_timeout(TIMER) {
+            Ok(DroneEvent::ControllerShortcut(_)) => break,
+            Ok(_) => {}
+            Err(_) => panic!("Timeout: No shortcut received."),
+        }
+    }
+
+    let snapshot = stats.snapshot();
+    assert_eq!(snapshot.received.msg_fragment, 1);
+    assert_eq!(snapshot.received.ack, 1);
+    assert_eq!(snapshot.fragments_dropped, 1);
+    assert_eq!(snapshot.nacks_generated.dropped, 1);
+    assert_eq!(snapshot.controller_shortcuts, 1);
+}
+
+#[test]
+fn stats_count_flood_requests() {
+    //Create channel
+    let (sim_controller_send, _) = crossbeam_channel::unbounded::<DroneEvent>();
+    let (_send_controller, controller_receive) = crossbeam_channel::unbounded::<DroneCommand>();
+    let (packet_send, packet_receive) = crossbeam_channel::unbounded::<Packet>();
+
+    //Create channel for the neighbors
+    let (client_send, client_receive) = crossbeam_channel::unbounded::<Packet>();
+    let (neighbor_send, neighbor_receive) = crossbeam_channel::unbounded::<Packet>();
+    let mut senders = HashMap::new();
+    senders.insert(0, client_send);
+    senders.insert(2, neighbor_send);
+
+    let mut my_drone = Dronegowski::new(
+        1,
+        sim_controller_send,
+        controller_receive,
+        packet_receive,
+        senders,
+        0.0,
+    );
+    let stats = my_drone.stats_handle();
+
+    std::thread::spawn(move || {
+        my_drone.run();
+    });
+
+    let flood_request = Packet {
+        pack_type: PacketType::FloodRequest(FloodRequest {
+            flood_id: 123,
+            initiator_id: 0,
+            path_trace: vec![(0, NodeType::Client)],
+        }),
+        routing_header: SourceRoutingHeader {
+            hop_index: 0,
+            hops: vec![],
+        },
+        session_id: 1,
+    };
+
+    //The first one is forwarded, the second one is answered
+    packet_send
+        .send(flood_request.clone())
+        .expect("Error sending the flood request...");
+    neighbor_receive
+        .recv_timeout(TIMER)
+        .expect("Timeout: No Flood Request received.");
+    packet_send
+        .send(flood_request)
+        .expect("Error sending the flood request...");
+    client_receive
+        .recv_timeout(TIMER)
+        .expect("Timeout: No Flood Response received.");
+
+    let snapshot = stats.snapshot();
+    assert_eq!(snapshot.received.flood_request, 2);
+    assert_eq!(snapshot.flood_requests_forwarded, 1);
+    assert_eq!(snapshot.flood_requests_answered, 1);
+    assert_ne!(snapshot, DroneStats::default());
+}
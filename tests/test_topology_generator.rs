@@ -0,0 +1,200 @@
+use dronegowski::{
+    validate_config, NetworkGraph, NetworkInitializer, PdrDistribution, TopologyGenerator,
+    TopologyGeneratorError, TopologyShape,
+};
+use wg_2024::packet::NodeType;
+
+fn drone_links(shape: TopologyShape) -> usize {
+    let config = TopologyGenerator::new(shape)
+        .clients(0)
+        .servers(0)
+        .generate()
+        .expect("Error generating the topology");
+    NetworkGraph::from_config(&config).edges().len()
+}
+
+#[test]
+fn shapes_have_expected_links() {
+    assert_eq!(drone_links(TopologyShape::Ring { drones: 10 }), 10);
+    assert_eq!(drone_links(TopologyShape::Star { drones: 10 }), 9);
+    assert_eq!(
+        drone_links(TopologyShape::Grid {
+            rows: 3,
+            columns: 4
+        }),
+        17
+    );
+    assert_eq!(
+        drone_links(TopologyShape::Tree {
+            depth: 3,
+            branching: 2
+        }),
+        14
+    );
+    assert_eq!(drone_links(TopologyShape::DoubleChain { length: 5 }), 13);
+    assert_eq!(drone_links(TopologyShape::Butterfly { dimension: 3 }), 48);
+    assert_eq!(
+        drone_links(TopologyShape::Random {
+            drones: 20,
+            average_degree: 4.0
+        }),
+        40
+    );
+    assert_eq!(TopologyShape::Butterfly { dimension: 3 }.drone_count(), 32);
+    assert_eq!(
+        TopologyShape::Tree {
+            depth: 3,
+            branching: 2
+        }
+        .drone_count(),
+        15
+    );
+}
+
+#[test]
+fn generated_configs_are_valid() {
+    let shapes = [
+        TopologyShape::Ring { drones: 8 },
+        TopologyShape::Star { drones: 8 },
+        TopologyShape::Grid {
+            rows: 4,
+            columns: 4,
+        },
+        TopologyShape::Tree {
+            depth: 2,
+            branching: 3,
+        },
+        TopologyShape::DoubleChain { length: 6 },
+        TopologyShape::Butterfly { dimension: 2 },
+        TopologyShape::Random {
+            drones: 30,
+            average_degree: 3.0,
+        },
+    ];
+    for shape in shapes {
+        for seed in 0..5 {
+            let config = TopologyGenerator::new(shape)
+                .clients(3)
+                .servers(2)
+                .pdr(PdrDistribution::Uniform { min: 0.0, max: 0.3 })
+                .seed(seed)
+                .generate()
+                .expect("Error generating the topology");
+            assert_eq!(validate_config(&config), Ok(()), "{shape:?}");
+            assert_eq!(config.drone.len(), shape.drone_count());
+            assert!(config
+                .client
+                .iter()
+                .all(|client| (1..=2).contains(&client.connected_drone_ids.len())));
+            assert!(config
+                .server
+                .iter()
+                .all(|server| (2..=3).contains(&server.connected_drone_ids.len())));
+
+            let graph = NetworkGraph::from_config(&config);
+            assert_eq!(graph.nodes_of_type(NodeType::Client).len(), 3);
+            assert_eq!(graph.nodes_of_type(NodeType::Server).len(), 2);
+            assert!(graph.cut_off_nodes().is_empty(), "{shape:?}");
+        }
+    }
+}
+
+#[test]
+fn generation_is_seeded() {
+    let generator = |seed| {
+        TopologyGenerator::new(TopologyShape::Random {
+            drones: 25,
+            average_degree: 3.5,
+        })
+        .clients(4)
+        .servers(3)
+        .pdr(PdrDistribution::Normal {
+            mean: 0.2,
+            std_dev: 0.1,
+        })
+        .seed(seed)
+        .generate()
+        .expect("Error generating the topology")
+    };
+    assert_eq!(format!("{:?}", generator(1)), format!("{:?}", generator(1)));
+    assert_ne!(format!("{:?}", generator(1)), format!("{:?}", generator(2)));
+}
+
+#[test]
+fn pdr_follows_distribution() {
+    let pdrs = |pdr| {
+        TopologyGenerator::new(TopologyShape::Ring { drones: 100 })
+            .pdr(pdr)
+            .seed(3)
+            .generate()
+            .expect("Error generating the topology")
+            .drone
+            .iter()
+            .map(|drone| drone.pdr)
+            .collect::<Vec<f32>>()
+    };
+    assert!(pdrs(PdrDistribution::Constant(0.25))
+        .iter()
+        .all(|pdr| *pdr == 0.25));
+    let uniform = pdrs(PdrDistribution::Uniform { min: 0.1, max: 0.2 });
+    assert!(uniform.iter().all(|pdr| (0.1..=0.2).contains(pdr)));
+    assert!(uniform.windows(2).any(|pair| pair[0] != pair[1]));
+    let normal = pdrs(PdrDistribution::Normal {
+        mean: 0.05,
+        std_dev: 0.2,
+    });
+    assert!(normal.iter().all(|pdr| (0.0..=1.0).contains(pdr)));
+    assert!(normal.contains(&0.0), "Low samples are clamped to 0");
+}
+
+#[test]
+fn impossible_topologies_are_refused() {
+    assert_eq!(
+        TopologyGenerator::new(TopologyShape::Ring { drones: 250 })
+            .clients(5)
+            .servers(1)
+            .generate()
+            .err(),
+        Some(TopologyGeneratorError::TooManyNodes(256))
+    );
+    assert_eq!(
+        TopologyGenerator::new(TopologyShape::Ring { drones: 0 })
+            .generate()
+            .err(),
+        Some(TopologyGeneratorError::NoDrones)
+    );
+    assert_eq!(
+        TopologyGenerator::new(TopologyShape::Star { drones: 1 })
+            .generate()
+            .err(),
+        Some(TopologyGeneratorError::NotEnoughDronesForServers)
+    );
+    assert!(matches!(
+        TopologyGenerator::new(TopologyShape::Ring { drones: 3 })
+            .pdr(PdrDistribution::Uniform { min: 0.5, max: 0.2 })
+            .generate(),
+        Err(TopologyGeneratorError::InvalidPdr(_))
+    ));
+}
+
+#[test]
+fn large_generated_network_boots() {
+    let config = TopologyGenerator::new(TopologyShape::Random {
+        drones: 200,
+        average_degree: 4.0,
+    })
+    .clients(30)
+    .servers(20)
+    .pdr(PdrDistribution::Uniform { min: 0.0, max: 0.5 })
+    .seed(42)
+    .generate()
+    .expect("Error generating the topology");
+    assert_eq!(config.drone.len(), 200);
+
+    let network = NetworkInitializer::new(config)
+        .start()
+        .expect("Error starting the network");
+    assert_eq!(network.drone_ids().len(), 200);
+    let reports = network.stop().expect("Error stopping the network");
+    assert_eq!(reports.len(), 200);
+}
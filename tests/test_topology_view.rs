@@ -0,0 +1,118 @@
+use dronegowski::{
+    drone_colour, force_directed_layout, parse_config, DroneState, NetworkInitializer,
+    SimulationController, TopologyView,
+};
+use std::time::Duration;
+use wg_2024::controller::DroneEvent;
+use wg_2024::network::SourceRoutingHeader;
+use wg_2024::packet::{Ack, Packet, PacketType};
+const TIMER: Duration = Duration::from_secs(5);
+
+fn config_view() -> TopologyView {
+    let config = parse_config("tests/common/config.toml").expect("Error loading the config");
+    TopologyView::from_config(&config)
+}
+
+fn ack_packet(hop_index: usize, hops: Vec<u8>) -> Packet {
+    Packet {
+        pack_type: PacketType::Ack(Ack { fragment_index: 0 }),
+        routing_header: SourceRoutingHeader { hop_index, hops },
+        session_id: 1,
+    }
+}
+
+#[test]
+fn dot_has_shapes_and_traffic() {
+    let mut view = config_view();
+    view.record_event(&DroneEvent::PacketSent(ack_packet(2, vec![4, 3, 6])));
+    view.record_event(&DroneEvent::PacketSent(ack_packet(2, vec![4, 3, 6])));
+    view.record_event(&DroneEvent::PacketSent(ack_packet(1, vec![4, 3, 6])));
+    view.record_event(&DroneEvent::PacketDropped(ack_packet(1, vec![4, 2, 6])));
+    assert_eq!(view.packets_on_link(3, 6), 2);
+    assert_eq!(view.packets_on_link(6, 3), 2);
+    assert_eq!(view.packets_on_link(3, 4), 1);
+    assert_eq!(view.packets_on_link(2, 6), 0);
+
+    let dot = view.to_dot();
+    assert!(dot.starts_with("graph network {"));
+    assert!(dot.contains("1 [label=\"1\\npdr 0.05\", shape=ellipse"));
+    assert!(dot.contains("4 [label=\"4\", shape=box"));
+    assert!(dot.contains("6 [label=\"6\", shape=cylinder"));
+    assert!(dot.contains("3 -- 6 [label=\"2\", tooltip=\"3 -> 6: 2, 6 -> 3: 0\""));
+    assert!(dot.contains("3 -- 4 [label=\"1\", tooltip=\"3 -> 4: 0, 4 -> 3: 1\""));
+    assert!(dot.contains("2 -- 6;\n"));
+    assert_eq!(dot.matches(" -- ").count(), 8);
+}
+
+#[test]
+fn drones_coloured_by_state_and_pdr() {
+    assert_eq!(drone_colour(&DroneState::Active, 0.0), "#2ecc71");
+    assert_eq!(drone_colour(&DroneState::Active, 1.0), "#e74c3c");
+    assert_eq!(drone_colour(&DroneState::Crashing, 0.0), "#f39c12");
+    assert_eq!(drone_colour(&DroneState::Crashed, 0.0), "#7f8c8d");
+
+    let mut view = config_view();
+    view.set_state(2, DroneState::Crashed);
+    let dot = view.to_dot();
+    assert!(dot.contains("fillcolor=\"#7f8c8d\", style=\"filled,dashed\"];"));
+    assert!(dot.contains(&format!(
+        "fillcolor=\"{}\"];",
+        drone_colour(&DroneState::Active, 0.05)
+    )));
+}
+
+#[test]
+fn svg_draws_every_node_and_link() {
+    let svg = config_view().to_svg();
+    assert!(svg.starts_with("<svg xmlns=\"http://www.w3.org/2000/svg\""));
+    assert!(svg.ends_with("</svg>\n"));
+    assert_eq!(svg.matches("<circle").count(), 3);
+    assert_eq!(svg.matches("<rect x=").count(), 2);
+    assert_eq!(svg.matches("<polygon").count(), 1);
+    assert_eq!(svg.matches("<line").count(), 8);
+}
+
+#[test]
+fn layout_is_deterministic_and_bounded() {
+    let view = config_view();
+    let layout = force_directed_layout(view.graph(), 400.0, 300.0, 100);
+    assert_eq!(layout.len(), 6);
+    assert_eq!(
+        layout,
+        force_directed_layout(view.graph(), 400.0, 300.0, 100)
+    );
+    for (x, y) in layout.values() {
+        assert!((0.0..=400.0).contains(x) && (0.0..=300.0).contains(y));
+    }
+    let positions: Vec<&(f64, f64)> = layout.values().collect();
+    for (index, a) in positions.iter().enumerate() {
+        for b in &positions[index + 1..] {
+            assert!((a.0 - b.0).hypot(a.1 - b.1) > 10.0, "Nodes overlap");
+        }
+    }
+}
+
+#[test]
+fn view_of_live_controller() {
+    let network = NetworkInitializer::from_file("tests/common/config.toml")
+        .expect("Error loading the config")
+        .start()
+        .expect("Error starting the network");
+    let mut controller = SimulationController::new(network);
+    controller.network().packet_send()[&3]
+        .send(ack_packet(1, vec![4, 3, 6]))
+        .expect("Error sending the packet...");
+    controller
+        .recv_event_timeout(TIMER)
+        .expect("Timeout: No event received.");
+    controller.crash(3).expect("Error crashing the drone");
+    controller.set_pdr(2, 1.0).expect("Error setting the PDR");
+
+    let view = TopologyView::from_controller(&controller);
+    assert_eq!(view.packets_on_link(3, 6), 1);
+    assert_eq!(view.graph().neighbours(3), Some(vec![]));
+    let dot = view.to_dot();
+    assert!(dot.contains("3 [label=\"3\\npdr 0.14\", shape=ellipse, fillcolor=\"#7f8c8d\""));
+    assert!(dot.contains("2 [label=\"2\\npdr 1.00\", shape=ellipse, fillcolor=\"#e74c3c\""));
+    controller.stop().expect("Error stopping the network");
+}
@@ -0,0 +1,110 @@
+use dronegowski::{parse_config, validate_config, ValidationError};
+use wg_2024::config::Config;
+
+fn config_from_str(config: &str) -> Config {
+    toml::from_str(config).expect("Error occurred while parsing the config")
+}
+
+#[test]
+fn valid_config() {
+    let config = parse_config("tests/common/config.toml").expect("Error loading the config");
+    assert_eq!(validate_config(&config), Ok(()));
+}
+
+#[test]
+fn every_violation_is_reported() {
+    let config = config_from_str(
+        r#"
+        [[drone]]
+        id = 1
+        connected_node_ids = [1, 2, 4]
+        pdr = 1.5
+
+        [[drone]]
+        id = 2
+        connected_node_ids = [1, 9]
+        pdr = 0.1
+
+        [[client]]
+        id = 4
+        connected_drone_ids = [1, 5]
+
+        [[server]]
+        id = 5
+        connected_drone_ids = [2]
+        "#,
+    );
+
+    let errors = validate_config(&config).unwrap_err();
+    assert!(errors.contains(&ValidationError::SelfNeighbour(1)));
+    assert!(errors.contains(&ValidationError::PdrOutOfBounds(1, 1.5)));
+    assert!(errors.contains(&ValidationError::InvalidServerDegree(5, 1)));
+    assert!(errors.contains(&ValidationError::NotConnectedToDrone(4, 5)));
+    assert!(errors.contains(&ValidationError::UnknownNeighbour(2, 9)));
+    assert!(errors.contains(&ValidationError::NotBidirectional(5, 2)));
+}
+
+#[test]
+fn duplicate_ids_and_client_degree() {
+    let config = config_from_str(
+        r#"
+        [[drone]]
+        id = 1
+        connected_node_ids = [2, 3, 4]
+        pdr = 0.0
+
+        [[drone]]
+        id = 2
+        connected_node_ids = [1, 3, 4]
+        pdr = 0.0
+
+        [[drone]]
+        id = 3
+        connected_node_ids = [1, 2, 4]
+        pdr = 0.0
+
+        [[client]]
+        id = 4
+        connected_drone_ids = [1, 2, 3]
+
+        [[server]]
+        id = 1
+        connected_drone_ids = [2, 3]
+        "#,
+    );
+
+    let errors = validate_config(&config).unwrap_err();
+    assert!(errors.contains(&ValidationError::DuplicateNodeId(1)));
+    assert!(errors.contains(&ValidationError::InvalidClientDegree(4, 3)));
+}
+
+#[test]
+fn drones_disconnected_without_edge_nodes() {
+    //Drones 1 and 2 are only connected through the client
+    let config = config_from_str(
+        r#"
+        [[drone]]
+        id = 1
+        connected_node_ids = [3, 4]
+        pdr = 0.0
+
+        [[drone]]
+        id = 2
+        connected_node_ids = [3, 4]
+        pdr = 0.0
+
+        [[client]]
+        id = 3
+        connected_drone_ids = [1, 2]
+
+        [[server]]
+        id = 4
+        connected_drone_ids = [1, 2]
+        "#,
+    );
+
+    assert_eq!(
+        validate_config(&config),
+        Err(vec![ValidationError::NotConnected(vec![2])])
+    );
+}